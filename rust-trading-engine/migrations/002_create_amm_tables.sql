@@ -0,0 +1,14 @@
+-- Add liquidity mode to markets (0 = order book, 1 = AMM, 2 = hybrid)
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS liquidity_mode INTEGER NOT NULL DEFAULT 0;
+
+-- Create amm_states table
+CREATE TABLE IF NOT EXISTS amm_states (
+    market_id TEXT PRIMARY KEY REFERENCES markets(id),
+    house_account_id TEXT NOT NULL,
+    liquidity DECIMAL NOT NULL,
+    subsidy DECIMAL NOT NULL,
+    quantities DECIMAL[] NOT NULL,
+    collected DECIMAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Add named outcomes to markets; existing markets are binary Yes/No
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS outcomes TEXT[] NOT NULL DEFAULT ARRAY['Yes', 'No'];
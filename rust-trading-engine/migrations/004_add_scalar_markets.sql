@@ -0,0 +1,5 @@
+-- Add scalar markets, which pay out on where a value lands between two bounds
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS market_type INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS lower_bound DECIMAL;
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS upper_bound DECIMAL;
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS resolved_value DECIMAL;
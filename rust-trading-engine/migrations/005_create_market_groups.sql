@@ -0,0 +1,14 @@
+-- Create market_groups table for mutually exclusive and exhaustive markets
+CREATE TABLE IF NOT EXISTS market_groups (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    house_account_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Link markets to their group
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS group_id TEXT REFERENCES market_groups(id);
+
+CREATE INDEX IF NOT EXISTS idx_markets_group_id ON markets(group_id);
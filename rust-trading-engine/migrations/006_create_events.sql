@@ -0,0 +1,15 @@
+-- Create events table; an event owns a set of markets
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    starts_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Link markets to the event that owns them
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS event_id TEXT REFERENCES events(id);
+
+CREATE INDEX IF NOT EXISTS idx_markets_event_id ON markets(event_id);
@@ -0,0 +1,11 @@
+-- Create settlement_entries table with each user's result from settling a market
+CREATE TABLE IF NOT EXISTS settlement_entries (
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    user_id TEXT NOT NULL,
+    positions BIGINT[] NOT NULL,
+    cost_basis DECIMAL NOT NULL,
+    payout DECIMAL NOT NULL,
+    realized_pnl DECIMAL NOT NULL,
+    settled_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (market_id, user_id)
+);
@@ -0,0 +1,15 @@
+-- Create positions table with each user's holding of each outcome in a market
+CREATE TABLE IF NOT EXISTS positions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    outcome INTEGER NOT NULL,
+    quantity BIGINT NOT NULL,
+    average_entry_price DECIMAL NOT NULL,
+    realized_pnl DECIMAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (user_id, market_id, outcome)
+);
+
+CREATE INDEX IF NOT EXISTS idx_positions_user_id ON positions(user_id);
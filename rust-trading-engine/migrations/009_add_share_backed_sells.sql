@@ -0,0 +1,5 @@
+-- Record how many shares of a sell order are backed by shares the seller holds
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS covered_quantity INTEGER NOT NULL DEFAULT 0;
+
+-- Let markets refuse sell orders that are not fully backed by shares
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS allow_shorting BOOLEAN NOT NULL DEFAULT TRUE;
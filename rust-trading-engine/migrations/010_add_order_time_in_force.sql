@@ -0,0 +1,2 @@
+-- Record how long each order stays on the book
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS time_in_force INTEGER NOT NULL DEFAULT 0;
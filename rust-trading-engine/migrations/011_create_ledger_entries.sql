@@ -0,0 +1,45 @@
+-- Create ledger_entries table: every movement of money debits one account and credits another
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id TEXT PRIMARY KEY,
+    debit_account_type INTEGER NOT NULL,
+    debit_owner_id TEXT,
+    credit_account_type INTEGER NOT NULL,
+    credit_owner_id TEXT,
+    amount DECIMAL NOT NULL CHECK (amount > 0),
+    transaction_type INTEGER NOT NULL,
+    reference_id TEXT,
+    description TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    CHECK (debit_account_type <> credit_account_type OR debit_owner_id IS DISTINCT FROM credit_owner_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_debit ON ledger_entries(debit_account_type, debit_owner_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_credit ON ledger_entries(credit_account_type, credit_owner_id);
+
+-- Ledger entries are immutable
+CREATE OR REPLACE FUNCTION reject_ledger_entry_change() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'ledger entries are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_entries_immutable ON ledger_entries;
+CREATE TRIGGER ledger_entries_immutable
+    BEFORE UPDATE OR DELETE ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION reject_ledger_entry_change();
+
+-- Open the ledger with the balances users already hold, funded from the external account
+INSERT INTO ledger_entries (
+    id, debit_account_type, debit_owner_id, credit_account_type, credit_owner_id,
+    amount, transaction_type, reference_id, description, created_at
+)
+SELECT gen_random_uuid()::text, 0, NULL, account_type, user_id, amount, 0, NULL, description, now()
+FROM (
+    SELECT 1 AS account_type, user_id, available_balance AS amount, 'Opening available balance' AS description
+    FROM user_balances
+    UNION ALL
+    SELECT 2, user_id, reserved_balance, 'Opening reserved balance'
+    FROM user_balances
+) AS opening
+WHERE amount > 0
+  AND NOT EXISTS (SELECT 1 FROM ledger_entries);
@@ -0,0 +1,92 @@
+-- Name the user whose funds a market account holds (collateral accounts are per market and user)
+ALTER TABLE ledger_entries ADD COLUMN IF NOT EXISTS debit_holder_id TEXT;
+ALTER TABLE ledger_entries ADD COLUMN IF NOT EXISTS credit_holder_id TEXT;
+
+ALTER TABLE ledger_entries DROP CONSTRAINT IF EXISTS ledger_entries_check;
+ALTER TABLE ledger_entries DROP CONSTRAINT IF EXISTS ledger_entries_distinct_accounts;
+ALTER TABLE ledger_entries ADD CONSTRAINT ledger_entries_distinct_accounts CHECK (
+    debit_account_type <> credit_account_type
+    OR debit_owner_id IS DISTINCT FROM credit_owner_id
+    OR debit_holder_id IS DISTINCT FROM credit_holder_id
+);
+
+-- Market maker pools are held for the house account funding them. Entries are never
+-- changed, so whatever a market's pool holds without a holder is moved into the pool
+-- held for its house account.
+WITH unheld_pools AS (
+    SELECT market_id, SUM(amount) AS balance
+    FROM (
+        SELECT credit_owner_id AS market_id, amount
+        FROM ledger_entries WHERE credit_account_type = 5 AND credit_holder_id IS NULL
+        UNION ALL
+        SELECT debit_owner_id, -amount
+        FROM ledger_entries WHERE debit_account_type = 5 AND debit_holder_id IS NULL
+    ) AS movements
+    GROUP BY market_id
+)
+INSERT INTO ledger_entries (
+    id, debit_account_type, debit_owner_id, credit_account_type, credit_owner_id,
+    credit_holder_id, amount, transaction_type, reference_id, description, created_at
+)
+SELECT gen_random_uuid()::text, 5, p.market_id, 5, p.market_id, a.house_account_id, p.balance, 7, p.market_id,
+    'Market maker pool of market ' || p.market_id || ' moved to house account ' || a.house_account_id, now()
+FROM unheld_pools p
+JOIN amm_states a ON a.market_id = p.market_id
+WHERE p.balance > 0;
+
+-- Move funds already reserved in unresolved markets from the users' reserved accounts
+-- into the markets holding them: the unfilled part of active orders plus the
+-- collateral kept for shares sold short. A user's markets never take more than
+-- their reserved account holds; anything left over stays there.
+WITH market_reserves AS (
+    SELECT user_id, market_id, SUM(amount) AS amount
+    FROM (
+        SELECT o.user_id, o.market_id,
+            CASE
+                WHEN o.side = 0 AND o.status IN (0, 1) THEN o.price * o.remaining_quantity
+                WHEN o.side = 1 THEN
+                    CASE WHEN o.status IN (0, 1)
+                        THEN o.remaining_quantity - GREATEST(o.covered_quantity - (o.quantity - o.remaining_quantity), 0)
+                        ELSE 0
+                    END
+                    + GREATEST((o.quantity - o.remaining_quantity) - o.covered_quantity, 0)
+                ELSE 0
+            END AS amount
+        FROM orders o
+        JOIN markets m ON m.id = o.market_id
+        WHERE m.status IN (0, 1, 2)
+          AND EXISTS (
+              SELECT 1 FROM balance_transactions t
+              WHERE t.user_id = o.user_id AND t.transaction_type = 2 AND t.reference_id = o.id
+          )
+    ) AS order_reserves
+    GROUP BY user_id, market_id
+    HAVING SUM(amount) > 0
+),
+reserved_accounts AS (
+    SELECT owner_id AS user_id, SUM(amount) AS balance
+    FROM (
+        SELECT credit_owner_id AS owner_id, amount FROM ledger_entries WHERE credit_account_type = 2
+        UNION ALL
+        SELECT debit_owner_id, -amount FROM ledger_entries WHERE debit_account_type = 2
+    ) AS movements
+    GROUP BY owner_id
+),
+capped AS (
+    SELECT r.user_id, r.market_id,
+        LEAST(
+            r.amount,
+            a.balance - (SUM(r.amount) OVER (PARTITION BY r.user_id ORDER BY r.market_id) - r.amount)
+        ) AS amount
+    FROM market_reserves r
+    JOIN reserved_accounts a ON a.user_id = r.user_id
+)
+INSERT INTO ledger_entries (
+    id, debit_account_type, debit_owner_id, credit_account_type, credit_owner_id,
+    credit_holder_id, amount, transaction_type, reference_id, description, created_at
+)
+SELECT gen_random_uuid()::text, 2, user_id, 6, market_id, user_id, amount, 2, market_id,
+    'Reserved funds moved into market ' || market_id, now()
+FROM capped
+WHERE amount > 0
+  AND NOT EXISTS (SELECT 1 FROM ledger_entries WHERE credit_account_type = 6);
@@ -0,0 +1,3 @@
+-- Version every user balance so concurrent updates can detect each other
+-- (each write must name the version it read and bumps it by one)
+ALTER TABLE user_balances ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,21 @@
+-- Client-supplied order IDs, unique per user
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS client_order_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_client_order_id
+    ON orders(user_id, client_order_id) WHERE client_order_id IS NOT NULL;
+
+-- A deposit with a reference is credited at most once
+CREATE UNIQUE INDEX IF NOT EXISTS idx_balance_transactions_deposit_reference
+    ON balance_transactions(user_id, reference_id) WHERE transaction_type = 0 AND reference_id IS NOT NULL;
+
+-- Create idempotency_keys table with the first response to each keyed request
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    endpoint TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_body TEXT NOT NULL,
+    status_code INTEGER,
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    PRIMARY KEY (endpoint, idempotency_key)
+);
@@ -0,0 +1,46 @@
+-- Record how much each balance transaction changed the user's available balance,
+-- so available balances can be rebuilt by replaying transactions
+ALTER TABLE balance_transactions ADD COLUMN IF NOT EXISTS available_change DECIMAL;
+
+-- Most transaction types only ever move available funds one way
+UPDATE balance_transactions SET available_change = CASE transaction_type
+    WHEN 0 THEN amount   -- Deposit
+    WHEN 1 THEN -amount  -- Withdraw
+    WHEN 2 THEN -amount  -- OrderReserve
+    WHEN 3 THEN amount   -- OrderRelease
+    WHEN 4 THEN amount   -- SettlementPayout
+    WHEN 5 THEN 0        -- TradeDebit, paid from reserved funds
+    WHEN 6 THEN amount   -- TradeCredit
+    WHEN 7 THEN -amount  -- AmmSubsidy
+    WHEN 8 THEN -amount  -- SettlementDebit
+    WHEN 9 THEN -amount  -- CompleteSetMint
+    WHEN 10 THEN amount  -- CompleteSetRedeem
+END
+WHERE available_change IS NULL;
+
+-- Proceeds kept in a market maker's pool never reached available funds
+UPDATE balance_transactions t SET available_change = 0
+WHERE t.transaction_type = 6 AND EXISTS (
+    SELECT 1 FROM ledger_entries e
+    WHERE e.transaction_type = 6
+      AND e.reference_id = t.reference_id
+      AND e.credit_account_type = 5
+      AND e.credit_holder_id = t.user_id
+);
+
+-- A short position settles from its collateral first; only the rest came out of available funds
+UPDATE balance_transactions t SET available_change = -COALESCE((
+    SELECT SUM(e.amount) FROM ledger_entries e
+    WHERE e.transaction_type = 8
+      AND e.reference_id = t.reference_id
+      AND e.debit_account_type = 1
+      AND e.debit_owner_id = t.user_id
+), 0)
+WHERE t.transaction_type = 8 AND EXISTS (
+    SELECT 1 FROM ledger_entries e
+    WHERE e.transaction_type = 8
+      AND e.reference_id = t.reference_id
+      AND (e.debit_owner_id = t.user_id OR e.debit_holder_id = t.user_id)
+);
+
+ALTER TABLE balance_transactions ALTER COLUMN available_change SET NOT NULL;
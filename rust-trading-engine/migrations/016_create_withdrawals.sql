@@ -0,0 +1,15 @@
+-- Create withdrawals table: requests to take funds off the platform, held until reviewed
+CREATE TABLE IF NOT EXISTS withdrawals (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    amount DECIMAL NOT NULL CHECK (amount > 0),
+    destination TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    payout_reference TEXT,
+    review_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    reviewed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_withdrawals_user_id ON withdrawals(user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_withdrawals_status ON withdrawals(status);
@@ -0,0 +1,14 @@
+-- Create deposits table: payments users start with a payment provider, credited once the provider confirms them
+CREATE TABLE IF NOT EXISTS deposits (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    amount DECIMAL NOT NULL CHECK (amount > 0),
+    status INTEGER NOT NULL,
+    provider_reference TEXT,
+    checkout_url TEXT,
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_deposits_user_id ON deposits(user_id, created_at);
@@ -0,0 +1,22 @@
+-- Split available balances into cash, winnings and promotional credit buckets
+-- (cash is whatever of the available balance is not winnings or promotional credit,
+-- so existing balances start out as cash)
+ALTER TABLE user_balances ADD COLUMN IF NOT EXISTS winnings_balance DECIMAL NOT NULL DEFAULT 0;
+
+-- What is left of each grant of promotional credit, soonest-expiring first
+ALTER TABLE user_balances ADD COLUMN IF NOT EXISTS promo_credits JSONB NOT NULL DEFAULT '[]';
+
+-- Part of the reserved balance paid for with promotional credit
+ALTER TABLE user_balances ADD COLUMN IF NOT EXISTS reserved_promo DECIMAL NOT NULL DEFAULT 0;
+
+-- Create promo_credits table: promotional credit granted to users, which expires
+CREATE TABLE IF NOT EXISTS promo_credits (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    amount DECIMAL NOT NULL CHECK (amount > 0),
+    reason TEXT NOT NULL,
+    granted_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_promo_credits_user_id ON promo_credits(user_id, granted_at);
@@ -0,0 +1,14 @@
+-- Create tax_withholdings table: net winnings from each settlement and the TDS withheld from its payout
+CREATE TABLE IF NOT EXISTS tax_withholdings (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    market_id TEXT NOT NULL,
+    financial_year INTEGER NOT NULL,
+    net_winnings DECIMAL NOT NULL,
+    taxable_winnings DECIMAL NOT NULL,
+    rate DECIMAL NOT NULL,
+    amount DECIMAL NOT NULL CHECK (amount >= 0),
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_tax_withholdings_user_id ON tax_withholdings(user_id, financial_year, created_at);
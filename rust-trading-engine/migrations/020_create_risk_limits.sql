@@ -0,0 +1,30 @@
+-- Create risk_tiers table: named sets of pre-trade limits that users are assigned to (NULL means no limit)
+CREATE TABLE IF NOT EXISTS risk_tiers (
+    name TEXT PRIMARY KEY,
+    max_open_orders INTEGER,
+    max_position BIGINT,
+    max_market_notional DECIMAL,
+    max_total_exposure DECIMAL,
+    max_order_value DECIMAL
+);
+
+-- Users are in the standard tier until they are assigned another
+INSERT INTO risk_tiers (name, max_open_orders, max_position, max_market_notional, max_total_exposure, max_order_value)
+VALUES ('standard', 200, 100000, 50000, 250000, 25000)
+ON CONFLICT (name) DO NOTHING;
+
+-- Create user_risk_tiers table: the tier each user is assigned to
+CREATE TABLE IF NOT EXISTS user_risk_tiers (
+    user_id TEXT PRIMARY KEY,
+    tier TEXT NOT NULL REFERENCES risk_tiers(name)
+);
+
+-- Create market_risk_limits table: limits for every user trading a market, on top of their tier's
+CREATE TABLE IF NOT EXISTS market_risk_limits (
+    market_id TEXT PRIMARY KEY,
+    max_open_orders INTEGER,
+    max_position BIGINT,
+    max_market_notional DECIMAL,
+    max_total_exposure DECIMAL,
+    max_order_value DECIMAL
+);
@@ -0,0 +1,12 @@
+-- Create api_keys table: keys users sign their API requests with
+-- (the secret is kept so signatures can be checked, and only shown when the key is issued)
+CREATE TABLE IF NOT EXISTS api_keys (
+    key_id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    label TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id, created_at);
@@ -0,0 +1,8 @@
+-- Create user_roles table: roles granted to users on top of trading
+-- (role: 1 = MarketMaker, 2 = Operator, 3 = Resolver, 4 = Admin; every user is a trader)
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id TEXT NOT NULL,
+    role INTEGER NOT NULL,
+    granted_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, role)
+);
@@ -0,0 +1,97 @@
+-- Keep a running balance of the ledger accounts that may never be overdrawn: a market's
+-- escrow (3), a market maker pool (5), a user's collateral in a market (6) and funds held
+-- for withdrawal (7). Any transaction leaving one of them negative is refused. Each posting
+-- locks the balances it moves until it commits, so concurrent postings against the same
+-- account are checked one at a time.
+CREATE TABLE IF NOT EXISTS ledger_account_balances (
+    account_type INTEGER NOT NULL,
+    owner_id TEXT NOT NULL DEFAULT '',
+    holder_id TEXT NOT NULL DEFAULT '',
+    balance DECIMAL NOT NULL DEFAULT 0,
+    PRIMARY KEY (account_type, owner_id, holder_id)
+);
+
+BEGIN;
+
+-- No entries are posted while the balances are built from them
+LOCK TABLE ledger_entries IN SHARE ROW EXCLUSIVE MODE;
+
+CREATE OR REPLACE FUNCTION apply_ledger_entry_to_balances() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.credit_account_type IN (3, 5, 6, 7) THEN
+        INSERT INTO ledger_account_balances (account_type, owner_id, holder_id, balance)
+        VALUES (NEW.credit_account_type, COALESCE(NEW.credit_owner_id, ''), COALESCE(NEW.credit_holder_id, ''), NEW.amount)
+        ON CONFLICT (account_type, owner_id, holder_id)
+        DO UPDATE SET balance = ledger_account_balances.balance + EXCLUDED.balance;
+    END IF;
+
+    IF NEW.debit_account_type IN (3, 5, 6, 7) THEN
+        INSERT INTO ledger_account_balances (account_type, owner_id, holder_id, balance)
+        VALUES (NEW.debit_account_type, COALESCE(NEW.debit_owner_id, ''), COALESCE(NEW.debit_holder_id, ''), -NEW.amount)
+        ON CONFLICT (account_type, owner_id, holder_id)
+        DO UPDATE SET balance = ledger_account_balances.balance + EXCLUDED.balance;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Checked at commit, so a posting may pass through an account before paying it back
+CREATE OR REPLACE FUNCTION reject_ledger_overdraft() RETURNS TRIGGER AS $$
+DECLARE
+    remaining DECIMAL;
+BEGIN
+    IF NEW.debit_account_type NOT IN (3, 5, 6, 7) THEN
+        RETURN NULL;
+    END IF;
+
+    SELECT balance INTO remaining
+    FROM ledger_account_balances
+    WHERE account_type = NEW.debit_account_type
+      AND owner_id = COALESCE(NEW.debit_owner_id, '')
+      AND holder_id = COALESCE(NEW.debit_holder_id, '');
+
+    IF remaining < 0 THEN
+        RAISE EXCEPTION '% account of % would be overdrawn by %',
+            CASE NEW.debit_account_type
+                WHEN 3 THEN 'Escrow'
+                WHEN 5 THEN 'Market maker pool'
+                WHEN 6 THEN 'Collateral'
+                ELSE 'Pending withdrawal'
+            END,
+            concat_ws(' held for ', NEW.debit_owner_id, NEW.debit_holder_id),
+            -remaining
+            USING ERRCODE = 'check_violation';
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_entries_balances ON ledger_entries;
+CREATE TRIGGER ledger_entries_balances
+    AFTER INSERT ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION apply_ledger_entry_to_balances();
+
+DROP TRIGGER IF EXISTS ledger_entries_no_overdraft ON ledger_entries;
+CREATE CONSTRAINT TRIGGER ledger_entries_no_overdraft
+    AFTER INSERT ON ledger_entries
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION reject_ledger_overdraft();
+
+-- Open the balances with the entries posted so far
+DELETE FROM ledger_account_balances;
+INSERT INTO ledger_account_balances (account_type, owner_id, holder_id, balance)
+SELECT account_type, COALESCE(owner_id, ''), COALESCE(holder_id, ''), SUM(amount)
+FROM (
+    SELECT credit_account_type AS account_type, credit_owner_id AS owner_id,
+        credit_holder_id AS holder_id, amount
+    FROM ledger_entries
+    UNION ALL
+    SELECT debit_account_type, debit_owner_id, debit_holder_id, -amount
+    FROM ledger_entries
+) AS movements
+WHERE account_type IN (3, 5, 6, 7)
+GROUP BY account_type, COALESCE(owner_id, ''), COALESCE(holder_id, '');
+
+COMMIT;
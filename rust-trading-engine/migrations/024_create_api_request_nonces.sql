@@ -0,0 +1,11 @@
+-- Create api_request_nonces table: the nonce each signed request was made with, so a
+-- captured request cannot be replayed while its timestamp is still accepted
+-- (rows older than the accepted clock skew are pruned)
+CREATE TABLE IF NOT EXISTS api_request_nonces (
+    key_id TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (key_id, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_request_nonces_created_at ON api_request_nonces(created_at);
@@ -0,0 +1,8 @@
+-- Record which market and order each part of the reserved promotional credit is held for
+-- (credit reserved before this is not tied to any order, and is released as
+-- promotional credit by whichever of the user's reserves is released first)
+ALTER TABLE user_balances ADD COLUMN IF NOT EXISTS promo_reserves JSONB NOT NULL DEFAULT '[]';
+
+-- Promotional credit paid for shares in each market, returned as promotional
+-- credit when the shares are sold or paid out
+ALTER TABLE user_balances ADD COLUMN IF NOT EXISTS promo_positions JSONB NOT NULL DEFAULT '[]';
@@ -47,6 +47,13 @@ DATABASE_URL=postgres://$DB_USER:$DB_PASSWORD@$DB_HOST:$DB_PORT/$DB_NAME
 
 # Logging
 RUST_LOG=info
+
+# Development only: allows the fixed development deposit webhook secret
+DEV_MODE=true
+
+# First admin: set to a user ID to issue them an API key, written to ADMIN_KEY_FILE
+# ADMIN_USER_ID=
+# ADMIN_KEY_FILE=admin_api_key.json
 EOF
     
     echo ".env file created."
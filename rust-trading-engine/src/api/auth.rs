@@ -0,0 +1,347 @@
+use std::sync::Arc;
+use log::debug;
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+use warp::http::{HeaderMap, Method, StatusCode};
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+use crate::api::rate_limit::{RateBudget, RateLimited, too_many_requests};
+use crate::api::routes::ApiResponse;
+use crate::models::role::Role;
+use crate::services::auth_service::{AuthService, RequestSignature};
+use crate::db::connection::Repository;
+
+/// Header carrying the client's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Header carrying the Unix timestamp, in seconds, the request was signed at
+pub const TIMESTAMP_HEADER: &str = "x-api-timestamp";
+
+/// Header carrying the request's nonce, which its API key can only use once
+pub const NONCE_HEADER: &str = "x-api-nonce";
+
+/// Header carrying the request's signature
+pub const SIGNATURE_HEADER: &str = "x-api-signature";
+
+/// Largest request body, in bytes, read to check a request's signature
+pub const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// The user a request was signed by
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    /// User the request acts for
+    pub user_id: Uuid,
+    
+    /// API key the request was signed with
+    pub key_id: String,
+    
+    /// Roles the user holds
+    pub roles: Vec<Role>,
+}
+
+impl AuthenticatedUser {
+    /// Checks the user may act for `user_id`, turning the request away if not
+    pub fn authorize(&self, user_id: Uuid) -> Result<(), Rejection> {
+        if self.user_id == user_id {
+            Ok(())
+        } else {
+            Err(ApiRejection::forbidden(format!("API key {} cannot act for user {}", self.key_id, user_id)))
+        }
+    }
+    
+    /// Checks if the user holds a role that allows what `role` does
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.iter().any(|held| held.grants(role))
+    }
+    
+    /// Checks the user holds `role`, turning the request away if not
+    pub fn require(&self, role: Role) -> Result<(), Rejection> {
+        if self.has_role(role) {
+            Ok(())
+        } else {
+            Err(ApiRejection::forbidden(format!("User {} does not hold the {:?} role", self.user_id, role)))
+        }
+    }
+}
+
+/// A request turned away before it reached its handler
+#[derive(Debug)]
+pub struct ApiRejection {
+    /// Status to respond with
+    pub status: StatusCode,
+    
+    /// Why the request was turned away
+    pub message: String,
+}
+
+impl warp::reject::Reject for ApiRejection {}
+
+impl ApiRejection {
+    /// Turns a request away as not authenticated (401)
+    pub fn unauthorized(message: String) -> Rejection {
+        warp::reject::custom(Self { status: StatusCode::UNAUTHORIZED, message })
+    }
+    
+    /// Turns a request away as not allowed for its user (403)
+    pub fn forbidden(message: String) -> Rejection {
+        warp::reject::custom(Self { status: StatusCode::FORBIDDEN, message })
+    }
+    
+    /// Turns a request away as malformed (400)
+    pub fn bad_request(message: String) -> Rejection {
+        warp::reject::custom(Self { status: StatusCode::BAD_REQUEST, message })
+    }
+}
+
+/// Responds to requests turned away with an `ApiRejection` or for their key's rate limit,
+/// passing any other rejection on
+pub async fn handle_rejection(rejection: Rejection) -> Result<Response, Rejection> {
+    if let Some(limited) = rejection.find::<RateLimited>() {
+        return Ok(too_many_requests(limited));
+    }
+    match rejection.find::<ApiRejection>() {
+        Some(api_rejection) => Ok(warp::reply::with_status(
+            warp::reply::json(&ApiResponse::<()>::error(api_rejection.message.clone())),
+            api_rejection.status,
+        ).into_response()),
+        None => Err(rejection),
+    }
+}
+
+/// What a request is signed over, along with the headers carrying its key and signature
+struct SignedRequest {
+    key_id: Option<String>,
+    timestamp: Option<String>,
+    nonce: Option<String>,
+    signature: Option<String>,
+    method: Method,
+    path: String,
+    
+    /// User the request was already authenticated as, when it is re-run by the idempotency layer
+    authenticated: Option<AuthenticatedUser>,
+}
+
+/// Reads the parts of a request that are signed
+fn signed_request() -> impl Filter<Extract = (SignedRequest,), Error = Rejection> + Clone {
+    warp::header::optional::<String>(API_KEY_HEADER)
+        .and(warp::header::optional::<String>(TIMESTAMP_HEADER))
+        .and(warp::header::optional::<String>(NONCE_HEADER))
+        .and(warp::header::optional::<String>(SIGNATURE_HEADER))
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and(warp::ext::optional::<AuthenticatedUser>())
+        .map(|key_id, timestamp, nonce, signature, method, path: FullPath, query: String, authenticated| {
+            let path = if query.is_empty() { path.as_str().to_string() } else { format!("{}?{}", path.as_str(), query) };
+            SignedRequest { key_id, timestamp, nonce, signature, method, path, authenticated }
+        })
+}
+
+/// Checks a request's signature and counts it against its key's rate limits,
+/// returning the user it was signed by or why it was refused
+async fn verify<R: Repository + Send + Sync + 'static>(
+    auth_service: &AuthService<R>,
+    request: SignedRequest,
+    body: &[u8],
+) -> Result<AuthenticatedUser, Rejection> {
+    // Its nonce is already used up and it has been counted, so it cannot be checked again
+    if let Some(user) = request.authenticated {
+        return Ok(user);
+    }
+    
+    let (key_id, timestamp, nonce, signature) = match (request.key_id, request.timestamp, request.nonce, request.signature) {
+        (Some(key_id), Some(timestamp), Some(nonce), Some(signature)) => (key_id, timestamp, nonce, signature),
+        _ => {
+            return Err(ApiRejection::unauthorized(format!(
+                "Requests must be signed with the {}, {}, {} and {} headers",
+                API_KEY_HEADER, TIMESTAMP_HEADER, NONCE_HEADER, SIGNATURE_HEADER
+            )));
+        }
+    };
+    
+    let signed = RequestSignature {
+        key_id: &key_id,
+        timestamp: &timestamp,
+        nonce: &nonce,
+        signature: &signature,
+        method: request.method.as_str(),
+        path: &request.path,
+        body,
+    };
+    let api_key = match auth_service.authenticate(&signed).await {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            debug!("Refused {} {} signed with API key {}: {}", request.method, request.path, key_id, e);
+            return Err(ApiRejection::unauthorized(e.to_string()));
+        }
+    };
+    
+    // Only now is it known whose budgets the request can be counted against
+    auth_service.check_rate_limit(RateBudget::for_method(&request.method), &api_key).map_err(|limited| {
+        debug!("Rate limited {} {} signed with API key {}", request.method, request.path, key_id);
+        warp::reject::custom(limited)
+    })?;
+    
+    let roles = auth_service.get_roles(api_key.user_id).await.map_err(|e| ApiRejection::unauthorized(e.to_string()))?;
+    
+    Ok(AuthenticatedUser { user_id: api_key.user_id, key_id: api_key.key_id, roles })
+}
+
+/// Authenticates a request from its parts, for code that handles requests outside the routes
+///
+/// A request without an API key is nobody's; one with a key must be signed correctly.
+/// This uses up the request's nonce, so a request handed on to the routes must carry
+/// the user in its extensions for the routes to take instead.
+pub async fn authenticate_request<R: Repository + Send + Sync + 'static>(
+    auth_service: &AuthService<R>,
+    headers: &HeaderMap,
+    method: &Method,
+    path: &str,
+    body: &[u8],
+) -> Result<Option<AuthenticatedUser>, Rejection> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let request = SignedRequest {
+        key_id: header(API_KEY_HEADER),
+        timestamp: header(TIMESTAMP_HEADER),
+        nonce: header(NONCE_HEADER),
+        signature: header(SIGNATURE_HEADER),
+        method: method.clone(),
+        path: path.to_string(),
+        authenticated: None,
+    };
+    if request.key_id.is_none() {
+        return Ok(None);
+    }
+    verify(auth_service, request, body).await.map(Some)
+}
+
+/// Reads a request's body, turning away bodies over `MAX_BODY_BYTES`
+///
+/// Bodies must state their length, so a request without a Content-Length
+/// header is only read when it carries no body at all.
+pub fn limited_body() -> impl Filter<Extract = (Bytes,), Error = Rejection> + Clone {
+    let without_body = warp::header::optional::<String>("content-length")
+        .and(warp::header::optional::<String>("transfer-encoding"))
+        .and_then(|length: Option<String>, encoding: Option<String>| async move {
+            if length.is_none() && encoding.is_none() {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one();
+    warp::body::content_length_limit(MAX_BODY_BYTES)
+        .or(without_body)
+        .unify()
+        .and(warp::body::bytes())
+}
+
+/// Authenticates a signed request, extracting the user it was signed by
+///
+/// Reads the request body, so routes needing the body should use
+/// `authenticated_json` instead.
+pub fn authenticated<R: Repository + Send + Sync + 'static>(
+    auth_service: Arc<AuthService<R>>,
+) -> impl Filter<Extract = (AuthenticatedUser,), Error = Rejection> + Clone {
+    signed_request()
+        .and(limited_body())
+        .and_then(move |request: SignedRequest, body: Bytes| {
+            let auth_service = auth_service.clone();
+            async move { verify(&auth_service, request, &body).await }
+        })
+}
+
+/// Authenticates a signed request and reads its JSON body
+pub fn authenticated_json<R, T>(
+    auth_service: Arc<AuthService<R>>,
+) -> impl Filter<Extract = (AuthenticatedUser, T), Error = Rejection> + Clone
+where
+    R: Repository + Send + Sync + 'static,
+    T: DeserializeOwned + Send,
+{
+    signed_json(auth_service, None)
+}
+
+/// Authenticates a signed request, checks its user holds `role` if one is given, then reads its JSON body
+fn signed_json<R, T>(
+    auth_service: Arc<AuthService<R>>,
+    role: Option<Role>,
+) -> impl Filter<Extract = (AuthenticatedUser, T), Error = Rejection> + Clone
+where
+    R: Repository + Send + Sync + 'static,
+    T: DeserializeOwned + Send,
+{
+    signed_request()
+        .and(limited_body())
+        .and_then(move |request: SignedRequest, body: Bytes| {
+            let auth_service = auth_service.clone();
+            async move {
+                let user = verify(&auth_service, request, &body).await?;
+                if let Some(role) = role {
+                    user.require(role)?;
+                }
+                let body = serde_json::from_slice::<T>(&body)
+                    .map_err(|e| ApiRejection::bad_request(format!("Invalid request body: {}", e)))?;
+                Ok::<_, Rejection>((user, body))
+            }
+        })
+        .untuple_one()
+}
+
+/// Authenticates a request if it is signed, extracting nobody if it is not
+///
+/// For requests without a body, such as WebSocket upgrades.
+pub fn optionally_authenticated<R: Repository + Send + Sync + 'static>(
+    auth_service: Arc<AuthService<R>>,
+) -> impl Filter<Extract = (Option<AuthenticatedUser>,), Error = Rejection> + Clone {
+    signed_request().and_then(move |request: SignedRequest| {
+        let auth_service = auth_service.clone();
+        async move {
+            if request.key_id.is_none() && request.authenticated.is_none() {
+                return Ok(None);
+            }
+            verify(&auth_service, request, &[]).await.map(Some)
+        }
+    })
+}
+
+/// Authenticates a signed request and checks its user holds `role`
+///
+/// Reads the request body, so routes needing the body should use
+/// `authorized_json` instead.
+pub fn authorized<R: Repository + Send + Sync + 'static>(
+    auth_service: Arc<AuthService<R>>,
+    role: Role,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    authenticated(auth_service)
+        .and_then(move |user: AuthenticatedUser| async move { user.require(role) })
+        .untuple_one()
+}
+
+/// Authenticates a signed request, checks its user holds `role` and reads its JSON body
+pub fn authorized_json<R, T>(
+    auth_service: Arc<AuthService<R>>,
+    role: Role,
+) -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    R: Repository + Send + Sync + 'static,
+    T: DeserializeOwned + Send,
+{
+    signed_json::<R, T>(auth_service, Some(role)).map(|_user: AuthenticatedUser, body: T| body)
+}
+
+/// Authenticates a signed request, checks its user holds `role` and reads its JSON body,
+/// extracting the user along with the body
+pub fn authorized_user_json<R, T>(
+    auth_service: Arc<AuthService<R>>,
+    role: Role,
+) -> impl Filter<Extract = (AuthenticatedUser, T), Error = Rejection> + Clone
+where
+    R: Repository + Send + Sync + 'static,
+    T: DeserializeOwned + Send,
+{
+    signed_json::<R, T>(auth_service, Some(role))
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+use log::error;
+use warp::http::{HeaderMap, Method, Request, StatusCode};
+use warp::hyper::{self, body::Bytes, service::Service, Body};
+use warp::path::FullPath;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+use crate::api::auth::{authenticate_request, handle_rejection, limited_body};
+use crate::api::routes::ApiResponse;
+use crate::services::auth_service::AuthService;
+use crate::services::idempotency_service::{IdempotencyClaim, IdempotencyService};
+use crate::db::connection::Repository;
+
+/// Header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header set on responses replayed for a repeated idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Makes mutating requests that carry an `Idempotency-Key` header safe to retry
+///
+/// The first request with a key runs as normal. If it succeeds its response is
+/// stored, and repeats of the same request with the same key get that response
+/// back without running again. Failed requests are not stored, so they can be
+/// retried with the same key. Reads and requests without a key go straight to
+/// the routes.
+///
+/// Keys of signed requests are kept apart per user, and a response is only
+/// replayed to a request signed by the user it was made for.
+pub fn idempotent<F, T, R>(
+    routes: F,
+    idempotency_service: Arc<IdempotencyService<R>>,
+    auth_service: Arc<AuthService<R>>,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (T,), Error = Rejection> + Clone + Send + Sync + 'static,
+    T: Reply,
+    R: Repository + Send + Sync + 'static,
+{
+    let keyed_routes = routes.clone();
+    let keyed = warp::method()
+        .and_then(|method: Method| async move {
+            if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+                Err(warp::reject::not_found())
+            } else {
+                Ok(method)
+            }
+        })
+        .and(warp::path::full())
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and(warp::header::optional::<String>(IDEMPOTENCY_KEY_HEADER).and_then(|key: Option<String>| async move {
+            key.ok_or_else(warp::reject::not_found)
+        }))
+        .and(warp::header::headers_cloned())
+        .and(limited_body())
+        .and_then(move |method: Method, path: FullPath, query: String, key: String, headers: HeaderMap, body: Bytes| {
+            let routes = keyed_routes.clone();
+            let idempotency_service = idempotency_service.clone();
+            let auth_service = auth_service.clone();
+            async move {
+                let request = KeyedRequest { method, path, query, key, headers, body };
+                Ok::<_, Rejection>(run_once(routes, idempotency_service, auth_service, request).await)
+            }
+        });
+    
+    keyed
+        .or(routes.map(|reply: T| reply.into_response()))
+        .unify()
+}
+
+/// A mutating request made with an idempotency key
+struct KeyedRequest {
+    method: Method,
+    path: FullPath,
+    query: String,
+    key: String,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Runs a keyed request unless the key has been used before, storing its response if it succeeds
+async fn run_once<F, T, R>(
+    routes: F,
+    idempotency_service: Arc<IdempotencyService<R>>,
+    auth_service: Arc<AuthService<R>>,
+    request: KeyedRequest,
+) -> Response
+where
+    F: Filter<Extract = (T,), Error = Rejection> + Clone + Send + Sync + 'static,
+    T: Reply,
+    R: Repository + Send + Sync + 'static,
+{
+    let KeyedRequest { method, path, query, key, headers, body } = request;
+    let uri = if query.is_empty() { path.as_str().to_string() } else { format!("{}?{}", path.as_str(), query) };
+    let request_body = String::from_utf8_lossy(&body).into_owned();
+    
+    // Check the signature before anything is replayed, and keep each user's keys apart
+    let user = match authenticate_request(&auth_service, &headers, &method, &uri, &body).await {
+        Ok(user) => user,
+        Err(rejection) => return match handle_rejection(rejection).await {
+            Ok(response) => response,
+            Err(_) => error_response(StatusCode::UNAUTHORIZED, "Request could not be authenticated".to_string()),
+        },
+    };
+    let endpoint = match &user {
+        Some(user) => format!("{} {} {}", method, path.as_str(), user.user_id),
+        None => format!("{} {}", method, path.as_str()),
+    };
+    
+    match idempotency_service.claim(&endpoint, &key, &request_body).await {
+        Ok(IdempotencyClaim::New) => {}
+        Ok(IdempotencyClaim::Replay { status_code, response_body }) => {
+            let status = StatusCode::from_u16(status_code).unwrap_or(StatusCode::OK);
+            let mut response = json_response(status, response_body);
+            response.headers_mut().insert(IDEMPOTENT_REPLAYED_HEADER, "true".parse().unwrap());
+            return response;
+        }
+        Ok(IdempotencyClaim::InProgress) => {
+            return error_response(StatusCode::CONFLICT, format!("A request with idempotency key {} is still in progress", key));
+        }
+        Ok(IdempotencyClaim::Mismatch) => {
+            return error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Idempotency key {} was already used for a different request", key),
+            );
+        }
+        Err(e) => {
+            error!("Failed to claim idempotency key {} on {}: {}", key, endpoint, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    }
+    
+    // Hand the request to the routes on a task of its own, since warp cannot route a request inside another
+    let mut inner_request = Request::new(Body::from(body));
+    *inner_request.method_mut() = method;
+    *inner_request.uri_mut() = match uri.parse() {
+        Ok(uri) => uri,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, format!("Invalid request path: {}", e)),
+    };
+    *inner_request.headers_mut() = headers;
+    // The request's nonce is used up, so the routes take the user it was signed by from here
+    if let Some(user) = user {
+        inner_request.extensions_mut().insert(user);
+    }
+    
+    let response = match tokio::spawn(async move { warp::service(routes).call(inner_request).await }).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(never)) => match never {},
+        Err(e) => {
+            error!("Request with idempotency key {} on {} failed: {}", key, endpoint, e);
+            release(&idempotency_service, &endpoint, &key).await;
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+    
+    let (parts, response_body) = response.into_parts();
+    let response_body = match hyper::body::to_bytes(response_body).await {
+        Ok(response_body) => response_body,
+        Err(e) => {
+            error!("Failed to read response for idempotency key {} on {}: {}", key, endpoint, e);
+            release(&idempotency_service, &endpoint, &key).await;
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+    
+    if parts.status.is_success() && is_success(&response_body) {
+        let stored = String::from_utf8_lossy(&response_body);
+        if let Err(e) = idempotency_service.complete(&endpoint, &key, parts.status.as_u16(), &stored).await {
+            error!("Failed to store response for idempotency key {} on {}: {}", key, endpoint, e);
+        }
+    } else {
+        release(&idempotency_service, &endpoint, &key).await;
+    }
+    
+    Response::from_parts(parts, Body::from(response_body))
+}
+
+/// Frees a key whose request failed so the client can retry it
+async fn release<R: Repository + Send + Sync + 'static>(idempotency_service: &IdempotencyService<R>, endpoint: &str, key: &str) {
+    if let Err(e) = idempotency_service.release(endpoint, key).await {
+        error!("Failed to release idempotency key {} on {}: {}", key, endpoint, e);
+    }
+}
+
+/// Checks if a response body is an `ApiResponse` that succeeded
+fn is_success(body: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value.get("success").and_then(|success| success.as_bool()))
+        .unwrap_or(false)
+}
+
+/// Builds a JSON response from a stored body
+fn json_response(status: StatusCode, body: String) -> Response {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert("content-type", "application/json".parse().unwrap());
+    response
+}
+
+/// Builds an error response in the usual API format
+fn error_response(status: StatusCode, message: String) -> Response {
+    warp::reply::with_status(warp::reply::json(&ApiResponse::<()>::error(message)), status).into_response()
+}
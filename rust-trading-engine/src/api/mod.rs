@@ -1,6 +1,10 @@
+pub mod auth;
+pub mod idempotency;
+pub mod rate_limit;
 pub mod routes;
 pub mod websocket;
 
 // Re-export common types
 pub use routes::{ApiResponse, routes};
+pub use rate_limit::{RateLimit, RateLimiter, rate_limited};
 pub use websocket::{WebSocketEvent, WebSocketServer}; 
\ No newline at end of file
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::warn;
+use uuid::Uuid;
+use warp::http::{Method, StatusCode};
+use warp::path::FullPath;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+use crate::api::routes::ApiResponse;
+
+/// Buckets kept before full ones are dropped
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// Budgets that requests are counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateBudget {
+    /// Order entry and any other request that changes state
+    Orders,
+    
+    /// Data queries and WebSocket messages
+    Queries,
+}
+
+impl RateBudget {
+    /// Gets the budget for a request made with a method
+    pub fn for_method(method: &Method) -> Self {
+        if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+            RateBudget::Queries
+        } else {
+            RateBudget::Orders
+        }
+    }
+}
+
+/// Who a request is counted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateKey {
+    /// The API key the request was signed with
+    ApiKey(String),
+    
+    /// The user the request was signed by
+    User(Uuid),
+    
+    /// The address the request came from
+    Ip(IpAddr),
+}
+
+impl fmt::Display for RateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RateKey::ApiKey(key) => write!(f, "API key {}", key),
+            RateKey::User(user_id) => write!(f, "user {}", user_id),
+            RateKey::Ip(ip) => write!(f, "IP {}", ip),
+        }
+    }
+}
+
+/// How fast a budget can be spent
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Requests per second the budget refills at
+    pub per_second: f64,
+    
+    /// Most requests that can be made at once
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Creates a new rate limit
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self { per_second, burst }
+    }
+}
+
+/// A request turned away for being over budget
+#[derive(Debug, Clone)]
+pub struct RateLimited {
+    /// Budget that was spent
+    pub budget: RateBudget,
+    
+    /// Key whose budget was spent
+    pub key: RateKey,
+    
+    /// How long until the key can make another request
+    pub retry_after: Duration,
+}
+
+impl warp::reject::Reject for RateLimited {}
+
+/// Tokens left in one key's budget
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    /// Adds the tokens earned since the bucket was last updated
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(f64::from(limit.burst));
+        self.updated_at = now;
+    }
+}
+
+/// Token bucket rate limiter with separate budgets for order entry and data queries
+///
+/// Every key a request is counted against has its own bucket per budget, which
+/// holds up to `burst` requests and refills at `per_second`. A request takes one
+/// token from each of its keys, and is turned away without taking any if one of
+/// them is empty.
+pub struct RateLimiter {
+    /// Limit on order entry
+    orders: RateLimit,
+    
+    /// Limit on data queries
+    queries: RateLimit,
+    
+    /// Bucket for each budget and key
+    buckets: Mutex<HashMap<(RateBudget, RateKey), TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter
+    pub fn new(orders: RateLimit, queries: RateLimit) -> Self {
+        Self {
+            orders,
+            queries,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Gets the limit on a budget
+    pub fn limit(&self, budget: RateBudget) -> RateLimit {
+        match budget {
+            RateBudget::Orders => self.orders,
+            RateBudget::Queries => self.queries,
+        }
+    }
+    
+    /// Counts a request against the budget of each of its keys
+    pub fn check(&self, budget: RateBudget, keys: &[RateKey]) -> Result<(), RateLimited> {
+        let limit = self.limit(budget);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        
+        // Full buckets are the same as new ones, so drop them rather than keep every key ever seen
+        if buckets.len() >= MAX_IDLE_BUCKETS {
+            buckets.retain(|(budget, _), bucket| {
+                bucket.refill(&self.limit(*budget), now);
+                bucket.tokens < f64::from(self.limit(*budget).burst)
+            });
+        }
+        
+        for key in keys {
+            let bucket = buckets.entry((budget, key.clone())).or_insert(TokenBucket {
+                tokens: f64::from(limit.burst),
+                updated_at: now,
+            });
+            bucket.refill(&limit, now);
+            if bucket.tokens < 1.0 {
+                return Err(RateLimited {
+                    budget,
+                    key: key.clone(),
+                    retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second),
+                });
+            }
+        }
+        
+        for key in keys {
+            if let Some(bucket) = buckets.get_mut(&(budget, key.clone())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turns away requests over their rate limit with 429 Too Many Requests
+///
+/// Reads count against the query budget and everything else against the order
+/// budget. Requests are counted here for the address they came from; a signed
+/// request is also counted for its API key and user, but only once its signature
+/// has been checked, so no one can spend another key's or user's budget.
+pub fn rate_limited<F, T>(
+    routes: F,
+    rate_limiter: Arc<RateLimiter>,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (T,), Error = Rejection> + Clone + Send + Sync + 'static,
+    T: Reply,
+{
+    let limited = warp::method()
+        .and(warp::path::full())
+        .and(warp::addr::remote())
+        .and_then(move |method: Method, path: FullPath, remote: Option<SocketAddr>| {
+            let rate_limiter = rate_limiter.clone();
+            async move {
+                let keys: Vec<RateKey> = remote.map(|remote| RateKey::Ip(remote.ip())).into_iter().collect();
+                match rate_limiter.check(RateBudget::for_method(&method), &keys) {
+                    Ok(()) => Err(warp::reject::not_found()),
+                    Err(limited) => {
+                        warn!("Rate limited {} {} from {}", method, path.as_str(), limited.key);
+                        Ok(too_many_requests(&limited))
+                    }
+                }
+            }
+        });
+    
+    limited
+        .or(routes.map(|reply: T| reply.into_response()))
+        .unify()
+}
+
+/// Builds a 429 response saying when to retry
+pub fn too_many_requests(limited: &RateLimited) -> Response {
+    let message = format!(
+        "Rate limit exceeded for {}, retry in {} ms",
+        limited.key,
+        limited.retry_after.as_millis().max(1)
+    );
+    let mut response = warp::reply::with_status(
+        warp::reply::json(&ApiResponse::<()>::error(message)),
+        StatusCode::TOO_MANY_REQUESTS,
+    ).into_response();
+    
+    let retry_after = limited.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    response.headers_mut().insert("retry-after", retry_after.into());
+    response
+}
@@ -4,13 +4,30 @@ use log::error;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use warp::{self, Filter, Rejection, Reply};
+use warp::http::StatusCode;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
-use crate::models::{Market, Order, OrderSide, OutcomeSide};
-use crate::services::order_service::OrderService;
+use crate::models::{Deposit, Event, LedgerBalance, LedgerCheck, LiquidityMode, MarkPriceSource, Market, MarketEscrow, MarketGroup, MarketType, Order, OrderSide, OutcomeSide, Portfolio, Position, PromoCredit, ReconciliationReport, ApiKey, IssuedApiKey, RiskLimits, Role, RoleGrant, RiskTier, TaxStatement, TimeInForce, Withdrawal, WithdrawalStatus};
+use crate::services::order_service::{OrderService, PauseOrderPolicy};
 use crate::services::bot_service::{BotService, BotStrategy};
 use crate::services::settlement_service::SettlementService;
+use crate::services::amm_service::AmmService;
+use crate::services::event_service::EventService;
+use crate::services::position_service::PositionService;
+use crate::services::portfolio_service::PortfolioService;
+use crate::services::ledger_service::LedgerService;
+use crate::services::idempotency_service::IdempotencyService;
+use crate::services::reconciliation_service::ReconciliationService;
+use crate::services::withdrawal_service::WithdrawalService;
+use crate::services::deposit_service::DepositService;
+use crate::services::promo_service::PromoService;
+use crate::services::tax_service::TaxService;
+use crate::services::risk_service::RiskService;
+use crate::services::auth_service::AuthService;
+use crate::api::auth::{AuthenticatedUser, authenticated, authenticated_json, authorized, authorized_json, authorized_user_json, handle_rejection};
+use crate::api::idempotency::idempotent;
+use crate::api::rate_limit::{RateLimiter, rate_limited};
 use crate::db::connection::Repository;
 
 /// Request to create a new market
@@ -20,17 +37,23 @@ pub struct CreateMarketRequest {
     pub question: String,
     pub description: String,
     pub close_time: Option<DateTime<Utc>>,
+    pub outcomes: Option<Vec<String>>,
+    pub market_type: Option<MarketType>,
+    pub lower_bound: Option<Decimal>,
+    pub upper_bound: Option<Decimal>,
+    pub allow_shorting: Option<bool>,
 }
 
 /// Request to submit a new order
 #[derive(Debug, Deserialize)]
 pub struct SubmitOrderRequest {
-    pub user_id: Uuid,
     pub market_id: String,
     pub side: OrderSide,
     pub outcome: OutcomeSide,
     pub price: Decimal,
     pub quantity: u32,
+    pub time_in_force: Option<TimeInForce>,
+    pub client_order_id: Option<String>,
 }
 
 /// Request to cancel an order
@@ -44,7 +67,8 @@ pub struct CancelOrderRequest {
 #[derive(Debug, Deserialize)]
 pub struct ResolveMarketRequest {
     pub market_id: String,
-    pub outcome: OutcomeSide,
+    pub outcome: Option<OutcomeSide>,
+    pub value: Option<Decimal>,
 }
 
 /// Request to start a bot on a market
@@ -54,6 +78,136 @@ pub struct StartBotRequest {
     pub strategy: String,
 }
 
+/// Request to pause a market
+#[derive(Debug, Deserialize)]
+pub struct PauseMarketRequest {
+    pub order_policy: Option<PauseOrderPolicy>,
+}
+
+/// Request to enable an automated market maker on a market
+#[derive(Debug, Deserialize)]
+pub struct EnableMarketMakerRequest {
+    pub liquidity: Decimal,
+    pub liquidity_mode: Option<LiquidityMode>,
+}
+
+/// Query for a market maker price quote
+#[derive(Debug, Deserialize)]
+pub struct MarketMakerQuoteQuery {
+    pub outcome: OutcomeSide,
+    pub side: OrderSide,
+    pub quantity: u32,
+}
+
+/// Request to create a group of mutually exclusive markets
+#[derive(Debug, Deserialize)]
+pub struct CreateMarketGroupRequest {
+    pub group_id: String,
+    pub name: String,
+    pub description: String,
+    pub market_ids: Vec<String>,
+}
+
+/// Request to convert No shares in one market of a group into Yes shares in the others
+#[derive(Debug, Deserialize)]
+pub struct ConvertSharesRequest {
+    pub market_id: String,
+    pub quantity: u32,
+}
+
+/// Request to mint or redeem complete sets of a market's outcome shares
+#[derive(Debug, Deserialize)]
+pub struct CompleteSetRequest {
+    pub quantity: u32,
+}
+
+/// Identifies the position to quote or close for a cash-out
+#[derive(Debug, Deserialize)]
+pub struct CashOutRequest {
+    pub market_id: String,
+    pub outcome: OutcomeSide,
+}
+
+/// Query parameters for valuing a portfolio
+#[derive(Debug, Deserialize)]
+pub struct PortfolioQuery {
+    pub mark_source: Option<MarkPriceSource>,
+}
+
+/// Request to create a new event
+#[derive(Debug, Deserialize)]
+pub struct CreateEventRequest {
+    pub event_id: String,
+    pub name: String,
+    pub description: String,
+    pub starts_at: Option<DateTime<Utc>>,
+}
+
+/// Request to add existing markets to an event
+#[derive(Debug, Deserialize)]
+pub struct AddEventMarketsRequest {
+    pub market_ids: Vec<String>,
+}
+
+/// Request to withdraw funds
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalRequest {
+    pub amount: Decimal,
+    pub destination: String,
+}
+
+/// Request to approve or reject a withdrawal
+#[derive(Debug, Deserialize)]
+pub struct ReviewWithdrawalRequest {
+    pub note: Option<String>,
+}
+
+/// Query parameters for listing withdrawals
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalQuery {
+    pub status: Option<WithdrawalStatus>,
+}
+
+/// Request to start a deposit
+#[derive(Debug, Deserialize)]
+pub struct DepositRequest {
+    pub amount: Decimal,
+}
+
+/// Request to grant a user promotional credit
+#[derive(Debug, Deserialize)]
+pub struct GrantPromoCreditRequest {
+    pub user_id: Uuid,
+    pub amount: Decimal,
+    pub expires_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+/// Request to assign a user to a risk tier
+#[derive(Debug, Deserialize)]
+pub struct AssignRiskTierRequest {
+    pub tier: String,
+}
+
+/// Request to issue a user an API key
+#[derive(Debug, Deserialize)]
+pub struct IssueApiKeyRequest {
+    pub user_id: Uuid,
+    pub label: Option<String>,
+}
+
+/// Request to create another API key for the signed-in user
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub label: Option<String>,
+}
+
+/// Request to grant a user a role
+#[derive(Debug, Deserialize)]
+pub struct GrantRoleRequest {
+    pub role: Role,
+}
+
 /// Standard API response wrapper
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
@@ -81,26 +235,62 @@ impl<T> ApiResponse<T> {
 }
 
 /// Sets up all API routes
+#[allow(clippy::too_many_arguments)]
 pub fn routes<R: Repository + Send + Sync + 'static>(
     order_service: Arc<OrderService<R>>,
     bot_service: Arc<BotService<R>>,
     settlement_service: Arc<SettlementService<R>>,
+    amm_service: Arc<AmmService<R>>,
+    event_service: Arc<EventService<R>>,
+    position_service: Arc<PositionService<R>>,
+    portfolio_service: Arc<PortfolioService<R>>,
+    ledger_service: Arc<LedgerService<R>>,
+    idempotency_service: Arc<IdempotencyService<R>>,
+    reconciliation_service: Arc<ReconciliationService<R>>,
+    withdrawal_service: Arc<WithdrawalService<R>>,
+    deposit_service: Arc<DepositService<R>>,
+    promo_service: Arc<PromoService<R>>,
+    tax_service: Arc<TaxService<R>>,
+    risk_service: Arc<RiskService<R>>,
+    auth_service: Arc<AuthService<R>>,
+    rate_limiter: Arc<RateLimiter>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let api = warp::path("api");
     let markets = api.and(warp::path("markets"));
     let orders = api.and(warp::path("orders"));
-    let bots = api.and(warp::path("bots"));
+    let market_groups = api.and(warp::path("market-groups"));
+    let events = api.and(warp::path("events"));
+    let users = api.and(warp::path("users"));
+    let withdrawals = api.and(warp::path("withdrawals"));
+    let deposits = api.and(warp::path("deposits"));
+    let api_keys = api.and(warp::path("api-keys"));
+    
+    // Operations on the platform rather than a user's own account, each needing a role
+    let admin = api.and(warp::path("admin"));
+    let admin_markets = admin.and(warp::path("markets"));
+    let admin_market_groups = admin.and(warp::path("market-groups"));
+    let admin_events = admin.and(warp::path("events"));
+    let admin_bots = admin.and(warp::path("bots"));
+    let admin_ledger = admin.and(warp::path("ledger"));
+    let reconciliation = admin.and(warp::path("reconciliation"));
+    let admin_withdrawals = admin.and(warp::path("withdrawals"));
+    let admin_promo_credits = admin.and(warp::path("promo-credits"));
+    let admin_risk = admin.and(warp::path("risk"));
+    let admin_api_keys = admin.and(warp::path("api-keys"));
+    let admin_users = admin.and(warp::path("users"));
     
     // GET /api/markets - List all markets
     let list_markets = markets
+        .and(warp::path::end())
         .and(warp::get())
         .and(with_order_service(order_service.clone()))
         .and_then(handle_list_markets);
     
-    // POST /api/markets - Create a new market
-    let create_market = markets
+    // POST /api/admin/markets - Create a new market
+    let create_market = admin_markets
+        .and(warp::path::end())
         .and(warp::post())
-        .and(warp::body::json())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
         .and(with_order_service(order_service.clone()))
         .and_then(handle_create_market);
     
@@ -108,23 +298,182 @@ pub fn routes<R: Repository + Send + Sync + 'static>(
     let get_market = markets
         .and(warp::get())
         .and(warp::path::param::<String>())
+        .and(warp::path::end())
         .and(with_order_service(order_service.clone()))
         .and_then(handle_get_market);
     
-    // POST /api/markets/:id/resolve - Resolve a market
-    let resolve_market = markets
+    // POST /api/admin/markets/:id/resolve - Resolve a market
+    let resolve_market = admin_markets
         .and(warp::path::param::<String>())
         .and(warp::path("resolve"))
+        .and(warp::path::end())
         .and(warp::post())
-        .and(warp::body::json())
+        .and(authorized_json(auth_service.clone(), Role::Resolver))
         .and(with_order_service(order_service.clone()))
         .and(with_settlement_service(settlement_service.clone()))
         .and_then(handle_resolve_market);
     
+    // GET /api/markets/:id/settlement - Get the settlement report of a resolved market
+    let get_settlement_report = markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("settlement"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_settlement_service(settlement_service.clone()))
+        .and_then(handle_get_settlement_report);
+    
+    // POST /api/admin/markets/:id/pause - Pause trading on a market
+    let pause_market = admin_markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("pause"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_pause_market);
+    
+    // POST /api/admin/markets/:id/resume - Resume trading on a paused market
+    let resume_market = admin_markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("resume"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_resume_market);
+    
+    // POST /api/markets/:id/mint - Mint complete sets of a market's outcome shares
+    let mint_complete_sets = markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("mint"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_mint_complete_sets);
+    
+    // POST /api/markets/:id/redeem - Redeem complete sets of a market's outcome shares
+    let redeem_complete_sets = markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("redeem"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_redeem_complete_sets);
+    
+    // POST /api/admin/markets/:id/amm - Enable an automated market maker on a market
+    let enable_market_maker = admin_markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("amm"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_user_json(auth_service.clone(), Role::MarketMaker))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_enable_market_maker);
+    
+    // GET /api/markets/:id/amm - Get a market maker's inventory and risk
+    let get_market_maker = markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("amm"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_amm_service(amm_service.clone()))
+        .and_then(handle_get_market_maker);
+    
+    // GET /api/markets/:id/amm/quote - Get a price quote from a market maker
+    let quote_market_maker = markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("amm"))
+        .and(warp::path("quote"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<MarketMakerQuoteQuery>())
+        .and(with_amm_service(amm_service.clone()))
+        .and_then(handle_quote_market_maker);
+    
+    // POST /api/admin/market-groups - Create a group of mutually exclusive markets
+    let create_market_group = admin_market_groups
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_user_json(auth_service.clone(), Role::Operator))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_create_market_group);
+    
+    // GET /api/market-groups/:id - Get a market group by ID
+    let get_market_group = market_groups
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_get_market_group);
+    
+    // POST /api/market-groups/:id/convert - Convert No shares into Yes shares in sibling markets
+    let convert_shares = market_groups
+        .and(warp::path::param::<String>())
+        .and(warp::path("convert"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_convert_shares);
+    
+    // GET /api/events - List all events
+    let list_events = events
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_event_service(event_service.clone()))
+        .and_then(handle_list_events);
+    
+    // POST /api/admin/events - Create a new event
+    let create_event = admin_events
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_event_service(event_service.clone()))
+        .and_then(handle_create_event);
+    
+    // GET /api/events/:id - Get an event by ID
+    let get_event = events
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_event_service(event_service.clone()))
+        .and_then(handle_get_event);
+    
+    // GET /api/events/:id/status - Get the status of an event and its markets
+    let get_event_status = events
+        .and(warp::path::param::<String>())
+        .and(warp::path("status"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_event_service(event_service.clone()))
+        .and_then(handle_get_event_status);
+    
+    // POST /api/admin/events/:id/markets - Add existing markets to an event
+    let add_event_markets = admin_events
+        .and(warp::path::param::<String>())
+        .and(warp::path("markets"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_event_service(event_service.clone()))
+        .and_then(handle_add_event_markets);
+    
+    // POST /api/admin/events/:id/:action - Pause, resume, close or cancel an event and all its markets
+    let update_event_status = admin_events
+        .and(warp::path::param::<String>())
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_event_service(event_service.clone()))
+        .and_then(handle_update_event_status);
+    
     // POST /api/orders - Submit a new order
     let submit_order = orders
         .and(warp::post())
-        .and(warp::body::json())
+        .and(authenticated_json(auth_service.clone()))
         .and(with_order_service(order_service.clone()))
         .and_then(handle_submit_order);
     
@@ -132,10 +481,22 @@ pub fn routes<R: Repository + Send + Sync + 'static>(
     let cancel_order = orders
         .and(warp::delete())
         .and(warp::path::param::<Uuid>())
-        .and(warp::body::json())
+        .and(authenticated_json(auth_service.clone()))
         .and(with_order_service(order_service.clone()))
         .and_then(handle_cancel_order);
     
+    // GET /api/orders/user/:user_id/client/:client_order_id - Get a user's order by its client order ID
+    let get_order_by_client_id = orders
+        .and(warp::get())
+        .and(warp::path("user"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("client"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(authenticated(auth_service.clone()))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_get_order_by_client_id);
+    
     // GET /api/orders/user/:user_id/market/:market_id - Get user orders for a market
     let get_user_orders = orders
         .and(warp::get())
@@ -143,36 +504,430 @@ pub fn routes<R: Repository + Send + Sync + 'static>(
         .and(warp::path::param::<Uuid>())
         .and(warp::path("market"))
         .and(warp::path::param::<String>())
+        .and(authenticated(auth_service.clone()))
         .and(with_order_service(order_service.clone()))
         .and_then(handle_get_user_orders);
     
-    // POST /api/bots/start - Start a bot for a market
-    let start_bot = bots
+    // GET /api/users/:id/positions - Get a user's positions
+    let get_user_positions = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("positions"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_position_service(position_service.clone()))
+        .and_then(handle_get_user_positions);
+    
+    // GET /api/users/:id/portfolio - Value a user's positions and reserved funds
+    let get_user_portfolio = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("portfolio"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(warp::query::<PortfolioQuery>())
+        .and(with_portfolio_service(portfolio_service.clone()))
+        .and_then(handle_get_user_portfolio);
+    
+    // GET /api/users/:id/tax-statements - Get a user's tax statement for each financial year
+    let get_user_tax_statements = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("tax-statements"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_tax_service(tax_service.clone()))
+        .and_then(handle_get_user_tax_statements);
+    
+    // GET /api/users/:id/tax-statements/:year - Get a user's tax statement for the financial year starting in :year
+    let get_user_tax_statement = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("tax-statements"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_tax_service(tax_service.clone()))
+        .and_then(handle_get_user_tax_statement);
+    
+    // GET /api/admin/ledger/balances - Get the balance of every ledger account
+    let get_ledger_balances = admin_ledger
+        .and(warp::path("balances"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_ledger_service(ledger_service.clone()))
+        .and_then(handle_get_ledger_balances);
+    
+    // GET /api/admin/ledger/check - Check that the ledger conserves money and agrees with user balances
+    let check_ledger = admin_ledger
+        .and(warp::path("check"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_ledger_service(ledger_service.clone()))
+        .and_then(handle_check_ledger);
+    
+    // GET /api/markets/:id/escrow - Get what a market holds against what it could owe
+    let get_market_escrow = markets
+        .and(warp::path::param::<String>())
+        .and(warp::path("escrow"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_ledger_service(ledger_service.clone()))
+        .and_then(handle_get_market_escrow);
+    
+    // GET /api/users/:id/cash-out - Quote closing a position at current book prices
+    let quote_cash_out = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("cash-out"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(warp::query::<CashOutRequest>())
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_quote_cash_out);
+    
+    // POST /api/users/:id/cash-out - Close a position at current book prices
+    let cash_out = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("cash-out"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_order_service(order_service.clone()))
+        .and_then(handle_cash_out);
+    
+    // POST /api/admin/bots/start - Start a bot for a market
+    let start_bot = admin_bots
         .and(warp::path("start"))
+        .and(warp::path::end())
         .and(warp::post())
-        .and(warp::body::json())
+        .and(authorized_json(auth_service.clone(), Role::MarketMaker))
         .and(with_bot_service(bot_service.clone()))
         .and_then(handle_start_bot);
     
-    // POST /api/bots/stop/:market_id - Stop a bot for a market
-    let stop_bot = bots
+    // POST /api/admin/bots/stop/:market_id - Stop a bot for a market
+    let stop_bot = admin_bots
         .and(warp::path("stop"))
         .and(warp::path::param::<String>())
+        .and(warp::path::end())
         .and(warp::post())
+        .and(authorized(auth_service.clone(), Role::MarketMaker))
         .and(with_bot_service(bot_service.clone()))
         .and_then(handle_stop_bot);
     
-    // Combine all routes
-    list_markets
+    // POST /api/admin/reconciliation/run - Reconcile balances, orders and positions now
+    let run_reconciliation = reconciliation
+        .and(warp::path("run"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_reconciliation_service(reconciliation_service.clone()))
+        .and_then(handle_run_reconciliation);
+    
+    // GET /api/admin/reconciliation - Get the report of the last reconciliation
+    let get_reconciliation_report = reconciliation
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_reconciliation_service(reconciliation_service.clone()))
+        .and_then(handle_get_reconciliation_report);
+    
+    // GET /api/admin/reconciliation/metrics - Get reconciliation counters
+    let get_reconciliation_metrics = reconciliation
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_reconciliation_service(reconciliation_service.clone()))
+        .and_then(handle_get_reconciliation_metrics);
+    
+    // POST /api/withdrawals - Request a withdrawal
+    let request_withdrawal = withdrawals
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_withdrawal_service(withdrawal_service.clone()))
+        .and_then(handle_request_withdrawal);
+    
+    // GET /api/withdrawals/user/:user_id - Get a user's withdrawals
+    let get_user_withdrawals = withdrawals
+        .and(warp::path("user"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_withdrawal_service(withdrawal_service.clone()))
+        .and_then(handle_get_user_withdrawals);
+    
+    // GET /api/admin/withdrawals?status=Pending - List withdrawals for review
+    let list_withdrawals = admin_withdrawals
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(warp::query::<WithdrawalQuery>())
+        .and(with_withdrawal_service(withdrawal_service.clone()))
+        .and_then(handle_list_withdrawals);
+    
+    // POST /api/admin/withdrawals/:id/:action - Approve or reject a withdrawal
+    let review_withdrawal = admin_withdrawals
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_withdrawal_service(withdrawal_service.clone()))
+        .and_then(handle_review_withdrawal);
+    
+    // POST /api/deposits - Start a deposit with the payment provider
+    let create_deposit = deposits
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_deposit_service(deposit_service.clone()))
+        .and_then(handle_create_deposit);
+    
+    // POST /api/deposits/webhook - Payment provider callback, signed in the X-Signature header
+    let deposit_webhook = deposits
+        .and(warp::path("webhook"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::header::optional::<String>("x-signature"))
+        .and(warp::body::content_length_limit(64 * 1024))
+        .and(warp::body::bytes())
+        .and(with_deposit_service(deposit_service.clone()))
+        .and_then(handle_deposit_webhook);
+    
+    // GET /api/deposits/:id - Get a deposit
+    let get_deposit = deposits
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_deposit_service(deposit_service.clone()))
+        .and_then(handle_get_deposit);
+    
+    // GET /api/deposits/user/:user_id - Get a user's deposits
+    let get_user_deposits = deposits
+        .and(warp::path("user"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_deposit_service(deposit_service.clone()))
+        .and_then(handle_get_user_deposits);
+    
+    // POST /api/admin/promo-credits - Grant a user promotional credit until it expires
+    let grant_promo_credit = admin_promo_credits
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Admin))
+        .and(with_promo_service(promo_service.clone()))
+        .and_then(handle_grant_promo_credit);
+    
+    // GET /api/users/:id/promo-credits - Get the promotional credit granted to a user
+    let get_user_promo_credits = users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("promo-credits"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_promo_service(promo_service.clone()))
+        .and_then(handle_get_user_promo_credits);
+    
+    // GET /api/admin/risk/tiers - List risk tiers and their limits
+    let list_risk_tiers = admin_risk
+        .and(warp::path("tiers"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_risk_service(risk_service.clone()))
+        .and_then(handle_list_risk_tiers);
+    
+    // POST /api/admin/risk/tiers - Create a risk tier or replace its limits
+    let save_risk_tier = admin_risk
+        .and(warp::path("tiers"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_risk_service(risk_service.clone()))
+        .and_then(handle_save_risk_tier);
+    
+    // GET /api/admin/risk/users/:id - Get the risk tier a user is in
+    let get_user_risk_tier = admin_risk
+        .and(warp::path("users"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Operator))
+        .and(with_risk_service(risk_service.clone()))
+        .and_then(handle_get_user_risk_tier);
+    
+    // POST /api/admin/risk/users/:id - Assign a user to a risk tier
+    let assign_user_risk_tier = admin_risk
+        .and(warp::path("users"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_risk_service(risk_service.clone()))
+        .and_then(handle_assign_user_risk_tier);
+    
+    // POST /api/admin/risk/markets/:id - Set the risk limits for a market
+    let set_market_risk_limits = admin_risk
+        .and(warp::path("markets"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Operator))
+        .and(with_risk_service(risk_service.clone()))
+        .and_then(handle_set_market_risk_limits);
+    
+    // POST /api/admin/api-keys - Issue a user an API key
+    let issue_api_key = admin_api_keys
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Admin))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_issue_api_key);
+    
+    // POST /api/api-keys - Create another API key for the signed-in user
+    let create_api_key = api_keys
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authenticated_json(auth_service.clone()))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_create_api_key);
+    
+    // GET /api/api-keys - List the signed-in user's API keys
+    let list_api_keys = api_keys
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authenticated(auth_service.clone()))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_list_api_keys);
+    
+    // DELETE /api/api-keys/:key_id - Revoke one of the signed-in user's API keys
+    let revoke_api_key = api_keys
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(authenticated(auth_service.clone()))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_revoke_api_key);
+    
+    // GET /api/admin/users/:id/roles - Get the roles a user holds
+    let get_user_roles = admin_users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("roles"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(auth_service.clone(), Role::Admin))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_get_user_roles);
+    
+    // POST /api/admin/users/:id/roles - Grant a user a role
+    let grant_role = admin_users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("roles"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized_json(auth_service.clone(), Role::Admin))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_grant_role);
+    
+    // DELETE /api/admin/users/:id/roles/:role - Take a role away from a user
+    let revoke_role = admin_users
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("roles"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(authorized(auth_service.clone(), Role::Admin))
+        .and(with_auth_service(auth_service.clone()))
+        .and_then(handle_revoke_role);
+    
+    // Combine all routes, boxing each group so a request does not nest every route's future on the stack
+    let market_routes = list_markets
         .or(create_market)
         .or(get_market)
         .or(resolve_market)
-        .or(submit_order)
+        .or(get_settlement_report)
+        .or(pause_market)
+        .or(resume_market)
+        .or(mint_complete_sets)
+        .or(redeem_complete_sets)
+        .or(enable_market_maker)
+        .or(get_market_maker)
+        .or(quote_market_maker)
+        .or(create_market_group)
+        .or(get_market_group)
+        .or(convert_shares)
+        .or(list_events)
+        .or(create_event)
+        .or(get_event)
+        .or(get_event_status)
+        .or(add_event_markets)
+        .or(update_event_status)
+        .boxed();
+    
+    let trading_routes = submit_order
         .or(cancel_order)
+        .or(get_order_by_client_id)
         .or(get_user_orders)
+        .or(get_user_positions)
+        .or(get_user_portfolio)
+        .or(quote_cash_out)
+        .or(cash_out)
         .or(start_bot)
         .or(stop_bot)
-        .with(warp::log("api"))
+        .or(list_risk_tiers)
+        .or(save_risk_tier)
+        .or(get_user_risk_tier)
+        .or(assign_user_risk_tier)
+        .or(set_market_risk_limits)
+        .boxed();
+    
+    let funds_routes = get_ledger_balances
+        .or(check_ledger)
+        .or(get_market_escrow)
+        .or(request_withdrawal)
+        .or(get_user_withdrawals)
+        .or(list_withdrawals)
+        .or(review_withdrawal)
+        .or(create_deposit)
+        .or(deposit_webhook)
+        .or(get_deposit)
+        .or(get_user_deposits)
+        .or(grant_promo_credit)
+        .or(get_user_promo_credits)
+        .or(get_user_tax_statements)
+        .or(get_user_tax_statement)
+        .or(run_reconciliation)
+        .or(get_reconciliation_report)
+        .or(get_reconciliation_metrics)
+        .boxed();
+    
+    let account_routes = issue_api_key
+        .or(create_api_key)
+        .or(list_api_keys)
+        .or(revoke_api_key)
+        .or(get_user_roles)
+        .or(grant_role)
+        .or(revoke_role)
+        .boxed();
+    
+    // Requests turned away for their signature or user get an error response
+    let api_routes = market_routes
+        .or(trading_routes)
+        .or(funds_routes)
+        .or(account_routes)
+        .recover(handle_rejection);
+    
+    // Mutating requests with an idempotency key are run once and replayed on retry,
+    // once they are within their rate limits
+    rate_limited(idempotent(api_routes, idempotency_service, auth_service), rate_limiter).with(warp::log("api"))
 }
 
 // Helper function to extract the order service from the filter context
@@ -196,33 +951,147 @@ fn with_settlement_service<R: Repository + Send + Sync + 'static>(
     warp::any().map(move || settlement_service.clone())
 }
 
-// Handler for listing all markets
-async fn handle_list_markets<R: Repository + Send + Sync + 'static>(
-    order_service: Arc<OrderService<R>>,
-) -> Result<impl Reply, Rejection> {
-    // In a real implementation, this would fetch markets from a database
-    let markets: Vec<Market> = Vec::new();
-    Ok(warp::reply::json(&ApiResponse::success(markets)))
+// Helper function to extract the AMM service from the filter context
+fn with_amm_service<R: Repository + Send + Sync + 'static>(
+    amm_service: Arc<AmmService<R>>,
+) -> impl Filter<Extract = (Arc<AmmService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || amm_service.clone())
 }
 
-// Handler for creating a new market
-async fn handle_create_market<R: Repository + Send + Sync + 'static>(
-    req: CreateMarketRequest,
-    order_service: Arc<OrderService<R>>,
-) -> Result<impl Reply, Rejection> {
-    let market = Market::new(
-        req.market_id,
-        req.question,
-        req.description,
-        req.close_time,
-    );
-    
-    match order_service.create_market(market).await {
-        Ok(_) => Ok(warp::reply::json(&ApiResponse::<()>::success(()))),
-        Err(e) => {
-            error!("Failed to create market: {}", e);
-            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
-        }
+// Helper function to extract the event service from the filter context
+fn with_event_service<R: Repository + Send + Sync + 'static>(
+    event_service: Arc<EventService<R>>,
+) -> impl Filter<Extract = (Arc<EventService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || event_service.clone())
+}
+
+// Helper function to extract the position service from the filter context
+fn with_position_service<R: Repository + Send + Sync + 'static>(
+    position_service: Arc<PositionService<R>>,
+) -> impl Filter<Extract = (Arc<PositionService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || position_service.clone())
+}
+
+// Helper function to extract the portfolio service from the filter context
+fn with_portfolio_service<R: Repository + Send + Sync + 'static>(
+    portfolio_service: Arc<PortfolioService<R>>,
+) -> impl Filter<Extract = (Arc<PortfolioService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || portfolio_service.clone())
+}
+
+// Helper function to extract the ledger service from the filter context
+fn with_ledger_service<R: Repository + Send + Sync + 'static>(
+    ledger_service: Arc<LedgerService<R>>,
+) -> impl Filter<Extract = (Arc<LedgerService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || ledger_service.clone())
+}
+
+// Helper function to extract the withdrawal service from the filter context
+fn with_withdrawal_service<R: Repository + Send + Sync + 'static>(
+    withdrawal_service: Arc<WithdrawalService<R>>,
+) -> impl Filter<Extract = (Arc<WithdrawalService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || withdrawal_service.clone())
+}
+
+// Helper function to extract the deposit service from the filter context
+fn with_deposit_service<R: Repository + Send + Sync + 'static>(
+    deposit_service: Arc<DepositService<R>>,
+) -> impl Filter<Extract = (Arc<DepositService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || deposit_service.clone())
+}
+
+// Helper function to extract the promotional credit service from the filter context
+fn with_promo_service<R: Repository + Send + Sync + 'static>(
+    promo_service: Arc<PromoService<R>>,
+) -> impl Filter<Extract = (Arc<PromoService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || promo_service.clone())
+}
+
+// Helper function to extract the tax service from the filter context
+fn with_tax_service<R: Repository + Send + Sync + 'static>(
+    tax_service: Arc<TaxService<R>>,
+) -> impl Filter<Extract = (Arc<TaxService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || tax_service.clone())
+}
+
+// Helper function to extract the auth service from the filter context
+fn with_auth_service<R: Repository + Send + Sync + 'static>(
+    auth_service: Arc<AuthService<R>>,
+) -> impl Filter<Extract = (Arc<AuthService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || auth_service.clone())
+}
+
+// Helper function to extract the risk service from the filter context
+fn with_risk_service<R: Repository + Send + Sync + 'static>(
+    risk_service: Arc<RiskService<R>>,
+) -> impl Filter<Extract = (Arc<RiskService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || risk_service.clone())
+}
+
+// Helper function to extract the reconciliation service from the filter context
+fn with_reconciliation_service<R: Repository + Send + Sync + 'static>(
+    reconciliation_service: Arc<ReconciliationService<R>>,
+) -> impl Filter<Extract = (Arc<ReconciliationService<R>>,), Error = Infallible> + Clone {
+    warp::any().map(move || reconciliation_service.clone())
+}
+
+// Handler for listing all markets
+async fn handle_list_markets<R: Repository + Send + Sync + 'static>(
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    // In a real implementation, this would fetch markets from a database
+    let markets: Vec<Market> = Vec::new();
+    Ok(warp::reply::json(&ApiResponse::success(markets)))
+}
+
+// Handler for creating a new market
+async fn handle_create_market<R: Repository + Send + Sync + 'static>(
+    req: CreateMarketRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let mut market = match (req.market_type, req.outcomes) {
+        (Some(MarketType::Scalar), _) => {
+            let (lower_bound, upper_bound) = match (req.lower_bound, req.upper_bound) {
+                (Some(lower), Some(upper)) => (lower, upper),
+                _ => {
+                    return Ok(warp::reply::json(&ApiResponse::<()>::error(
+                        "Scalar markets need a lower_bound and an upper_bound".to_string()
+                    )));
+                }
+            };
+            Market::new_scalar(
+                req.market_id,
+                req.question,
+                req.description,
+                lower_bound,
+                upper_bound,
+                req.close_time,
+            )
+        }
+        (_, Some(outcomes)) => Market::new_categorical(
+            req.market_id,
+            req.question,
+            req.description,
+            outcomes,
+            req.close_time,
+        ),
+        (_, None) => Market::new(
+            req.market_id,
+            req.question,
+            req.description,
+            req.close_time,
+        ),
+    };
+    if let Some(allow_shorting) = req.allow_shorting {
+        market.allow_shorting = allow_shorting;
+    }
+    
+    match order_service.create_market(market).await {
+        Ok(_) => Ok(warp::reply::json(&ApiResponse::<()>::success(()))),
+        Err(e) => {
+            error!("Failed to create market: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
     }
 }
 
@@ -247,9 +1116,15 @@ async fn handle_resolve_market<R: Repository + Send + Sync + 'static>(
     _order_service: Arc<OrderService<R>>,
     settlement_service: Arc<SettlementService<R>>,
 ) -> Result<impl Reply, Rejection> {
-    match settlement_service.resolve_market(&market_id, req.outcome).await {
-        Ok(resolved_market) => {
-            Ok(warp::reply::json(&ApiResponse::success(resolved_market)))
+    let result = match (req.outcome, req.value) {
+        (_, Some(value)) => settlement_service.resolve_scalar_market(&market_id, value).await,
+        (Some(outcome), None) => settlement_service.resolve_market(&market_id, outcome).await,
+        (None, None) => Err("Either an outcome or a value is required".to_string()),
+    };
+    
+    match result {
+        Ok(report) => {
+            Ok(warp::reply::json(&ApiResponse::success(report)))
         }
         Err(e) => {
             error!("Failed to resolve market {}: {}", market_id, e);
@@ -258,19 +1133,300 @@ async fn handle_resolve_market<R: Repository + Send + Sync + 'static>(
     }
 }
 
+// Handler for getting the settlement report of a resolved market
+async fn handle_get_settlement_report<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    settlement_service: Arc<SettlementService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match settlement_service.get_settlement_report(&market_id).await {
+        Ok(report) => Ok(warp::reply::json(&ApiResponse::success(report))),
+        Err(e) => {
+            error!("Failed to get settlement report for market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e)))
+        }
+    }
+}
+
+// Handler for pausing a market
+async fn handle_pause_market<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    req: PauseMarketRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let policy = req.order_policy.unwrap_or(PauseOrderPolicy::KeepOrders);
+    
+    match order_service.pause_market(&market_id, policy).await {
+        Ok(result) => Ok(warp::reply::json(&ApiResponse::success(result))),
+        Err(e) => {
+            error!("Failed to pause market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for resuming a market
+async fn handle_resume_market<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match order_service.resume_market(&market_id).await {
+        Ok(market) => Ok(warp::reply::json(&ApiResponse::success(market))),
+        Err(e) => {
+            error!("Failed to resume market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Market>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for enabling an automated market maker on a market, funded by the signing user
+async fn handle_enable_market_maker<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    user: AuthenticatedUser,
+    req: EnableMarketMakerRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let liquidity_mode = req.liquidity_mode.unwrap_or(LiquidityMode::Hybrid);
+    
+    match order_service.enable_market_maker(&market_id, req.liquidity, user.user_id, liquidity_mode).await {
+        Ok(market) => Ok(warp::reply::json(&ApiResponse::success(market))),
+        Err(e) => {
+            error!("Failed to enable market maker on market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Market>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a market maker's state
+async fn handle_get_market_maker<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    amm_service: Arc<AmmService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match amm_service.get_summary(&market_id).await {
+        Ok(summary) => Ok(warp::reply::json(&ApiResponse::success(summary))),
+        Err(e) => {
+            error!("Failed to get market maker for market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for quoting a trade against a market maker
+async fn handle_quote_market_maker<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    query: MarketMakerQuoteQuery,
+    amm_service: Arc<AmmService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match amm_service.quote(&market_id, query.outcome, query.side, query.quantity).await {
+        Ok(quote) => Ok(warp::reply::json(&ApiResponse::success(quote))),
+        Err(e) => {
+            error!("Failed to quote market maker for market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for creating a market group, with the signing user as its house account
+async fn handle_create_market_group<R: Repository + Send + Sync + 'static>(
+    user: AuthenticatedUser,
+    req: CreateMarketGroupRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let group = MarketGroup::new(
+        req.group_id,
+        req.name,
+        req.description,
+        req.market_ids,
+        user.user_id,
+    );
+    
+    match order_service.create_market_group(group).await {
+        Ok(group) => Ok(warp::reply::json(&ApiResponse::success(group))),
+        Err(e) => {
+            error!("Failed to create market group: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a market group by ID
+async fn handle_get_market_group<R: Repository + Send + Sync + 'static>(
+    group_id: String,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match order_service.get_market_group(&group_id).await {
+        Ok(group) => Ok(warp::reply::json(&ApiResponse::success(group))),
+        Err(e) => {
+            error!("Failed to get market group {}: {}", group_id, e);
+            Ok(warp::reply::json(&ApiResponse::<MarketGroup>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for minting complete sets
+async fn handle_mint_complete_sets<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    user: AuthenticatedUser,
+    req: CompleteSetRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match order_service.mint_complete_sets(&market_id, user.user_id, req.quantity).await {
+        Ok(operation) => Ok(warp::reply::json(&ApiResponse::success(operation))),
+        Err(e) => {
+            error!("Failed to mint complete sets in market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for redeeming complete sets
+async fn handle_redeem_complete_sets<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    user: AuthenticatedUser,
+    req: CompleteSetRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match order_service.redeem_complete_sets(&market_id, user.user_id, req.quantity).await {
+        Ok(operation) => Ok(warp::reply::json(&ApiResponse::success(operation))),
+        Err(e) => {
+            error!("Failed to redeem complete sets in market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for converting No shares into Yes shares across a market group
+async fn handle_convert_shares<R: Repository + Send + Sync + 'static>(
+    group_id: String,
+    user: AuthenticatedUser,
+    req: ConvertSharesRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match order_service.convert_no_to_yes(&group_id, user.user_id, &req.market_id, req.quantity).await {
+        Ok(conversion) => Ok(warp::reply::json(&ApiResponse::success(conversion))),
+        Err(e) => {
+            error!("Failed to convert shares in group {}: {}", group_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for listing all events
+async fn handle_list_events<R: Repository + Send + Sync + 'static>(
+    event_service: Arc<EventService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match event_service.get_all_events().await {
+        Ok(events) => Ok(warp::reply::json(&ApiResponse::success(events))),
+        Err(e) => {
+            error!("Failed to list events: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Event>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for creating a new event
+async fn handle_create_event<R: Repository + Send + Sync + 'static>(
+    req: CreateEventRequest,
+    event_service: Arc<EventService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let event = Event::new(req.event_id, req.name, req.description, req.starts_at);
+    
+    match event_service.create_event(event).await {
+        Ok(event) => Ok(warp::reply::json(&ApiResponse::success(event))),
+        Err(e) => {
+            error!("Failed to create event: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<Event>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting an event by ID
+async fn handle_get_event<R: Repository + Send + Sync + 'static>(
+    event_id: String,
+    event_service: Arc<EventService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match event_service.get_event(&event_id).await {
+        Ok(event) => Ok(warp::reply::json(&ApiResponse::success(event))),
+        Err(e) => {
+            error!("Failed to get event {}: {}", event_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Event>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting the status of an event and its markets
+async fn handle_get_event_status<R: Repository + Send + Sync + 'static>(
+    event_id: String,
+    event_service: Arc<EventService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match event_service.get_event_status(&event_id).await {
+        Ok(summary) => Ok(warp::reply::json(&ApiResponse::success(summary))),
+        Err(e) => {
+            error!("Failed to get status of event {}: {}", event_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for adding markets to an event
+async fn handle_add_event_markets<R: Repository + Send + Sync + 'static>(
+    event_id: String,
+    req: AddEventMarketsRequest,
+    event_service: Arc<EventService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match event_service.add_markets(&event_id, &req.market_ids).await {
+        Ok(summary) => Ok(warp::reply::json(&ApiResponse::success(summary))),
+        Err(e) => {
+            error!("Failed to add markets to event {}: {}", event_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for pausing, resuming, closing or cancelling an event
+async fn handle_update_event_status<R: Repository + Send + Sync + 'static>(
+    event_id: String,
+    action: String,
+    event_service: Arc<EventService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let result = match action.as_str() {
+        "pause" => event_service.pause_event(&event_id).await,
+        "resume" => event_service.resume_event(&event_id).await,
+        "close" => event_service.close_event(&event_id).await,
+        "cancel" => event_service.cancel_event(&event_id).await,
+        _ => {
+            return Ok(warp::reply::json(&ApiResponse::<()>::error(
+                format!("Unknown event action: {}", action)
+            )));
+        }
+    };
+    
+    match result {
+        Ok(summary) => Ok(warp::reply::json(&ApiResponse::success(summary))),
+        Err(e) => {
+            error!("Failed to {} event {}: {}", action, event_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
 // Handler for submitting a new order
 async fn handle_submit_order<R: Repository + Send + Sync + 'static>(
+    user: AuthenticatedUser,
     req: SubmitOrderRequest,
     order_service: Arc<OrderService<R>>,
 ) -> Result<impl Reply, Rejection> {
-    let order = Order::new(
-        req.user_id,
+    let mut order = Order::new(
+        user.user_id,
         req.market_id,
         req.side,
         req.outcome,
         req.price,
         req.quantity,
     );
+    if let Some(time_in_force) = req.time_in_force {
+        order.time_in_force = time_in_force;
+    }
+    order.client_order_id = req.client_order_id;
     
     match order_service.submit_order(order).await {
         Ok(result) => Ok(warp::reply::json(&ApiResponse::success(result))),
@@ -284,9 +1440,18 @@ async fn handle_submit_order<R: Repository + Send + Sync + 'static>(
 // Handler for cancelling an order
 async fn handle_cancel_order<R: Repository + Send + Sync + 'static>(
     order_id: Uuid,
+    user: AuthenticatedUser,
     _req: CancelOrderRequest,
     order_service: Arc<OrderService<R>>,
 ) -> Result<impl Reply, Rejection> {
+    match order_service.get_order(order_id).await {
+        Ok(order) => user.authorize(order.user_id)?,
+        Err(e) => {
+            error!("Failed to get order {}: {}", order_id, e);
+            return Ok(warp::reply::json(&ApiResponse::<Order>::error(e.to_string())));
+        }
+    }
+    
     match order_service.cancel_order(order_id).await {
         Ok(order) => Ok(warp::reply::json(&ApiResponse::success(order))),
         Err(e) => {
@@ -296,12 +1461,33 @@ async fn handle_cancel_order<R: Repository + Send + Sync + 'static>(
     }
 }
 
+// Handler for getting a user's order by its client order ID
+async fn handle_get_order_by_client_id<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    client_order_id: String,
+    user: AuthenticatedUser,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match order_service.get_order_by_client_order_id(user_id, &client_order_id).await {
+        Ok(order) => Ok(warp::reply::json(&ApiResponse::success(order))),
+        Err(e) => {
+            error!("Failed to get order {} for user {}: {}", client_order_id, user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Order>::error(e.to_string())))
+        }
+    }
+}
+
 // Handler for getting user orders for a market
 async fn handle_get_user_orders<R: Repository + Send + Sync + 'static>(
     user_id: Uuid,
     market_id: String,
+    user: AuthenticatedUser,
     order_service: Arc<OrderService<R>>,
 ) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
     match order_service.get_orders_for_user(&market_id, user_id).await {
         Ok(orders) => Ok(warp::reply::json(&ApiResponse::success(orders))),
         Err(e) => {
@@ -311,6 +1497,355 @@ async fn handle_get_user_orders<R: Repository + Send + Sync + 'static>(
     }
 }
 
+// Handler for getting a user's positions
+async fn handle_get_user_positions<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    position_service: Arc<PositionService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match position_service.get_positions_for_user(user_id).await {
+        Ok(positions) => Ok(warp::reply::json(&ApiResponse::success(positions))),
+        Err(e) => {
+            error!("Failed to get positions for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Position>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for valuing a user's portfolio
+async fn handle_get_user_portfolio<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    query: PortfolioQuery,
+    portfolio_service: Arc<PortfolioService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match portfolio_service.get_portfolio(user_id, query.mark_source).await {
+        Ok(portfolio) => Ok(warp::reply::json(&ApiResponse::success(portfolio))),
+        Err(e) => {
+            error!("Failed to get portfolio for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Portfolio>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a user's tax statements
+async fn handle_get_user_tax_statements<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    tax_service: Arc<TaxService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match tax_service.get_tax_statements(user_id).await {
+        Ok(statements) => Ok(warp::reply::json(&ApiResponse::success(statements))),
+        Err(e) => {
+            error!("Failed to get tax statements for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<TaxStatement>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a user's tax statement for one financial year
+async fn handle_get_user_tax_statement<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    financial_year: i32,
+    user: AuthenticatedUser,
+    tax_service: Arc<TaxService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match tax_service.get_tax_statement(user_id, financial_year).await {
+        Ok(statement) => Ok(warp::reply::json(&ApiResponse::success(statement))),
+        Err(e) => {
+            error!("Failed to get tax statement for user {} for {}: {}", user_id, financial_year, e);
+            Ok(warp::reply::json(&ApiResponse::<TaxStatement>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting ledger account balances
+async fn handle_get_ledger_balances<R: Repository + Send + Sync + 'static>(
+    ledger_service: Arc<LedgerService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match ledger_service.get_account_balances().await {
+        Ok(balances) => Ok(warp::reply::json(&ApiResponse::success(balances))),
+        Err(e) => {
+            error!("Failed to get ledger balances: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<LedgerBalance>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for checking the ledger invariants
+async fn handle_check_ledger<R: Repository + Send + Sync + 'static>(
+    ledger_service: Arc<LedgerService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match ledger_service.check_invariants().await {
+        Ok(check) => Ok(warp::reply::json(&ApiResponse::success(check))),
+        Err(e) => {
+            error!("Failed to check ledger: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<LedgerCheck>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for requesting a withdrawal
+async fn handle_request_withdrawal<R: Repository + Send + Sync + 'static>(
+    user: AuthenticatedUser,
+    req: WithdrawalRequest,
+    withdrawal_service: Arc<WithdrawalService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match withdrawal_service.request_withdrawal(user.user_id, req.amount, req.destination).await {
+        Ok(withdrawal) => Ok(warp::reply::json(&ApiResponse::success(withdrawal))),
+        Err(e) => {
+            error!("Failed to request withdrawal for user {}: {}", user.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Withdrawal>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a user's withdrawals
+async fn handle_get_user_withdrawals<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    withdrawal_service: Arc<WithdrawalService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match withdrawal_service.get_withdrawals_for_user(user_id).await {
+        Ok(withdrawals) => Ok(warp::reply::json(&ApiResponse::success(withdrawals))),
+        Err(e) => {
+            error!("Failed to get withdrawals for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Withdrawal>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for listing withdrawals for review
+async fn handle_list_withdrawals<R: Repository + Send + Sync + 'static>(
+    query: WithdrawalQuery,
+    withdrawal_service: Arc<WithdrawalService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match withdrawal_service.get_withdrawals(query.status).await {
+        Ok(withdrawals) => Ok(warp::reply::json(&ApiResponse::success(withdrawals))),
+        Err(e) => {
+            error!("Failed to list withdrawals: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Withdrawal>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for approving or rejecting a withdrawal
+async fn handle_review_withdrawal<R: Repository + Send + Sync + 'static>(
+    withdrawal_id: Uuid,
+    action: String,
+    req: ReviewWithdrawalRequest,
+    withdrawal_service: Arc<WithdrawalService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let result = match action.as_str() {
+        "approve" => withdrawal_service.approve_withdrawal(withdrawal_id, req.note).await,
+        "reject" => withdrawal_service.reject_withdrawal(withdrawal_id, req.note).await,
+        _ => return Err(warp::reject::not_found()),
+    };
+    
+    match result {
+        Ok(withdrawal) => Ok(warp::reply::json(&ApiResponse::success(withdrawal))),
+        Err(e) => {
+            error!("Failed to {} withdrawal {}: {}", action, withdrawal_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Withdrawal>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for starting a deposit
+async fn handle_create_deposit<R: Repository + Send + Sync + 'static>(
+    user: AuthenticatedUser,
+    req: DepositRequest,
+    deposit_service: Arc<DepositService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match deposit_service.create_deposit(user.user_id, req.amount).await {
+        Ok(deposit) => Ok(warp::reply::json(&ApiResponse::success(deposit))),
+        Err(e) => {
+            error!("Failed to start deposit for user {}: {}", user.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Deposit>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for the payment provider's webhook
+//
+// Anything but a success status tells the provider to deliver the webhook again.
+async fn handle_deposit_webhook<R: Repository + Send + Sync + 'static>(
+    signature: Option<String>,
+    body: warp::hyper::body::Bytes,
+    deposit_service: Arc<DepositService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let Some(signature) = signature else {
+        let reply = warp::reply::json(&ApiResponse::<Deposit>::error("Missing webhook signature".to_string()));
+        return Ok(warp::reply::with_status(reply, StatusCode::UNAUTHORIZED));
+    };
+    
+    match deposit_service.handle_callback(&body, &signature).await {
+        Ok(deposit) => Ok(warp::reply::with_status(warp::reply::json(&ApiResponse::success(deposit)), StatusCode::OK)),
+        Err(e) => {
+            error!("Failed to handle deposit webhook: {}", e);
+            Ok(warp::reply::with_status(warp::reply::json(&ApiResponse::<Deposit>::error(e.to_string())), StatusCode::BAD_REQUEST))
+        }
+    }
+}
+
+// Handler for getting a deposit
+async fn handle_get_deposit<R: Repository + Send + Sync + 'static>(
+    deposit_id: Uuid,
+    user: AuthenticatedUser,
+    deposit_service: Arc<DepositService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match deposit_service.get_deposit(deposit_id).await {
+        Ok(deposit) => {
+            user.authorize(deposit.user_id)?;
+            Ok(warp::reply::json(&ApiResponse::success(deposit)))
+        }
+        Err(e) => {
+            error!("Failed to get deposit {}: {}", deposit_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Deposit>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a user's deposits
+async fn handle_get_user_deposits<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    deposit_service: Arc<DepositService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match deposit_service.get_deposits_for_user(user_id).await {
+        Ok(deposits) => Ok(warp::reply::json(&ApiResponse::success(deposits))),
+        Err(e) => {
+            error!("Failed to get deposits for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Deposit>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for granting promotional credit
+async fn handle_grant_promo_credit<R: Repository + Send + Sync + 'static>(
+    req: GrantPromoCreditRequest,
+    promo_service: Arc<PromoService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let reason = req.reason.unwrap_or_else(|| "Promotional credit".to_string());
+    match promo_service.grant_promo_credit(req.user_id, req.amount, reason, req.expires_at).await {
+        Ok(credit) => Ok(warp::reply::json(&ApiResponse::success(credit))),
+        Err(e) => {
+            error!("Failed to grant promotional credit to user {}: {}", req.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<PromoCredit>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a user's promotional credit
+async fn handle_get_user_promo_credits<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    promo_service: Arc<PromoService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match promo_service.get_promo_credits_for_user(user_id).await {
+        Ok(credits) => Ok(warp::reply::json(&ApiResponse::success(credits))),
+        Err(e) => {
+            error!("Failed to get promotional credit for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<PromoCredit>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for running reconciliation on demand
+async fn handle_run_reconciliation<R: Repository + Send + Sync + 'static>(
+    reconciliation_service: Arc<ReconciliationService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match reconciliation_service.run(true).await {
+        Ok(report) => Ok(warp::reply::json(&ApiResponse::success(report))),
+        Err(e) => {
+            error!("Failed to run reconciliation: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<ReconciliationReport>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting the last reconciliation report
+async fn handle_get_reconciliation_report<R: Repository + Send + Sync + 'static>(
+    reconciliation_service: Arc<ReconciliationService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match reconciliation_service.get_last_report().await {
+        Ok(report) => Ok(warp::reply::json(&ApiResponse::success(report))),
+        Err(e) => Ok(warp::reply::json(&ApiResponse::<ReconciliationReport>::error(e.to_string()))),
+    }
+}
+
+// Handler for getting reconciliation counters
+async fn handle_get_reconciliation_metrics<R: Repository + Send + Sync + 'static>(
+    reconciliation_service: Arc<ReconciliationService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let metrics = reconciliation_service.get_metrics().await;
+    Ok(warp::reply::json(&ApiResponse::success(metrics)))
+}
+
+// Handler for getting a market's escrow
+async fn handle_get_market_escrow<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    ledger_service: Arc<LedgerService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match ledger_service.get_market_escrow(&market_id).await {
+        Ok(escrow) => Ok(warp::reply::json(&ApiResponse::success(escrow))),
+        Err(e) => {
+            error!("Failed to get escrow for market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<MarketEscrow>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for quoting a cash-out
+async fn handle_quote_cash_out<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    query: CashOutRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match order_service.quote_cash_out(user_id, &query.market_id, query.outcome).await {
+        Ok(quote) => Ok(warp::reply::json(&ApiResponse::success(quote))),
+        Err(e) => {
+            error!("Failed to quote cash-out for user {} in market {}: {}", user_id, query.market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for cashing out a position
+async fn handle_cash_out<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    user: AuthenticatedUser,
+    req: CashOutRequest,
+    order_service: Arc<OrderService<R>>,
+) -> Result<impl Reply, Rejection> {
+    user.authorize(user_id)?;
+    
+    match order_service.cash_out(user_id, &req.market_id, req.outcome).await {
+        Ok(cash_out) => Ok(warp::reply::json(&ApiResponse::success(cash_out))),
+        Err(e) => {
+            error!("Failed to cash out user {} in market {}: {}", user_id, req.market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
+        }
+    }
+}
+
 // Handler for starting a bot for a market
 async fn handle_start_bot<R: Repository + Send + Sync + 'static>(
     req: StartBotRequest,
@@ -348,4 +1883,183 @@ async fn handle_stop_bot<R: Repository + Send + Sync + 'static>(
             Ok(warp::reply::json(&ApiResponse::<()>::error(e.to_string())))
         }
     }
-} 
\ No newline at end of file
+} 
+
+// Handler for listing risk tiers
+async fn handle_list_risk_tiers<R: Repository + Send + Sync + 'static>(
+    risk_service: Arc<RiskService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match risk_service.get_tiers().await {
+        Ok(tiers) => Ok(warp::reply::json(&ApiResponse::success(tiers))),
+        Err(e) => {
+            error!("Failed to get risk tiers: {}", e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<RiskTier>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for creating or updating a risk tier
+async fn handle_save_risk_tier<R: Repository + Send + Sync + 'static>(
+    tier: RiskTier,
+    risk_service: Arc<RiskService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let name = tier.name.clone();
+    match risk_service.save_tier(tier).await {
+        Ok(tier) => Ok(warp::reply::json(&ApiResponse::success(tier))),
+        Err(e) => {
+            error!("Failed to save risk tier {}: {}", name, e);
+            Ok(warp::reply::json(&ApiResponse::<RiskTier>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting a user's risk tier
+async fn handle_get_user_risk_tier<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    risk_service: Arc<RiskService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match risk_service.get_user_tier(user_id).await {
+        Ok(tier) => Ok(warp::reply::json(&ApiResponse::success(tier))),
+        Err(e) => {
+            error!("Failed to get risk tier for user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<RiskTier>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for assigning a user to a risk tier
+async fn handle_assign_user_risk_tier<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    req: AssignRiskTierRequest,
+    risk_service: Arc<RiskService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match risk_service.assign_user_tier(user_id, &req.tier).await {
+        Ok(tier) => Ok(warp::reply::json(&ApiResponse::success(tier))),
+        Err(e) => {
+            error!("Failed to assign user {} to risk tier {}: {}", user_id, req.tier, e);
+            Ok(warp::reply::json(&ApiResponse::<RiskTier>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for setting a market's risk limits
+async fn handle_set_market_risk_limits<R: Repository + Send + Sync + 'static>(
+    market_id: String,
+    limits: RiskLimits,
+    risk_service: Arc<RiskService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match risk_service.set_market_limits(&market_id, limits).await {
+        Ok(limits) => Ok(warp::reply::json(&ApiResponse::success(limits))),
+        Err(e) => {
+            error!("Failed to set risk limits for market {}: {}", market_id, e);
+            Ok(warp::reply::json(&ApiResponse::<RiskLimits>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for issuing a user an API key
+async fn handle_issue_api_key<R: Repository + Send + Sync + 'static>(
+    req: IssueApiKeyRequest,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match auth_service.issue_api_key(req.user_id, req.label).await {
+        Ok(api_key) => Ok(warp::reply::json(&ApiResponse::success(api_key))),
+        Err(e) => {
+            error!("Failed to issue API key to user {}: {}", req.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<IssuedApiKey>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for creating another API key for the signed-in user
+async fn handle_create_api_key<R: Repository + Send + Sync + 'static>(
+    user: AuthenticatedUser,
+    req: CreateApiKeyRequest,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match auth_service.issue_api_key(user.user_id, req.label).await {
+        Ok(api_key) => Ok(warp::reply::json(&ApiResponse::success(api_key))),
+        Err(e) => {
+            error!("Failed to create API key for user {}: {}", user.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<IssuedApiKey>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for listing the signed-in user's API keys
+async fn handle_list_api_keys<R: Repository + Send + Sync + 'static>(
+    user: AuthenticatedUser,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match auth_service.get_api_keys_for_user(user.user_id).await {
+        Ok(api_keys) => Ok(warp::reply::json(&ApiResponse::success(api_keys))),
+        Err(e) => {
+            error!("Failed to get API keys for user {}: {}", user.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<ApiKey>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for revoking one of the signed-in user's API keys
+async fn handle_revoke_api_key<R: Repository + Send + Sync + 'static>(
+    key_id: String,
+    user: AuthenticatedUser,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match auth_service.revoke_api_key(user.user_id, &key_id).await {
+        Ok(api_key) => Ok(warp::reply::json(&ApiResponse::success(api_key))),
+        Err(e) => {
+            error!("Failed to revoke API key {} of user {}: {}", key_id, user.user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<ApiKey>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for getting the roles a user holds
+async fn handle_get_user_roles<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match auth_service.get_roles(user_id).await {
+        Ok(roles) => Ok(warp::reply::json(&ApiResponse::success(roles))),
+        Err(e) => {
+            error!("Failed to get roles of user {}: {}", user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Role>>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for granting a user a role
+async fn handle_grant_role<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    req: GrantRoleRequest,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    match auth_service.grant_role(user_id, req.role).await {
+        Ok(grant) => Ok(warp::reply::json(&ApiResponse::success(grant))),
+        Err(e) => {
+            error!("Failed to grant {:?} role to user {}: {}", req.role, user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<RoleGrant>::error(e.to_string())))
+        }
+    }
+}
+
+// Handler for taking a role away from a user
+async fn handle_revoke_role<R: Repository + Send + Sync + 'static>(
+    user_id: Uuid,
+    role: String,
+    auth_service: Arc<AuthService<R>>,
+) -> Result<impl Reply, Rejection> {
+    let role = match role.parse::<Role>() {
+        Ok(role) => role,
+        Err(e) => return Ok(warp::reply::json(&ApiResponse::<Vec<Role>>::error(e))),
+    };
+    
+    match auth_service.revoke_role(user_id, role).await {
+        Ok(roles) => Ok(warp::reply::json(&ApiResponse::success(roles))),
+        Err(e) => {
+            error!("Failed to revoke {:?} role of user {}: {}", role, user_id, e);
+            Ok(warp::reply::json(&ApiResponse::<Vec<Role>>::error(e.to_string())))
+        }
+    }
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use futures::{StreamExt, SinkExt};
 use log::{debug, info, error};
@@ -9,7 +10,8 @@ use warp::ws::{Message, WebSocket};
 use uuid::Uuid;
 use rust_decimal::Decimal;
 
-use crate::models::{Order, Trade, OutcomeSide};
+use crate::api::rate_limit::{RateBudget, RateKey, RateLimiter};
+use crate::models::{EventStatusSummary, MarketStatus, Order, Trade, OutcomeSide};
 
 /// Types of events that can be sent over WebSocket
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +27,12 @@ pub enum WebSocketEvent {
         price: Decimal,
     },
     
+    /// Market status changed (paused, resumed, closed or cancelled)
+    MarketStatus {
+        market_id: String,
+        status: MarketStatus,
+    },
+    
     /// Market resolved
     MarketResolution {
         market_id: String,
@@ -39,6 +47,14 @@ pub enum WebSocketEvent {
     
     /// Order status update
     OrderUpdate(Order),
+    
+    /// Event status update, covering the status of each of its markets
+    EventStatus(EventStatusSummary),
+    
+    /// Sent to a client in place of handling a message that is over its rate limit
+    Throttled {
+        retry_after_ms: u64,
+    },
 }
 
 /// Client subscription for a WebSocket connection
@@ -47,8 +63,14 @@ struct ClientSubscription {
     /// Markets the client is subscribed to
     markets: HashSet<String>,
     
+    /// Events the client is subscribed to (covers all of their markets)
+    events: HashSet<String>,
+    
     /// Whether the client is subscribed to user-specific events
     user_id: Option<Uuid>,
+    
+    /// User the connection was signed for, the only one whose events it can subscribe to
+    authenticated_user: Option<Uuid>,
 }
 
 /// WebSocket server for real-time notifications
@@ -58,16 +80,40 @@ pub struct WebSocketServer {
     
     /// Client subscriptions
     clients: Arc<RwLock<HashMap<Uuid, ClientSubscription>>>,
+    
+    /// Event that owns each market, for routing market events to event subscribers
+    market_events: Arc<RwLock<HashMap<String, String>>>,
+    
+    /// Limits how fast clients can send messages
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl ClientSubscription {
+    /// Checks if the client follows a market directly or through its event
+    fn follows_market(&self, market_id: &str, market_events: &HashMap<String, String>) -> bool {
+        self.markets.contains(market_id) ||
+            market_events.get(market_id).is_some_and(|event_id| self.events.contains(event_id))
+    }
 }
 
 impl WebSocketServer {
     /// Creates a new WebSocket server
-    pub fn new(capacity: usize) -> Self {
+    pub fn new(capacity: usize, rate_limiter: Arc<RateLimiter>) -> Self {
         let (event_sender, _) = broadcast::channel(capacity);
         
         Self {
             event_sender,
             clients: Arc::new(RwLock::new(HashMap::new())),
+            market_events: Arc::new(RwLock::new(HashMap::new())),
+            rate_limiter,
+        }
+    }
+    
+    /// Records which event owns each of the given markets
+    pub async fn register_event_markets(&self, event_id: &str, market_ids: &[String]) {
+        let mut market_events = self.market_events.write().await;
+        for market_id in market_ids {
+            market_events.insert(market_id.clone(), event_id.to_string());
         }
     }
     
@@ -124,37 +170,12 @@ impl WebSocketServer {
         // Get the market from repository
         let market = repository.get_market(market_id).await
             .map_err(|e| format!("Failed to get market: {}", e))?;
-            
-        // Get the implied probability/price from the order book
-        let price = market.get_implied_probability()
-            .unwrap_or_else(|| {
-                // Fallback to mid price calculation if no implied probability available
-                let (best_bid, best_ask) = match outcome {
-                    OutcomeSide::Yes => (
-                        market.order_book.get_best_yes_bid_price(),
-                        market.order_book.get_best_yes_ask_price()
-                    ),
-                    OutcomeSide::No => (
-                        market.order_book.get_best_no_bid_price(),
-                        market.order_book.get_best_no_ask_price()
-                    ),
-                };
-                
-                // Calculate mid price if both bid and ask exist
-                if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
-                    (bid + ask) / Decimal::from(2)
-                } else if let Some(bid) = best_bid {
-                    // Only bid exists
-                    bid
-                } else if let Some(ask) = best_ask {
-                    // Only ask exists
-                    ask
-                } else {
-                    // No orders, use default 0.5 probability
-                    Decimal::new(5, 1)
-                }
-            });
-            
+        
+        // Get the mid price for the outcome from the order book, defaulting
+        // to an even split across outcomes when there are no orders
+        let price = market.order_book.get_mid_price(outcome)
+            .unwrap_or_else(|| Decimal::ONE / Decimal::from(market.num_outcomes().max(1) as u32));
+        
         Ok(price)
     }
     
@@ -199,6 +220,28 @@ impl WebSocketServer {
         tx
     }
     
+    /// Gets a receiver for the market status channel
+    pub fn get_market_status_receiver(&self) -> mpsc::Sender<(String, MarketStatus)> {
+        let event_sender = self.event_sender.clone();
+        
+        let (tx, mut rx) = mpsc::channel::<(String, MarketStatus)>(100);
+        
+        tokio::spawn(async move {
+            while let Some((market_id, status)) = rx.recv().await {
+                let event = WebSocketEvent::MarketStatus {
+                    market_id,
+                    status,
+                };
+                
+                if let Err(e) = event_sender.send(event) {
+                    error!("Failed to broadcast market status: {}", e);
+                }
+            }
+        });
+        
+        tx
+    }
+    
     /// Gets a receiver for the market resolution channel
     pub fn get_market_resolution_receiver(&self) -> mpsc::Sender<(String, OutcomeSide)> {
         let event_sender = self.event_sender.clone();
@@ -221,8 +264,34 @@ impl WebSocketServer {
         tx
     }
     
+    /// Gets a receiver for the event status channel
+    pub fn get_event_update_receiver(&self) -> mpsc::Sender<EventStatusSummary> {
+        let event_sender = self.event_sender.clone();
+        let market_events = Arc::clone(&self.market_events);
+        
+        let (tx, mut rx) = mpsc::channel::<EventStatusSummary>(100);
+        
+        tokio::spawn(async move {
+            while let Some(summary) = rx.recv().await {
+                // Keep the market to event routing up to date
+                {
+                    let mut market_events = market_events.write().await;
+                    for market in &summary.markets {
+                        market_events.insert(market.market_id.clone(), summary.event_id.clone());
+                    }
+                }
+                
+                if let Err(e) = event_sender.send(WebSocketEvent::EventStatus(summary)) {
+                    error!("Failed to broadcast event status: {}", e);
+                }
+            }
+        });
+        
+        tx
+    }
+    
     /// Handles a new WebSocket connection
-    pub async fn handle_connection(&self, ws: WebSocket) {
+    pub async fn handle_connection(&self, ws: WebSocket, remote: Option<SocketAddr>, authenticated_user: Option<Uuid>) {
         let client_id = Uuid::new_v4();
         info!("New WebSocket connection: {}", client_id);
         
@@ -232,7 +301,9 @@ impl WebSocketServer {
         // Create a new subscription for this client
         let subscription = ClientSubscription {
             markets: HashSet::new(),
+            events: HashSet::new(),
             user_id: None,
+            authenticated_user,
         };
         
         // Add the client to the map
@@ -244,33 +315,53 @@ impl WebSocketServer {
         // Subscribe to events
         let event_rx = self.event_sender.subscribe();
         
+        // Channel for events meant for this client alone
+        let (direct_tx, mut direct_rx) = mpsc::unbounded_channel::<WebSocketEvent>();
+        
         // Spawn a task to forward events to the client
         let clients = Arc::clone(&self.clients);
+        let market_events = Arc::clone(&self.market_events);
         let event_forward = tokio::spawn(async move {
             let mut stream = BroadcastStream::new(event_rx);
             
-            while let Some(Ok(event)) = stream.next().await {
+            loop {
+                let (event, direct) = tokio::select! {
+                    Some(event) = direct_rx.recv() => (event, true),
+                    next = stream.next() => match next {
+                        Some(Ok(event)) => (event, false),
+                        _ => break,
+                    },
+                };
+                
                 // Check if the client is subscribed to this event
-                let should_send = {
+                let should_send = direct || {
                     let clients = clients.read().await;
+                    let market_events = market_events.read().await;
                     if let Some(subscription) = clients.get(&client_id) {
                         match &event {
                             WebSocketEvent::Trade(trade) => {
-                                subscription.markets.contains(&trade.market_id)
+                                subscription.follows_market(&trade.market_id, &market_events)
                             }
                             WebSocketEvent::PriceUpdate { market_id, .. } => {
-                                subscription.markets.contains(market_id)
+                                subscription.follows_market(market_id, &market_events)
+                            }
+                            WebSocketEvent::MarketStatus { market_id, .. } => {
+                                subscription.follows_market(market_id, &market_events)
                             }
                             WebSocketEvent::MarketResolution { market_id, .. } => {
-                                subscription.markets.contains(market_id)
+                                subscription.follows_market(market_id, &market_events)
                             }
                             WebSocketEvent::Payout { user_id, .. } => {
-                                subscription.user_id.map_or(false, |id| id == *user_id)
+                                subscription.user_id == Some(*user_id)
                             }
                             WebSocketEvent::OrderUpdate(order) => {
-                                subscription.user_id.map_or(false, |id| id == order.user_id) ||
-                                subscription.markets.contains(&order.market_id)
+                                subscription.user_id == Some(order.user_id) ||
+                                subscription.follows_market(&order.market_id, &market_events)
+                            }
+                            WebSocketEvent::EventStatus(summary) => {
+                                subscription.events.contains(&summary.event_id)
                             }
+                            WebSocketEvent::Throttled { .. } => false,
                         }
                     } else {
                         false
@@ -294,6 +385,13 @@ impl WebSocketServer {
             match result {
                 Ok(msg) => {
                     if msg.is_text() {
+                        if let Err(retry_after) = self.check_rate_limit(client_id, remote).await {
+                            let retry_after_ms = retry_after.as_millis().max(1) as u64;
+                            if direct_tx.send(WebSocketEvent::Throttled { retry_after_ms }).is_err() {
+                                break;
+                            }
+                            continue;
+                        }
                         if let Ok(text) = msg.to_str() {
                             self.process_client_message(client_id, text).await;
                         }
@@ -320,6 +418,22 @@ impl WebSocketServer {
         info!("WebSocket connection closed: {}", client_id);
     }
     
+    /// Counts a client's message against the query budget of its address and user
+    async fn check_rate_limit(&self, client_id: Uuid, remote: Option<SocketAddr>) -> Result<(), std::time::Duration> {
+        let mut keys = Vec::new();
+        if let Some(remote) = remote {
+            keys.push(RateKey::Ip(remote.ip()));
+        }
+        if let Some(user_id) = self.clients.read().await.get(&client_id).and_then(|s| s.user_id) {
+            keys.push(RateKey::User(user_id));
+        }
+        
+        self.rate_limiter.check(RateBudget::Queries, &keys).map_err(|limited| {
+            debug!("Throttled WebSocket client {}: over the limit for {}", client_id, limited.key);
+            limited.retry_after
+        })
+    }
+    
     /// Processes a message from a client
     async fn process_client_message(&self, client_id: Uuid, message: &str) {
         #[derive(Deserialize)]
@@ -327,6 +441,8 @@ impl WebSocketServer {
             action: String,
             #[serde(default)]
             markets: Vec<String>,
+            #[serde(default)]
+            events: Vec<String>,
             user_id: Option<String>,
         }
         
@@ -341,10 +457,19 @@ impl WebSocketServer {
                             subscription.markets.insert(market_id.clone());
                         }
                         
-                        // Subscribe to user events
+                        // Subscribe to events
+                        for event_id in msg.events {
+                            subscription.events.insert(event_id);
+                        }
+                        
+                        // Subscribe to user events, only for the user the connection was signed for
                         if let Some(user_id_str) = msg.user_id {
                             if let Ok(user_id) = Uuid::parse_str(&user_id_str) {
-                                subscription.user_id = Some(user_id);
+                                if subscription.authenticated_user == Some(user_id) {
+                                    subscription.user_id = Some(user_id);
+                                } else {
+                                    debug!("Client {} cannot subscribe to events of user {}", client_id, user_id);
+                                }
                             }
                         }
                         
@@ -356,6 +481,11 @@ impl WebSocketServer {
                             subscription.markets.remove(&market_id);
                         }
                         
+                        // Unsubscribe from events
+                        for event_id in msg.events {
+                            subscription.events.remove(&event_id);
+                        }
+                        
                         // Unsubscribe from user events
                         if msg.user_id.is_some() {
                             subscription.user_id = None;
@@ -23,7 +23,7 @@ pub async fn create_pg_pool() -> Result<PgPool> {
     let result = sqlx::query!("SELECT 1 as one")
         .fetch_one(&pool)
         .await;
-        
+    
     match result {
         Ok(_) => info!("Database connection test successful"),
         Err(e) => error!("Database connection test failed: {}", e),
@@ -50,16 +50,67 @@ pub trait Repository {
     /// Gets all orders for a user in a market
     async fn get_orders_for_user(&self, market_id: &str, user_id: uuid::Uuid) -> Result<Vec<crate::models::order::Order>>;
     
+    /// Gets all orders for a user across every market
+    async fn get_all_orders_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::order::Order>>;
+    
+    /// Gets a user's order by the ID the client chose for it
+    async fn get_order_by_client_order_id(&self, user_id: uuid::Uuid, client_order_id: &str) -> Result<Option<crate::models::order::Order>>;
+    
     /// Saves an order
     async fn save_order(&self, order: &crate::models::order::Order) -> Result<()>;
     
     /// Gets all trades for a market
     async fn get_trades_for_market(&self, market_id: &str) -> Result<Vec<crate::models::trade::Trade>>;
     
+    /// Gets all trades a user bought or sold in
+    async fn get_trades_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::trade::Trade>>;
+    
+    /// Saves a trade
+    async fn save_trade(&self, trade: &crate::models::trade::Trade) -> Result<()>;
+    
+    /// Gets the automated market maker for a market, if it has one
+    async fn get_market_maker(&self, market_id: &str) -> Result<Option<crate::models::amm::LmsrMarketMaker>>;
+    
+    /// Saves an automated market maker
+    async fn save_market_maker(&self, market_maker: &crate::models::amm::LmsrMarketMaker) -> Result<()>;
+    
+    /// Gets a market group with the IDs of its markets
+    async fn get_market_group(&self, group_id: &str) -> Result<crate::models::market_group::MarketGroup>;
+    
+    /// Saves a market group and links its markets to it in one transaction
+    async fn save_market_group(&self, group: &crate::models::market_group::MarketGroup) -> Result<()>;
+    
+    /// Gets an event with the IDs of its markets
+    async fn get_event(&self, event_id: &str) -> Result<crate::models::event::Event>;
+    
+    /// Gets all events
+    async fn get_all_events(&self) -> Result<Vec<crate::models::event::Event>>;
+    
+    /// Saves an event together with the status and event link of the given markets in one transaction
+    async fn save_event_with_markets(&self, event: &crate::models::event::Event, markets: &[crate::models::Market]) -> Result<()>;
+    
+    /// Saves the entries of a settlement report not saved yet, in one transaction
+    async fn save_settlement_report(&self, report: &crate::models::settlement::SettlementReport) -> Result<()>;
+    
+    /// Gets the settlement entries for a market
+    async fn get_settlement_entries(&self, market_id: &str) -> Result<Vec<crate::models::settlement::SettlementEntry>>;
+    
+    /// Gets a user's position in an outcome of a market, if they have one
+    async fn get_position(&self, user_id: uuid::Uuid, market_id: &str, outcome: crate::models::order::OutcomeSide) -> Result<Option<crate::models::position::Position>>;
+    
+    /// Gets all positions for a user
+    async fn get_positions_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::position::Position>>;
+    
+    /// Gets all positions in a market
+    async fn get_positions_for_market(&self, market_id: &str) -> Result<Vec<crate::models::position::Position>>;
+    
+    /// Saves a position
+    async fn save_position(&self, position: &crate::models::position::Position) -> Result<()>;
+    
     /// Gets a user balance
     async fn get_user_balance(&self, user_id: uuid::Uuid) -> Result<crate::models::balance::UserBalance>;
     
-    /// Saves a user balance
+    /// Saves a user balance, failing with `BalanceConflict` if it changed since it was read
     async fn save_user_balance(&self, balance: &crate::models::balance::UserBalance) -> Result<()>;
     
     /// Saves a balance transaction
@@ -67,4 +118,162 @@ pub trait Repository {
     
     /// Gets all balance transactions for a user
     async fn get_balance_transactions_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::balance::BalanceTransaction>>;
-} 
\ No newline at end of file
+    
+    /// Gets a user's balance transaction of a type by its reference
+    async fn find_balance_transaction(
+        &self,
+        user_id: uuid::Uuid,
+        transaction_type: crate::models::balance::TransactionType,
+        reference_id: &str,
+    ) -> Result<Option<crate::models::balance::BalanceTransaction>>;
+    
+    /// Gets every user balance
+    async fn get_all_user_balances(&self) -> Result<Vec<crate::models::balance::UserBalance>>;
+    
+    /// Saves user balances together with the transactions and ledger entries that explain them in one transaction
+    ///
+    /// Nothing is saved, and the error is a `BalanceConflict`, if any of the balances
+    /// changed since it was read.
+    async fn post_ledger_entries(
+        &self,
+        balances: &[crate::models::balance::UserBalance],
+        transactions: &[crate::models::balance::BalanceTransaction],
+        entries: &[crate::models::ledger::LedgerEntry],
+    ) -> Result<()>;
+    
+    /// Saves a user's balance, transactions and ledger entries from settling a market together
+    /// with their settlement entry and any tax withheld, in one transaction
+    ///
+    /// As with `post_ledger_entries`, nothing is saved if the balance changed since it was read.
+    async fn post_settlement(
+        &self,
+        balance: &crate::models::balance::UserBalance,
+        transactions: &[crate::models::balance::BalanceTransaction],
+        entries: &[crate::models::ledger::LedgerEntry],
+        settlement: &crate::models::settlement::PositionSettlement,
+    ) -> Result<()>;
+    
+    /// Gets the balance of a ledger account
+    async fn get_ledger_balance(&self, account: &crate::models::ledger::LedgerAccount) -> Result<rust_decimal::Decimal>;
+    
+    /// Gets the balance of every ledger account that has entries
+    async fn get_ledger_balances(&self) -> Result<Vec<crate::models::ledger::LedgerBalance>>;
+    
+    /// Gets the balance of every ledger account belonging to a market
+    async fn get_market_ledger_balances(&self, market_id: &str) -> Result<Vec<crate::models::ledger::LedgerBalance>>;
+    
+    /// Gets the balance of every market account holding a user's funds (collateral and market maker pools)
+    async fn get_market_reserves_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::ledger::LedgerBalance>>;
+    
+    /// Saves a new idempotency key, returning false if the key is already in use on its endpoint
+    async fn claim_idempotency_key(&self, record: &crate::models::idempotency::IdempotencyRecord) -> Result<bool>;
+    
+    /// Gets an idempotency key used on an endpoint
+    async fn get_idempotency_key(&self, endpoint: &str, idempotency_key: &str) -> Result<Option<crate::models::idempotency::IdempotencyRecord>>;
+    
+    /// Stores the response to the request made with an idempotency key
+    async fn complete_idempotency_key(&self, endpoint: &str, idempotency_key: &str, status_code: u16, response_body: &str) -> Result<()>;
+    
+    /// Deletes an idempotency key so it can be used again
+    async fn delete_idempotency_key(&self, endpoint: &str, idempotency_key: &str) -> Result<()>;
+    
+    /// Saves a new withdrawal request
+    async fn save_withdrawal(&self, withdrawal: &crate::models::withdrawal::Withdrawal) -> Result<()>;
+    
+    /// Saves a reviewed withdrawal if it still has the status it was read with, returning whether it did
+    async fn update_withdrawal(
+        &self,
+        withdrawal: &crate::models::withdrawal::Withdrawal,
+        previous_status: crate::models::withdrawal::WithdrawalStatus,
+    ) -> Result<bool>;
+    
+    /// Gets a withdrawal by ID
+    async fn get_withdrawal(&self, withdrawal_id: uuid::Uuid) -> Result<crate::models::withdrawal::Withdrawal>;
+    
+    /// Gets withdrawals, newest first, optionally only a user's or only those with a status
+    async fn get_withdrawals(
+        &self,
+        user_id: Option<uuid::Uuid>,
+        status: Option<crate::models::withdrawal::WithdrawalStatus>,
+    ) -> Result<Vec<crate::models::withdrawal::Withdrawal>>;
+    
+    /// Gets the total a user has asked to withdraw since a time, leaving out rejected requests
+    async fn get_withdrawn_since(&self, user_id: uuid::Uuid, since: chrono::DateTime<chrono::Utc>) -> Result<rust_decimal::Decimal>;
+    
+    /// Saves a new deposit
+    async fn save_deposit(&self, deposit: &crate::models::deposit::Deposit) -> Result<()>;
+    
+    /// Saves a paid or failed deposit if it still has the status it was read with, returning whether it did
+    async fn update_deposit(
+        &self,
+        deposit: &crate::models::deposit::Deposit,
+        previous_status: crate::models::deposit::DepositStatus,
+    ) -> Result<bool>;
+    
+    /// Gets a deposit by ID
+    async fn get_deposit(&self, deposit_id: uuid::Uuid) -> Result<crate::models::deposit::Deposit>;
+    
+    /// Gets a user's deposits, newest first
+    async fn get_deposits_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::deposit::Deposit>>;
+    
+    /// Saves a new grant of promotional credit
+    async fn save_promo_credit(&self, credit: &crate::models::promo::PromoCredit) -> Result<()>;
+    
+    /// Gets the promotional credit granted to a user, newest first
+    async fn get_promo_credits_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::promo::PromoCredit>>;
+    
+    /// Gets a user's tax withholdings, oldest first, optionally for one financial year only
+    async fn get_tax_withholdings_for_user(
+        &self,
+        user_id: uuid::Uuid,
+        financial_year: Option<i32>,
+    ) -> Result<Vec<crate::models::tax::TaxWithholding>>;
+    
+    /// Gets every risk tier
+    async fn get_risk_tiers(&self) -> Result<Vec<crate::models::risk::RiskTier>>;
+    
+    /// Gets a risk tier by name
+    async fn get_risk_tier(&self, name: &str) -> Result<Option<crate::models::risk::RiskTier>>;
+    
+    /// Saves a risk tier, replacing its limits if it exists
+    async fn save_risk_tier(&self, tier: &crate::models::risk::RiskTier) -> Result<()>;
+    
+    /// Gets the name of the risk tier a user is assigned to, if any
+    async fn get_user_risk_tier(&self, user_id: uuid::Uuid) -> Result<Option<String>>;
+    
+    /// Assigns a user to a risk tier
+    async fn save_user_risk_tier(&self, user_id: uuid::Uuid, tier: &str) -> Result<()>;
+    
+    /// Gets the risk limits set for a market, if any
+    async fn get_market_risk_limits(&self, market_id: &str) -> Result<Option<crate::models::risk::RiskLimits>>;
+    
+    /// Saves the risk limits for a market, replacing any it had
+    async fn save_market_risk_limits(&self, market_id: &str, limits: &crate::models::risk::RiskLimits) -> Result<()>;
+    
+    /// Saves an API key, updating it if it exists
+    async fn save_api_key(&self, api_key: &crate::models::api_key::ApiKey) -> Result<()>;
+    
+    /// Gets an API key by its ID
+    async fn get_api_key(&self, key_id: &str) -> Result<Option<crate::models::api_key::ApiKey>>;
+    
+    /// Gets a user's API keys, newest first
+    async fn get_api_keys_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::api_key::ApiKey>>;
+    
+    /// Saves the nonce a request signed with an API key carried, returning false if the key has already used it
+    async fn save_request_nonce(&self, key_id: &str, nonce: &str, created_at: chrono::DateTime<chrono::Utc>) -> Result<bool>;
+    
+    /// Deletes request nonces saved before a time, returning how many were deleted
+    async fn delete_request_nonces_before(&self, before: chrono::DateTime<chrono::Utc>) -> Result<u64>;
+    
+    /// Saves a role granted to a user, keeping the original grant if they already hold it
+    async fn save_role_grant(&self, grant: &crate::models::role::RoleGrant) -> Result<()>;
+    
+    /// Removes a role from a user, returning whether they held it
+    async fn delete_role_grant(&self, user_id: uuid::Uuid, role: crate::models::role::Role) -> Result<bool>;
+    
+    /// Gets the roles granted to a user, oldest first
+    async fn get_role_grants_for_user(&self, user_id: uuid::Uuid) -> Result<Vec<crate::models::role::RoleGrant>>;
+    
+    /// Gets the grants of a role to any user, oldest first
+    async fn get_role_grants(&self, role: crate::models::role::Role) -> Result<Vec<crate::models::role::RoleGrant>>;
+}
@@ -1,14 +1,26 @@
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
 use uuid::Uuid;
 use sqlx::postgres::PgPool;
-use log::{debug, info, error};
+use log::{debug, error};
 
-use crate::models::market::{Market, MarketStatus};
-use crate::models::order::{Order, OrderSide, OrderStatus, OutcomeSide};
+use crate::models::market::{Market, MarketStatus, MarketType, LiquidityMode};
+use crate::models::order::{Order, OrderSide, OrderStatus, OutcomeSide, TimeInForce};
 use crate::models::trade::Trade;
-use crate::models::balance::{UserBalance, BalanceTransaction, TransactionType};
+use crate::models::balance::{UserBalance, BalanceConflict, BalanceTransaction, TransactionType};
+use crate::models::amm::LmsrMarketMaker;
+use crate::models::market_group::MarketGroup;
+use crate::models::event::{Event, EventStatus};
+use crate::models::settlement::{PositionSettlement, SettlementEntry, SettlementReport};
+use crate::models::position::Position;
+use crate::models::ledger::{LedgerAccount, LedgerAccountType, LedgerBalance, LedgerEntry};
+use crate::models::idempotency::IdempotencyRecord;
+use crate::models::withdrawal::{Withdrawal, WithdrawalStatus};
+use crate::models::deposit::{Deposit, DepositStatus};
+use crate::models::promo::PromoCredit;
+use crate::models::tax::TaxWithholding;
+use crate::models::risk::{RiskLimits, RiskTier};
+use crate::models::api_key::ApiKey;
+use crate::models::role::{Role, RoleGrant};
 use crate::db::connection::Repository;
 
 /// Repository for database operations using SQLx
@@ -24,9 +36,11 @@ impl Repository for SqlxRepository {
         let market_row = sqlx::query!(
             r#"
             SELECT 
-                id, question, description, status, 
+                id, question, description, outcomes, status, 
                 created_at, updated_at, close_time, 
-                resolved_at, resolution
+                resolved_at, resolution, liquidity_mode,
+                market_type, lower_bound, upper_bound, resolved_value,
+                group_id, event_id, allow_shorting
             FROM markets 
             WHERE id = $1
             "#,
@@ -39,18 +53,27 @@ impl Repository for SqlxRepository {
         let orders = self.get_active_orders_for_market(market_id).await?;
         
         // Create market from database row
-        let market = Market::from_db(
+        let mut market = Market::from_db(
             market_row.id,
             market_row.question,
             market_row.description,
+            market_row.outcomes,
             MarketStatus::from(market_row.status),
             market_row.created_at,
             market_row.updated_at,
             market_row.close_time,
             market_row.resolved_at,
-            market_row.resolution.map(|r| OutcomeSide::from(r)),
+            market_row.resolution.map(OutcomeSide::from),
             orders,
         );
+        market.liquidity_mode = LiquidityMode::from(market_row.liquidity_mode);
+        market.market_type = MarketType::from(market_row.market_type);
+        market.lower_bound = market_row.lower_bound;
+        market.upper_bound = market_row.upper_bound;
+        market.resolved_value = market_row.resolved_value;
+        market.group_id = market_row.group_id;
+        market.event_id = market_row.event_id;
+        market.allow_shorting = market_row.allow_shorting;
         
         Ok(market)
     }
@@ -61,9 +84,11 @@ impl Repository for SqlxRepository {
         let market_rows = sqlx::query!(
             r#"
             SELECT 
-                id, question, description, status, 
+                id, question, description, outcomes, status, 
                 created_at, updated_at, close_time, 
-                resolved_at, resolution
+                resolved_at, resolution, liquidity_mode,
+                market_type, lower_bound, upper_bound, resolved_value,
+                group_id, event_id, allow_shorting
             FROM markets
             "#
         )
@@ -80,18 +105,27 @@ impl Repository for SqlxRepository {
             let orders = self.get_active_orders_for_market(market_id).await?;
             
             // Create market from database row
-            let market = Market::from_db(
+            let mut market = Market::from_db(
                 market_row.id,
                 market_row.question,
                 market_row.description,
+                market_row.outcomes,
                 MarketStatus::from(market_row.status),
                 market_row.created_at,
                 market_row.updated_at,
                 market_row.close_time,
                 market_row.resolved_at,
-                market_row.resolution.map(|r| OutcomeSide::from(r)),
+                market_row.resolution.map(OutcomeSide::from),
                 orders,
             );
+            market.liquidity_mode = LiquidityMode::from(market_row.liquidity_mode);
+            market.market_type = MarketType::from(market_row.market_type);
+            market.lower_bound = market_row.lower_bound;
+            market.upper_bound = market_row.upper_bound;
+            market.resolved_value = market_row.resolved_value;
+            market.group_id = market_row.group_id;
+            market.event_id = market_row.event_id;
+            market.allow_shorting = market_row.allow_shorting;
             
             results.push(market);
         }
@@ -107,9 +141,11 @@ impl Repository for SqlxRepository {
             INSERT INTO markets (
                 id, question, description, status, 
                 created_at, updated_at, close_time, 
-                resolved_at, resolution
+                resolved_at, resolution, liquidity_mode, outcomes,
+                market_type, lower_bound, upper_bound, resolved_value, group_id,
+                event_id, allow_shorting
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             ON CONFLICT (id) DO UPDATE SET
                 question = $2,
                 description = $3,
@@ -117,7 +153,12 @@ impl Repository for SqlxRepository {
                 updated_at = $6,
                 close_time = $7,
                 resolved_at = $8,
-                resolution = $9
+                resolution = $9,
+                liquidity_mode = $10,
+                resolved_value = $15,
+                group_id = $16,
+                event_id = $17,
+                allow_shorting = $18
             "#,
             market.market_id,
             market.question,
@@ -127,11 +168,20 @@ impl Repository for SqlxRepository {
             market.updated_at,
             market.close_time,
             market.resolved_at,
-            market.resolution.map(|r| i32::from(r))
+            market.resolution.map(|r| i32::from(r)),
+            i32::from(market.liquidity_mode),
+            &market.outcomes,
+            i32::from(market.market_type),
+            market.lower_bound,
+            market.upper_bound,
+            market.resolved_value,
+            market.group_id,
+            market.event_id,
+            market.allow_shorting
         )
         .execute(&self.pool)
         .await;
-            
+        
         match result {
             Ok(_) => {
                 debug!("Saved market {}", market.market_id);
@@ -150,8 +200,8 @@ impl Repository for SqlxRepository {
             r#"
             SELECT 
                 id, user_id, market_id, side, outcome, 
-                price, quantity, remaining_quantity, status,
-                created_at, updated_at
+                price, quantity, remaining_quantity, covered_quantity, status,
+                time_in_force, client_order_id, created_at, updated_at
             FROM orders
             WHERE id = $1
             "#,
@@ -169,6 +219,9 @@ impl Repository for SqlxRepository {
             price: order_row.price,
             quantity: order_row.quantity as u32,
             remaining_quantity: order_row.remaining_quantity as u32,
+            covered_quantity: order_row.covered_quantity as u32,
+            time_in_force: TimeInForce::from(order_row.time_in_force),
+            client_order_id: order_row.client_order_id,
             status: OrderStatus::from(order_row.status),
             created_at: order_row.created_at,
             updated_at: order_row.updated_at,
@@ -183,8 +236,8 @@ impl Repository for SqlxRepository {
             r#"
             SELECT 
                 id, user_id, market_id, side, outcome, 
-                price, quantity, remaining_quantity, status,
-                created_at, updated_at
+                price, quantity, remaining_quantity, covered_quantity, status,
+                time_in_force, client_order_id, created_at, updated_at
             FROM orders
             WHERE market_id = $1 AND user_id = $2
             "#,
@@ -204,6 +257,47 @@ impl Repository for SqlxRepository {
                 price: row.price,
                 quantity: row.quantity as u32,
                 remaining_quantity: row.remaining_quantity as u32,
+                covered_quantity: row.covered_quantity as u32,
+                time_in_force: TimeInForce::from(row.time_in_force),
+                client_order_id: row.client_order_id,
+                status: OrderStatus::from(row.status),
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            }
+        }).collect();
+        
+        Ok(orders)
+    }
+    
+    /// Gets all orders for a user across every market
+    async fn get_all_orders_for_user(&self, user_id: Uuid) -> Result<Vec<Order>> {
+        let order_rows = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, market_id, side, outcome, 
+                price, quantity, remaining_quantity, covered_quantity, status,
+                time_in_force, client_order_id, created_at, updated_at
+            FROM orders
+            WHERE user_id = $1
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let orders = order_rows.into_iter().map(|row| {
+            Order {
+                order_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                market_id: row.market_id,
+                side: OrderSide::from(row.side),
+                outcome: OutcomeSide::from(row.outcome),
+                price: row.price,
+                quantity: row.quantity as u32,
+                remaining_quantity: row.remaining_quantity as u32,
+                covered_quantity: row.covered_quantity as u32,
+                time_in_force: TimeInForce::from(row.time_in_force),
+                client_order_id: row.client_order_id,
                 status: OrderStatus::from(row.status),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
@@ -213,6 +307,45 @@ impl Repository for SqlxRepository {
         Ok(orders)
     }
     
+    /// Gets a user's order by the ID the client chose for it
+    async fn get_order_by_client_order_id(&self, user_id: Uuid, client_order_id: &str) -> Result<Option<Order>> {
+        let order_row = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, market_id, side, outcome, 
+                price, quantity, remaining_quantity, covered_quantity, status,
+                time_in_force, client_order_id, created_at, updated_at
+            FROM orders
+            WHERE user_id = $1 AND client_order_id = $2
+            "#,
+            user_id.to_string(),
+            client_order_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        let Some(row) = order_row else {
+            return Ok(None);
+        };
+        
+        Ok(Some(Order {
+            order_id: Uuid::parse_str(&row.id)?,
+            user_id: Uuid::parse_str(&row.user_id)?,
+            market_id: row.market_id,
+            side: OrderSide::from(row.side),
+            outcome: OutcomeSide::from(row.outcome),
+            price: row.price,
+            quantity: row.quantity as u32,
+            remaining_quantity: row.remaining_quantity as u32,
+            covered_quantity: row.covered_quantity as u32,
+            time_in_force: TimeInForce::from(row.time_in_force),
+            client_order_id: row.client_order_id,
+            status: OrderStatus::from(row.status),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }))
+    }
+    
     /// Saves an order to the database
     async fn save_order(&self, order: &Order) -> Result<()> {
         // Save an order to the database
@@ -221,9 +354,9 @@ impl Repository for SqlxRepository {
             INSERT INTO orders (
                 id, user_id, market_id, side, outcome, 
                 price, quantity, remaining_quantity, status,
-                created_at, updated_at
+                created_at, updated_at, covered_quantity, time_in_force, client_order_id
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             ON CONFLICT (id) DO UPDATE SET
                 side = $4,
                 outcome = $5,
@@ -231,7 +364,9 @@ impl Repository for SqlxRepository {
                 quantity = $7,
                 remaining_quantity = $8,
                 status = $9,
-                updated_at = $11
+                updated_at = $11,
+                covered_quantity = $12,
+                time_in_force = $13
             "#,
             order.order_id.to_string(),
             order.user_id.to_string(),
@@ -243,11 +378,14 @@ impl Repository for SqlxRepository {
             order.remaining_quantity as i32,
             i32::from(order.status),
             order.created_at,
-            order.updated_at
+            order.updated_at,
+            order.covered_quantity as i32,
+            i32::from(order.time_in_force),
+            order.client_order_id
         )
         .execute(&self.pool)
         .await;
-            
+        
         match result {
             Ok(_) => {
                 debug!("Saved order {}", order.order_id);
@@ -294,145 +432,2050 @@ impl Repository for SqlxRepository {
         Ok(trades)
     }
     
-    /// Gets a user balance
-    async fn get_user_balance(&self, user_id: Uuid) -> Result<UserBalance> {
-        let balance_row = sqlx::query!(
+    /// Gets all trades a user bought or sold in
+    async fn get_trades_for_user(&self, user_id: Uuid) -> Result<Vec<Trade>> {
+        let trade_rows = sqlx::query!(
             r#"
             SELECT 
-                user_id, available_balance, 
-                reserved_balance, updated_at
-            FROM user_balances
-            WHERE user_id = $1
+                id, market_id, buy_order_id, buyer_id, 
+                sell_order_id, seller_id, outcome, 
+                price, quantity, executed_at
+            FROM trades
+            WHERE buyer_id = $1 OR seller_id = $1
             "#,
             user_id.to_string()
         )
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
         
-        let balance = UserBalance {
-            user_id: Uuid::parse_str(&balance_row.user_id)?,
-            available_balance: balance_row.available_balance,
-            reserved_balance: balance_row.reserved_balance,
-            updated_at: balance_row.updated_at,
-        };
+        let trades = trade_rows.into_iter().map(|row| {
+            Trade {
+                trade_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                market_id: row.market_id,
+                buy_order_id: Uuid::parse_str(&row.buy_order_id).unwrap_or_else(|_| Uuid::nil()),
+                buyer_id: Uuid::parse_str(&row.buyer_id).unwrap_or_else(|_| Uuid::nil()),
+                sell_order_id: Uuid::parse_str(&row.sell_order_id).unwrap_or_else(|_| Uuid::nil()),
+                seller_id: Uuid::parse_str(&row.seller_id).unwrap_or_else(|_| Uuid::nil()),
+                outcome: OutcomeSide::from(row.outcome),
+                price: row.price,
+                quantity: row.quantity as u32,
+                executed_at: row.executed_at,
+            }
+        }).collect();
         
-        Ok(balance)
+        Ok(trades)
     }
     
-    /// Saves a user balance to the database
-    async fn save_user_balance(&self, balance: &UserBalance) -> Result<()> {
-        // Save a user balance to the database
+    /// Saves a trade to the database
+    async fn save_trade(&self, trade: &Trade) -> Result<()> {
         let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
             r#"
-            INSERT INTO user_balances (
-                user_id, available_balance, reserved_balance, updated_at
+            INSERT INTO trades (
+                id, market_id, buy_order_id, buyer_id, 
+                sell_order_id, seller_id, outcome, 
+                price, quantity, executed_at
             )
-            VALUES ($1, $2, $3, $4)
-            ON CONFLICT (user_id) DO UPDATE SET
-                available_balance = $2,
-                reserved_balance = $3,
-                updated_at = $4
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             "#,
-            balance.user_id.to_string(),
-            balance.available_balance,
-            balance.reserved_balance,
-            balance.updated_at
+            trade.trade_id.to_string(),
+            trade.market_id,
+            trade.buy_order_id.to_string(),
+            trade.buyer_id.to_string(),
+            trade.sell_order_id.to_string(),
+            trade.seller_id.to_string(),
+            i32::from(trade.outcome),
+            trade.price,
+            trade.quantity as i32,
+            trade.executed_at
         )
         .execute(&self.pool)
         .await;
-            
+        
         match result {
             Ok(_) => {
-                debug!("Saved user balance for user {}", balance.user_id);
+                debug!("Saved trade {}", trade.trade_id);
                 Ok(())
             }
             Err(e) => {
-                error!("Failed to save user balance for user {}: {}", balance.user_id, e);
+                error!("Failed to save trade {}: {}", trade.trade_id, e);
                 Err(anyhow!(e))
             }
         }
     }
     
-    /// Saves a balance transaction to the database
-    async fn save_balance_transaction(&self, transaction: &BalanceTransaction) -> Result<()> {
-        // Save a balance transaction to the database
+    /// Gets the automated market maker for a market, if it has one
+    async fn get_market_maker(&self, market_id: &str) -> Result<Option<LmsrMarketMaker>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT 
+                market_id, house_account_id, liquidity, subsidy,
+                quantities, collected, created_at, updated_at
+            FROM amm_states
+            WHERE market_id = $1
+            "#,
+            market_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        let market_maker = match row {
+            Some(row) => Some(LmsrMarketMaker {
+                market_id: row.market_id,
+                house_account_id: Uuid::parse_str(&row.house_account_id)?,
+                liquidity: row.liquidity,
+                subsidy: row.subsidy,
+                quantities: row.quantities,
+                collected: row.collected,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            }),
+            None => None,
+        };
+        
+        Ok(market_maker)
+    }
+    
+    /// Saves an automated market maker to the database
+    async fn save_market_maker(&self, market_maker: &LmsrMarketMaker) -> Result<()> {
         let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
             r#"
-            INSERT INTO balance_transactions (
-                id, user_id, amount, transaction_type, 
-                reference_id, description, created_at
+            INSERT INTO amm_states (
+                market_id, house_account_id, liquidity, subsidy,
+                quantities, collected, created_at, updated_at
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (market_id) DO UPDATE SET
+                liquidity = $3,
+                subsidy = $4,
+                quantities = $5,
+                collected = $6,
+                updated_at = $8
             "#,
-            transaction.transaction_id.to_string(),
-            transaction.user_id.to_string(),
-            transaction.amount,
-            i32::from(transaction.transaction_type),
-            transaction.reference_id,
-            transaction.description,
-            transaction.created_at
+            market_maker.market_id,
+            market_maker.house_account_id.to_string(),
+            market_maker.liquidity,
+            market_maker.subsidy,
+            &market_maker.quantities,
+            market_maker.collected,
+            market_maker.created_at,
+            market_maker.updated_at
         )
         .execute(&self.pool)
         .await;
-            
+        
         match result {
             Ok(_) => {
-                debug!("Saved balance transaction {}", transaction.transaction_id);
+                debug!("Saved market maker for market {}", market_maker.market_id);
                 Ok(())
             }
             Err(e) => {
-                error!("Failed to save balance transaction {}: {}", transaction.transaction_id, e);
+                error!("Failed to save market maker for market {}: {}", market_maker.market_id, e);
                 Err(anyhow!(e))
             }
         }
     }
     
-    /// Gets all balance transactions for a user
-    async fn get_balance_transactions_for_user(&self, user_id: Uuid) -> Result<Vec<BalanceTransaction>> {
-        let transaction_rows = sqlx::query!(
+    /// Gets a market group with the IDs of its markets
+    async fn get_market_group(&self, group_id: &str) -> Result<MarketGroup> {
+        let group_row = sqlx::query!(
             r#"
             SELECT 
-                id, user_id, amount, transaction_type, 
-                reference_id, description, created_at
-            FROM balance_transactions
-            WHERE user_id = $1
-            ORDER BY created_at DESC
+                id, name, description, house_account_id,
+                created_at, updated_at
+            FROM market_groups
+            WHERE id = $1
             "#,
-            user_id.to_string()
+            group_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        
+        let market_ids = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM markets
+            WHERE group_id = $1
+            ORDER BY created_at, id
+            "#,
+            group_id
         )
         .fetch_all(&self.pool)
         .await?;
         
-        let transactions = transaction_rows.into_iter().map(|row| {
-            BalanceTransaction {
-                transaction_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
-                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
-                amount: row.amount,
-                transaction_type: TransactionType::from(row.transaction_type),
-                reference_id: row.reference_id,
-                description: row.description,
-                created_at: row.created_at,
-            }
-        }).collect();
+        let group = MarketGroup {
+            group_id: group_row.id,
+            name: group_row.name,
+            description: group_row.description,
+            market_ids,
+            house_account_id: Uuid::parse_str(&group_row.house_account_id)?,
+            created_at: group_row.created_at,
+            updated_at: group_row.updated_at,
+        };
         
-        Ok(transactions)
+        Ok(group)
     }
-}
-
-impl SqlxRepository {
-    /// Creates a new repository
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    
+    /// Saves a market group and links its markets to it in one transaction
+    async fn save_market_group(&self, group: &MarketGroup) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO market_groups (
+                id, name, description, house_account_id,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (id) DO UPDATE SET
+                name = $2,
+                description = $3,
+                updated_at = $6
+            "#,
+            group.group_id,
+            group.name,
+            group.description,
+            group.house_account_id.to_string(),
+            group.created_at,
+            group.updated_at
+        )
+        .execute(&mut *tx)
+        .await;
+        
+        if let Err(e) = result {
+            error!("Failed to save market group {}: {}", group.group_id, e);
+            return Err(anyhow!(e));
+        }
+        
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            UPDATE markets SET group_id = $1, updated_at = $2
+            WHERE id = ANY($3)
+            "#,
+            group.group_id,
+            group.updated_at,
+            &group.market_ids
+        )
+        .execute(&mut *tx)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                tx.commit().await?;
+                debug!("Saved market group {}", group.group_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to link markets to group {}: {}", group.group_id, e);
+                Err(anyhow!(e))
+            }
+        }
     }
     
-    /// Helper method to get active orders for a market
-    async fn get_active_orders_for_market(&self, market_id: &str) -> Result<Vec<Order>> {
-        let order_rows = sqlx::query!(
+    /// Gets an event with the IDs of its markets
+    async fn get_event(&self, event_id: &str) -> Result<Event> {
+        let event_row = sqlx::query!(
             r#"
             SELECT 
-                id, user_id, market_id, side, outcome, 
-                price, quantity, remaining_quantity, status,
+                id, name, description, status, starts_at,
+                created_at, updated_at
+            FROM events
+            WHERE id = $1
+            "#,
+            event_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        
+        let market_ids = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM markets
+            WHERE event_id = $1
+            ORDER BY created_at, id
+            "#,
+            event_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let event = Event {
+            event_id: event_row.id,
+            name: event_row.name,
+            description: event_row.description,
+            status: EventStatus::from(event_row.status),
+            market_ids,
+            starts_at: event_row.starts_at,
+            created_at: event_row.created_at,
+            updated_at: event_row.updated_at,
+        };
+        
+        Ok(event)
+    }
+    
+    /// Gets all events
+    async fn get_all_events(&self) -> Result<Vec<Event>> {
+        let event_rows = sqlx::query!(
+            r#"
+            SELECT 
+                e.id, e.name, e.description, e.status, e.starts_at,
+                e.created_at, e.updated_at,
+                ARRAY_REMOVE(ARRAY_AGG(m.id ORDER BY m.created_at, m.id), NULL) AS "market_ids!"
+            FROM events e
+            LEFT JOIN markets m ON m.event_id = e.id
+            GROUP BY e.id
+            ORDER BY e.created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let events = event_rows.into_iter()
+            .map(|row| Event {
+                event_id: row.id,
+                name: row.name,
+                description: row.description,
+                status: EventStatus::from(row.status),
+                market_ids: row.market_ids,
+                starts_at: row.starts_at,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            })
+            .collect();
+        
+        Ok(events)
+    }
+    
+    /// Saves an event together with the status and event link of the given markets in one transaction
+    async fn save_event_with_markets(&self, event: &Event, markets: &[Market]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO events (
+                id, name, description, status, starts_at,
                 created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE SET
+                name = $2,
+                description = $3,
+                status = $4,
+                starts_at = $5,
+                updated_at = $7
+            "#,
+            event.event_id,
+            event.name,
+            event.description,
+            i32::from(event.status),
+            event.starts_at,
+            event.created_at,
+            event.updated_at
+        )
+        .execute(&mut *tx)
+        .await;
+        
+        if let Err(e) = result {
+            error!("Failed to save event {}: {}", event.event_id, e);
+            return Err(anyhow!(e));
+        }
+        
+        for market in markets {
+            let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+                r#"
+                UPDATE markets SET status = $2, event_id = $3, updated_at = $4
+                WHERE id = $1
+                "#,
+                market.market_id,
+                i32::from(market.status),
+                market.event_id,
+                market.updated_at
+            )
+            .execute(&mut *tx)
+            .await;
+            
+            if let Err(e) = result {
+                error!("Failed to update market {} for event {}: {}", market.market_id, event.event_id, e);
+                return Err(anyhow!(e));
+            }
+        }
+        
+        tx.commit().await?;
+        debug!("Saved event {} with {} markets", event.event_id, markets.len());
+        Ok(())
+    }
+    
+    /// Saves the entries of a settlement report not saved yet, in one transaction
+    async fn save_settlement_report(&self, report: &SettlementReport) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        for entry in &report.entries {
+            let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+                r#"
+                INSERT INTO settlement_entries (
+                    market_id, user_id, positions, cost_basis,
+                    payout, realized_pnl, settled_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (market_id, user_id) DO NOTHING
+                "#,
+                report.market_id,
+                entry.user_id.to_string(),
+                &entry.positions,
+                entry.cost_basis,
+                entry.payout,
+                entry.realized_pnl,
+                report.settled_at
+            )
+            .execute(&mut *tx)
+            .await;
+            
+            if let Err(e) = result {
+                error!("Failed to save settlement entry for user {} in market {}: {}", entry.user_id, report.market_id, e);
+                return Err(anyhow!(e));
+            }
+        }
+        
+        tx.commit().await?;
+        debug!("Saved settlement report for market {}", report.market_id);
+        Ok(())
+    }
+    
+    /// Gets the settlement entries for a market
+    async fn get_settlement_entries(&self, market_id: &str) -> Result<Vec<SettlementEntry>> {
+        let entry_rows = sqlx::query!(
+            r#"
+            SELECT 
+                user_id, positions, cost_basis,
+                payout, realized_pnl
+            FROM settlement_entries
+            WHERE market_id = $1
+            ORDER BY user_id
+            "#,
+            market_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut entries = Vec::with_capacity(entry_rows.len());
+        for row in entry_rows {
+            entries.push(SettlementEntry {
+                user_id: Uuid::parse_str(&row.user_id)?,
+                positions: row.positions,
+                cost_basis: row.cost_basis,
+                payout: row.payout,
+                realized_pnl: row.realized_pnl,
+            });
+        }
+        
+        Ok(entries)
+    }
+    
+    /// Gets a user's position in an outcome of a market, if they have one
+    async fn get_position(&self, user_id: Uuid, market_id: &str, outcome: OutcomeSide) -> Result<Option<Position>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, market_id, outcome, quantity,
+                average_entry_price, realized_pnl, created_at, updated_at
+            FROM positions
+            WHERE user_id = $1 AND market_id = $2 AND outcome = $3
+            "#,
+            user_id.to_string(),
+            market_id,
+            i32::from(outcome)
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        let position = match row {
+            Some(row) => Some(Position {
+                id: Uuid::parse_str(&row.id)?,
+                user_id: Uuid::parse_str(&row.user_id)?,
+                market_id: row.market_id,
+                market_option_id: OutcomeSide::from(row.outcome),
+                quantity: row.quantity,
+                average_entry_price: row.average_entry_price,
+                realized_pnl: row.realized_pnl,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            }),
+            None => None,
+        };
+        
+        Ok(position)
+    }
+    
+    /// Gets all positions for a user
+    async fn get_positions_for_user(&self, user_id: Uuid) -> Result<Vec<Position>> {
+        let position_rows = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, market_id, outcome, quantity,
+                average_entry_price, realized_pnl, created_at, updated_at
+            FROM positions
+            WHERE user_id = $1
+            ORDER BY market_id, outcome
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut positions = Vec::with_capacity(position_rows.len());
+        for row in position_rows {
+            positions.push(Position {
+                id: Uuid::parse_str(&row.id)?,
+                user_id: Uuid::parse_str(&row.user_id)?,
+                market_id: row.market_id,
+                market_option_id: OutcomeSide::from(row.outcome),
+                quantity: row.quantity,
+                average_entry_price: row.average_entry_price,
+                realized_pnl: row.realized_pnl,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            });
+        }
+        
+        Ok(positions)
+    }
+    
+    /// Gets all positions in a market
+    async fn get_positions_for_market(&self, market_id: &str) -> Result<Vec<Position>> {
+        let position_rows = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, market_id, outcome, quantity,
+                average_entry_price, realized_pnl, created_at, updated_at
+            FROM positions
+            WHERE market_id = $1
+            ORDER BY user_id, outcome
+            "#,
+            market_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut positions = Vec::with_capacity(position_rows.len());
+        for row in position_rows {
+            positions.push(Position {
+                id: Uuid::parse_str(&row.id)?,
+                user_id: Uuid::parse_str(&row.user_id)?,
+                market_id: row.market_id,
+                market_option_id: OutcomeSide::from(row.outcome),
+                quantity: row.quantity,
+                average_entry_price: row.average_entry_price,
+                realized_pnl: row.realized_pnl,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            });
+        }
+        
+        Ok(positions)
+    }
+    
+    /// Saves a position to the database
+    async fn save_position(&self, position: &Position) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO positions (
+                id, user_id, market_id, outcome, quantity,
+                average_entry_price, realized_pnl, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO UPDATE SET
+                quantity = $5,
+                average_entry_price = $6,
+                realized_pnl = $7,
+                updated_at = $9
+            "#,
+            position.id.to_string(),
+            position.user_id.to_string(),
+            position.market_id,
+            i32::from(position.market_option_id),
+            position.quantity,
+            position.average_entry_price,
+            position.realized_pnl,
+            position.created_at,
+            position.updated_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved position {} for user {}", position.id, position.user_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save position {} for user {}: {}", position.id, position.user_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets a user balance
+    async fn get_user_balance(&self, user_id: Uuid) -> Result<UserBalance> {
+        let balance_row = sqlx::query!(
+            r#"
+            SELECT 
+                user_id, available_balance, reserved_balance,
+                winnings_balance, promo_credits, reserved_promo, promo_reserves, promo_positions,
+                updated_at, version
+            FROM user_balances
+            WHERE user_id = $1
+            "#,
+            user_id.to_string()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        
+        let balance = UserBalance {
+            user_id: Uuid::parse_str(&balance_row.user_id)?,
+            available_balance: balance_row.available_balance,
+            reserved_balance: balance_row.reserved_balance,
+            winnings_balance: balance_row.winnings_balance,
+            promo_credits: serde_json::from_value(balance_row.promo_credits)?,
+            reserved_promo: balance_row.reserved_promo,
+            promo_reserves: serde_json::from_value(balance_row.promo_reserves)?,
+            promo_positions: serde_json::from_value(balance_row.promo_positions)?,
+            updated_at: balance_row.updated_at,
+            version: balance_row.version,
+        };
+        
+        Ok(balance)
+    }
+    
+    /// Saves a user balance to the database if it has not changed since it was read
+    async fn save_user_balance(&self, balance: &UserBalance) -> Result<()> {
+        // Save a user balance to the database, bumping its version
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO user_balances (
+                user_id, available_balance, reserved_balance, winnings_balance,
+                promo_credits, reserved_promo, promo_reserves, promo_positions, updated_at, version
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::BIGINT + 1)
+            ON CONFLICT (user_id) DO UPDATE SET
+                available_balance = $2,
+                reserved_balance = $3,
+                winnings_balance = $4,
+                promo_credits = $5,
+                reserved_promo = $6,
+                promo_reserves = $7,
+                promo_positions = $8,
+                updated_at = $9,
+                version = user_balances.version + 1
+            WHERE user_balances.version = $10
+            "#,
+            balance.user_id.to_string(),
+            balance.available_balance,
+            balance.reserved_balance,
+            balance.winnings_balance,
+            serde_json::to_value(&balance.promo_credits)?,
+            balance.reserved_promo,
+            serde_json::to_value(&balance.promo_reserves)?,
+            serde_json::to_value(&balance.promo_positions)?,
+            balance.updated_at,
+            balance.version
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(result) if result.rows_affected() == 0 => {
+                debug!("User balance for user {} changed since version {}", balance.user_id, balance.version);
+                Err(BalanceConflict { user_id: balance.user_id, version: balance.version }.into())
+            }
+            Ok(_) => {
+                debug!("Saved user balance for user {}", balance.user_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save user balance for user {}: {}", balance.user_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Saves a balance transaction to the database
+    async fn save_balance_transaction(&self, transaction: &BalanceTransaction) -> Result<()> {
+        // Save a balance transaction to the database
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO balance_transactions (
+                id, user_id, amount, transaction_type, 
+                reference_id, description, created_at, available_change
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            transaction.transaction_id.to_string(),
+            transaction.user_id.to_string(),
+            transaction.amount,
+            i32::from(transaction.transaction_type),
+            transaction.reference_id,
+            transaction.description,
+            transaction.created_at,
+            transaction.available_change
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved balance transaction {}", transaction.transaction_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save balance transaction {}: {}", transaction.transaction_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets all balance transactions for a user
+    async fn get_balance_transactions_for_user(&self, user_id: Uuid) -> Result<Vec<BalanceTransaction>> {
+        let transaction_rows = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, amount, transaction_type, 
+                reference_id, description, created_at, available_change
+            FROM balance_transactions
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let transactions = transaction_rows.into_iter().map(|row| {
+            BalanceTransaction {
+                transaction_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                amount: row.amount,
+                available_change: row.available_change,
+                transaction_type: TransactionType::from(row.transaction_type),
+                reference_id: row.reference_id,
+                description: row.description,
+                created_at: row.created_at,
+            }
+        }).collect();
+        
+        Ok(transactions)
+    }
+    
+    /// Gets a user's balance transaction of a type by its reference
+    async fn find_balance_transaction(
+        &self,
+        user_id: Uuid,
+        transaction_type: TransactionType,
+        reference_id: &str,
+    ) -> Result<Option<BalanceTransaction>> {
+        let transaction_row = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, amount, transaction_type, 
+                reference_id, description, created_at, available_change
+            FROM balance_transactions
+            WHERE user_id = $1 AND transaction_type = $2 AND reference_id = $3
+            ORDER BY created_at
+            LIMIT 1
+            "#,
+            user_id.to_string(),
+            i32::from(transaction_type),
+            reference_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        let Some(row) = transaction_row else {
+            return Ok(None);
+        };
+        
+        Ok(Some(BalanceTransaction {
+            transaction_id: Uuid::parse_str(&row.id)?,
+            user_id: Uuid::parse_str(&row.user_id)?,
+            amount: row.amount,
+            available_change: row.available_change,
+            transaction_type: TransactionType::from(row.transaction_type),
+            reference_id: row.reference_id,
+            description: row.description,
+            created_at: row.created_at,
+        }))
+    }
+    
+    /// Gets every user balance
+    async fn get_all_user_balances(&self) -> Result<Vec<UserBalance>> {
+        let balance_rows = sqlx::query!(
+            r#"
+            SELECT 
+                user_id, available_balance, reserved_balance,
+                winnings_balance, promo_credits, reserved_promo, promo_reserves, promo_positions,
+                updated_at, version
+            FROM user_balances
+            ORDER BY user_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut balances = Vec::with_capacity(balance_rows.len());
+        for row in balance_rows {
+            balances.push(UserBalance {
+                user_id: Uuid::parse_str(&row.user_id)?,
+                available_balance: row.available_balance,
+                reserved_balance: row.reserved_balance,
+                winnings_balance: row.winnings_balance,
+                promo_credits: serde_json::from_value(row.promo_credits)?,
+                reserved_promo: row.reserved_promo,
+                promo_reserves: serde_json::from_value(row.promo_reserves)?,
+                promo_positions: serde_json::from_value(row.promo_positions)?,
+                updated_at: row.updated_at,
+                version: row.version,
+            });
+        }
+        
+        Ok(balances)
+    }
+    
+    /// Saves user balances together with the transactions and ledger entries that explain them in one transaction
+    async fn post_ledger_entries(
+        &self,
+        balances: &[UserBalance],
+        transactions: &[BalanceTransaction],
+        entries: &[LedgerEntry],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::insert_ledger_entries(&mut tx, balances, transactions, entries).await?;
+        
+        tx.commit().await.map_err(Self::ledger_commit_error)?;
+        debug!("Posted {} ledger entries", entries.len());
+        Ok(())
+    }
+    
+    /// Saves a user's settlement in a market together with the funds it moves in one transaction
+    async fn post_settlement(
+        &self,
+        balance: &UserBalance,
+        transactions: &[BalanceTransaction],
+        entries: &[LedgerEntry],
+        settlement: &PositionSettlement,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::insert_ledger_entries(&mut tx, std::slice::from_ref(balance), transactions, entries).await?;
+        
+        let entry = &settlement.entry;
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO settlement_entries (
+                market_id, user_id, positions, cost_basis,
+                payout, realized_pnl, settled_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            settlement.market_id,
+            entry.user_id.to_string(),
+            &entry.positions,
+            entry.cost_basis,
+            entry.payout,
+            entry.realized_pnl,
+            settlement.settled_at
+        )
+        .execute(&mut *tx)
+        .await;
+        
+        if let Err(e) = result {
+            error!("Failed to save settlement entry for user {} in market {}: {}", entry.user_id, settlement.market_id, e);
+            return Err(anyhow!(e));
+        }
+        
+        if let Some(withholding) = &settlement.withholding {
+            let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+                r#"
+                INSERT INTO tax_withholdings (
+                    id, user_id, market_id, financial_year, net_winnings,
+                    taxable_winnings, rate, amount, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+                withholding.withholding_id.to_string(),
+                withholding.user_id.to_string(),
+                withholding.market_id,
+                withholding.financial_year,
+                withholding.net_winnings,
+                withholding.taxable_winnings,
+                withholding.rate,
+                withholding.amount,
+                withholding.created_at
+            )
+            .execute(&mut *tx)
+            .await;
+            
+            if let Err(e) = result {
+                error!("Failed to save tax withholding {}: {}", withholding.withholding_id, e);
+                return Err(anyhow!(e));
+            }
+        }
+        
+        tx.commit().await.map_err(Self::ledger_commit_error)?;
+        debug!("Posted settlement of user {} in market {}", entry.user_id, settlement.market_id);
+        Ok(())
+    }
+    
+    /// Gets the balance of a ledger account
+    async fn get_ledger_balance(&self, account: &LedgerAccount) -> Result<rust_decimal::Decimal> {
+        let row = sqlx::query!(
+            r#"
+            SELECT 
+                COALESCE(SUM(CASE
+                    WHEN credit_account_type = $1
+                        AND credit_owner_id IS NOT DISTINCT FROM $2
+                        AND credit_holder_id IS NOT DISTINCT FROM $3 THEN amount
+                    ELSE -amount
+                END), 0) AS "balance!"
+            FROM ledger_entries
+            WHERE (credit_account_type = $1 AND credit_owner_id IS NOT DISTINCT FROM $2 AND credit_holder_id IS NOT DISTINCT FROM $3)
+               OR (debit_account_type = $1 AND debit_owner_id IS NOT DISTINCT FROM $2 AND debit_holder_id IS NOT DISTINCT FROM $3)
+            "#,
+            i32::from(account.account_type),
+            account.owner_id,
+            account.holder_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(row.balance)
+    }
+    
+    /// Gets the balance of every ledger account that has entries
+    async fn get_ledger_balances(&self) -> Result<Vec<LedgerBalance>> {
+        let balance_rows = sqlx::query!(
+            r#"
+            SELECT 
+                account_type AS "account_type!", owner_id, holder_id,
+                SUM(amount) AS "balance!"
+            FROM (
+                SELECT credit_account_type AS account_type, credit_owner_id AS owner_id,
+                    credit_holder_id AS holder_id, amount
+                FROM ledger_entries
+                UNION ALL
+                SELECT debit_account_type, debit_owner_id, debit_holder_id, -amount
+                FROM ledger_entries
+            ) AS movements
+            GROUP BY account_type, owner_id, holder_id
+            ORDER BY account_type, owner_id, holder_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let balances = balance_rows.into_iter().map(|row| {
+            LedgerBalance {
+                account: LedgerAccount {
+                    account_type: LedgerAccountType::from(row.account_type),
+                    owner_id: row.owner_id,
+                    holder_id: row.holder_id,
+                },
+                balance: row.balance,
+            }
+        }).collect();
+        
+        Ok(balances)
+    }
+    
+    /// Gets the balance of every ledger account belonging to a market
+    async fn get_market_ledger_balances(&self, market_id: &str) -> Result<Vec<LedgerBalance>> {
+        let market_account_types: Vec<i32> = [
+            LedgerAccountType::MarketEscrow,
+            LedgerAccountType::AmmSubsidy,
+            LedgerAccountType::MarketCollateral,
+        ].into_iter().map(i32::from).collect();
+        
+        let balance_rows = sqlx::query!(
+            r#"
+            SELECT 
+                account_type AS "account_type!", holder_id,
+                SUM(amount) AS "balance!"
+            FROM (
+                SELECT credit_account_type AS account_type, credit_holder_id AS holder_id, amount
+                FROM ledger_entries
+                WHERE credit_owner_id = $1 AND credit_account_type = ANY($2)
+                UNION ALL
+                SELECT debit_account_type, debit_holder_id, -amount
+                FROM ledger_entries
+                WHERE debit_owner_id = $1 AND debit_account_type = ANY($2)
+            ) AS movements
+            GROUP BY account_type, holder_id
+            ORDER BY account_type, holder_id
+            "#,
+            market_id,
+            &market_account_types
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let balances = balance_rows.into_iter().map(|row| {
+            LedgerBalance {
+                account: LedgerAccount {
+                    account_type: LedgerAccountType::from(row.account_type),
+                    owner_id: Some(market_id.to_string()),
+                    holder_id: row.holder_id,
+                },
+                balance: row.balance,
+            }
+        }).collect();
+        
+        Ok(balances)
+    }
+    
+    /// Gets the balance of every market account holding a user's funds (collateral and market maker pools)
+    async fn get_market_reserves_for_user(&self, user_id: Uuid) -> Result<Vec<LedgerBalance>> {
+        let reserve_types: Vec<i32> = [
+            LedgerAccountType::AmmSubsidy,
+            LedgerAccountType::MarketCollateral,
+        ].into_iter().map(i32::from).collect();
+        
+        let balance_rows = sqlx::query!(
+            r#"
+            SELECT 
+                account_type AS "account_type!", owner_id,
+                SUM(amount) AS "balance!"
+            FROM (
+                SELECT credit_account_type AS account_type, credit_owner_id AS owner_id, amount
+                FROM ledger_entries
+                WHERE credit_account_type = ANY($1) AND credit_holder_id = $2
+                UNION ALL
+                SELECT debit_account_type, debit_owner_id, -amount
+                FROM ledger_entries
+                WHERE debit_account_type = ANY($1) AND debit_holder_id = $2
+            ) AS movements
+            GROUP BY account_type, owner_id
+            ORDER BY owner_id, account_type
+            "#,
+            &reserve_types,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let balances = balance_rows.into_iter().map(|row| {
+            LedgerBalance {
+                account: LedgerAccount {
+                    account_type: LedgerAccountType::from(row.account_type),
+                    owner_id: row.owner_id,
+                    holder_id: Some(user_id.to_string()),
+                },
+                balance: row.balance,
+            }
+        }).collect();
+        
+        Ok(balances)
+    }
+    
+    /// Saves a new idempotency key, returning false if the key is already in use on its endpoint
+    async fn claim_idempotency_key(&self, record: &IdempotencyRecord) -> Result<bool> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (
+                endpoint, idempotency_key, request_body, created_at
+            )
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (endpoint, idempotency_key) DO NOTHING
+            "#,
+            record.endpoint,
+            record.idempotency_key,
+            record.request_body,
+            record.created_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(e) => {
+                error!("Failed to save idempotency key {} for {}: {}", record.idempotency_key, record.endpoint, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets an idempotency key used on an endpoint
+    async fn get_idempotency_key(&self, endpoint: &str, idempotency_key: &str) -> Result<Option<IdempotencyRecord>> {
+        let record_row = sqlx::query!(
+            r#"
+            SELECT 
+                endpoint, idempotency_key, request_body, status_code,
+                response_body, created_at, completed_at
+            FROM idempotency_keys
+            WHERE endpoint = $1 AND idempotency_key = $2
+            "#,
+            endpoint,
+            idempotency_key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(record_row.map(|row| IdempotencyRecord {
+            endpoint: row.endpoint,
+            idempotency_key: row.idempotency_key,
+            request_body: row.request_body,
+            status_code: row.status_code.map(|code| code as u16),
+            response_body: row.response_body,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        }))
+    }
+    
+    /// Stores the response to the request made with an idempotency key
+    async fn complete_idempotency_key(&self, endpoint: &str, idempotency_key: &str, status_code: u16, response_body: &str) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET status_code = $3, response_body = $4, completed_at = NOW()
+            WHERE endpoint = $1 AND idempotency_key = $2
+            "#,
+            endpoint,
+            idempotency_key,
+            status_code as i32,
+            response_body
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Stored response for idempotency key {} on {}", idempotency_key, endpoint);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to store response for idempotency key {} on {}: {}", idempotency_key, endpoint, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Deletes an idempotency key so it can be used again
+    async fn delete_idempotency_key(&self, endpoint: &str, idempotency_key: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE endpoint = $1 AND idempotency_key = $2
+            "#,
+            endpoint,
+            idempotency_key
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Saves a new withdrawal request
+    async fn save_withdrawal(&self, withdrawal: &Withdrawal) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO withdrawals (
+                id, user_id, amount, destination, status,
+                payout_reference, review_note, created_at, reviewed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            withdrawal.withdrawal_id.to_string(),
+            withdrawal.user_id.to_string(),
+            withdrawal.amount,
+            withdrawal.destination,
+            i32::from(withdrawal.status),
+            withdrawal.payout_reference,
+            withdrawal.review_note,
+            withdrawal.created_at,
+            withdrawal.reviewed_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved withdrawal {}", withdrawal.withdrawal_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save withdrawal {}: {}", withdrawal.withdrawal_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Saves a reviewed withdrawal if it still has the status it was read with, returning whether it did
+    async fn update_withdrawal(&self, withdrawal: &Withdrawal, previous_status: WithdrawalStatus) -> Result<bool> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            UPDATE withdrawals SET
+                status = $2,
+                payout_reference = $3,
+                review_note = $4,
+                reviewed_at = $5
+            WHERE id = $1 AND status = $6
+            "#,
+            withdrawal.withdrawal_id.to_string(),
+            i32::from(withdrawal.status),
+            withdrawal.payout_reference,
+            withdrawal.review_note,
+            withdrawal.reviewed_at,
+            i32::from(previous_status)
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(result) => {
+                debug!("Updated withdrawal {} to {:?}", withdrawal.withdrawal_id, withdrawal.status);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(e) => {
+                error!("Failed to update withdrawal {}: {}", withdrawal.withdrawal_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets a withdrawal by ID
+    async fn get_withdrawal(&self, withdrawal_id: Uuid) -> Result<Withdrawal> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id, user_id, amount, destination, status,
+                payout_reference, review_note, created_at, reviewed_at
+            FROM withdrawals
+            WHERE id = $1
+            "#,
+            withdrawal_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Withdrawal {} not found", withdrawal_id))?;
+        
+        Ok(Withdrawal {
+            withdrawal_id: Uuid::parse_str(&row.id)?,
+            user_id: Uuid::parse_str(&row.user_id)?,
+            amount: row.amount,
+            destination: row.destination,
+            status: WithdrawalStatus::from(row.status),
+            payout_reference: row.payout_reference,
+            review_note: row.review_note,
+            created_at: row.created_at,
+            reviewed_at: row.reviewed_at,
+        })
+    }
+    
+    /// Gets withdrawals, newest first, optionally only a user's or only those with a status
+    async fn get_withdrawals(&self, user_id: Option<Uuid>, status: Option<WithdrawalStatus>) -> Result<Vec<Withdrawal>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                id, user_id, amount, destination, status,
+                payout_reference, review_note, created_at, reviewed_at
+            FROM withdrawals
+            WHERE ($1::TEXT IS NULL OR user_id = $1)
+              AND ($2::INTEGER IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            "#,
+            user_id.map(|user_id| user_id.to_string()),
+            status.map(i32::from)
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let withdrawals = rows.into_iter().map(|row| {
+            Withdrawal {
+                withdrawal_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                amount: row.amount,
+                destination: row.destination,
+                status: WithdrawalStatus::from(row.status),
+                payout_reference: row.payout_reference,
+                review_note: row.review_note,
+                created_at: row.created_at,
+                reviewed_at: row.reviewed_at,
+            }
+        }).collect();
+        
+        Ok(withdrawals)
+    }
+    
+    /// Gets the total a user has asked to withdraw since a time, leaving out rejected requests
+    async fn get_withdrawn_since(&self, user_id: Uuid, since: chrono::DateTime<chrono::Utc>) -> Result<rust_decimal::Decimal> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COALESCE(SUM(amount), 0) AS "total!"
+            FROM withdrawals
+            WHERE user_id = $1 AND created_at >= $2 AND status <> $3
+            "#,
+            user_id.to_string(),
+            since,
+            i32::from(WithdrawalStatus::Rejected)
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(row.total)
+    }
+    
+    /// Saves a new deposit
+    async fn save_deposit(&self, deposit: &Deposit) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO deposits (
+                id, user_id, amount, status, provider_reference,
+                checkout_url, failure_reason, created_at, completed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            deposit.deposit_id.to_string(),
+            deposit.user_id.to_string(),
+            deposit.amount,
+            i32::from(deposit.status),
+            deposit.provider_reference,
+            deposit.checkout_url,
+            deposit.failure_reason,
+            deposit.created_at,
+            deposit.completed_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved deposit {}", deposit.deposit_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save deposit {}: {}", deposit.deposit_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Saves a paid or failed deposit if it still has the status it was read with, returning whether it did
+    async fn update_deposit(&self, deposit: &Deposit, previous_status: DepositStatus) -> Result<bool> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            UPDATE deposits SET
+                status = $2,
+                failure_reason = $3,
+                completed_at = $4
+            WHERE id = $1 AND status = $5
+            "#,
+            deposit.deposit_id.to_string(),
+            i32::from(deposit.status),
+            deposit.failure_reason,
+            deposit.completed_at,
+            i32::from(previous_status)
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(result) => {
+                debug!("Updated deposit {} to {:?}", deposit.deposit_id, deposit.status);
+                Ok(result.rows_affected() > 0)
+            }
+            Err(e) => {
+                error!("Failed to update deposit {}: {}", deposit.deposit_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets a deposit by ID
+    async fn get_deposit(&self, deposit_id: Uuid) -> Result<Deposit> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id, user_id, amount, status, provider_reference,
+                checkout_url, failure_reason, created_at, completed_at
+            FROM deposits
+            WHERE id = $1
+            "#,
+            deposit_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Deposit {} not found", deposit_id))?;
+        
+        Ok(Deposit {
+            deposit_id: Uuid::parse_str(&row.id)?,
+            user_id: Uuid::parse_str(&row.user_id)?,
+            amount: row.amount,
+            status: DepositStatus::from(row.status),
+            provider_reference: row.provider_reference,
+            checkout_url: row.checkout_url,
+            failure_reason: row.failure_reason,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        })
+    }
+    
+    /// Gets a user's deposits, newest first
+    async fn get_deposits_for_user(&self, user_id: Uuid) -> Result<Vec<Deposit>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                id, user_id, amount, status, provider_reference,
+                checkout_url, failure_reason, created_at, completed_at
+            FROM deposits
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let deposits = rows.into_iter().map(|row| {
+            Deposit {
+                deposit_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                amount: row.amount,
+                status: DepositStatus::from(row.status),
+                provider_reference: row.provider_reference,
+                checkout_url: row.checkout_url,
+                failure_reason: row.failure_reason,
+                created_at: row.created_at,
+                completed_at: row.completed_at,
+            }
+        }).collect();
+        
+        Ok(deposits)
+    }
+    
+    /// Saves a new grant of promotional credit
+    async fn save_promo_credit(&self, credit: &PromoCredit) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO promo_credits (id, user_id, amount, reason, granted_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            credit.credit_id.to_string(),
+            credit.user_id.to_string(),
+            credit.amount,
+            credit.reason,
+            credit.granted_at,
+            credit.expires_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved promotional credit {}", credit.credit_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save promotional credit {}: {}", credit.credit_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets the promotional credit granted to a user, newest first
+    async fn get_promo_credits_for_user(&self, user_id: Uuid) -> Result<Vec<PromoCredit>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, user_id, amount, reason, granted_at, expires_at
+            FROM promo_credits
+            WHERE user_id = $1
+            ORDER BY granted_at DESC
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let credits = rows.into_iter().map(|row| {
+            PromoCredit {
+                credit_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                amount: row.amount,
+                reason: row.reason,
+                granted_at: row.granted_at,
+                expires_at: row.expires_at,
+            }
+        }).collect();
+        
+        Ok(credits)
+    }
+    
+    /// Gets a user's tax withholdings, oldest first, optionally for one financial year only
+    async fn get_tax_withholdings_for_user(&self, user_id: Uuid, financial_year: Option<i32>) -> Result<Vec<TaxWithholding>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                id, user_id, market_id, financial_year, net_winnings,
+                taxable_winnings, rate, amount, created_at
+            FROM tax_withholdings
+            WHERE user_id = $1 AND ($2::INTEGER IS NULL OR financial_year = $2)
+            ORDER BY created_at
+            "#,
+            user_id.to_string(),
+            financial_year
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let withholdings = rows.into_iter().map(|row| {
+            TaxWithholding {
+                withholding_id: Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::nil()),
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                market_id: row.market_id,
+                financial_year: row.financial_year,
+                net_winnings: row.net_winnings,
+                taxable_winnings: row.taxable_winnings,
+                rate: row.rate,
+                amount: row.amount,
+                created_at: row.created_at,
+            }
+        }).collect();
+        
+        Ok(withholdings)
+    }
+    
+    /// Gets every risk tier
+    async fn get_risk_tiers(&self) -> Result<Vec<RiskTier>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT name, max_open_orders, max_position, max_market_notional, max_total_exposure, max_order_value
+            FROM risk_tiers
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let tiers = rows.into_iter().map(|row| {
+            RiskTier {
+                name: row.name,
+                limits: RiskLimits {
+                    max_open_orders: row.max_open_orders.map(|limit| limit.max(0) as u32),
+                    max_position: row.max_position,
+                    max_market_notional: row.max_market_notional,
+                    max_total_exposure: row.max_total_exposure,
+                    max_order_value: row.max_order_value,
+                },
+            }
+        }).collect();
+        
+        Ok(tiers)
+    }
+    
+    /// Gets a risk tier by name
+    async fn get_risk_tier(&self, name: &str) -> Result<Option<RiskTier>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT name, max_open_orders, max_position, max_market_notional, max_total_exposure, max_order_value
+            FROM risk_tiers
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|row| RiskTier {
+            name: row.name,
+            limits: RiskLimits {
+                max_open_orders: row.max_open_orders.map(|limit| limit.max(0) as u32),
+                max_position: row.max_position,
+                max_market_notional: row.max_market_notional,
+                max_total_exposure: row.max_total_exposure,
+                max_order_value: row.max_order_value,
+            },
+        }))
+    }
+    
+    /// Saves a risk tier, replacing its limits if it exists
+    async fn save_risk_tier(&self, tier: &RiskTier) -> Result<()> {
+        let limits = &tier.limits;
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO risk_tiers (name, max_open_orders, max_position, max_market_notional, max_total_exposure, max_order_value)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (name) DO UPDATE SET
+                max_open_orders = $2,
+                max_position = $3,
+                max_market_notional = $4,
+                max_total_exposure = $5,
+                max_order_value = $6
+            "#,
+            tier.name,
+            limits.max_open_orders.map(|limit| limit as i32),
+            limits.max_position,
+            limits.max_market_notional,
+            limits.max_total_exposure,
+            limits.max_order_value
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved risk tier {}", tier.name);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save risk tier {}: {}", tier.name, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets the name of the risk tier a user is assigned to, if any
+    async fn get_user_risk_tier(&self, user_id: Uuid) -> Result<Option<String>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT tier
+            FROM user_risk_tiers
+            WHERE user_id = $1
+            "#,
+            user_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|row| row.tier))
+    }
+    
+    /// Assigns a user to a risk tier
+    async fn save_user_risk_tier(&self, user_id: Uuid, tier: &str) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO user_risk_tiers (user_id, tier)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET tier = $2
+            "#,
+            user_id.to_string(),
+            tier
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Assigned user {} to risk tier {}", user_id, tier);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to assign user {} to risk tier {}: {}", user_id, tier, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets the risk limits set for a market, if any
+    async fn get_market_risk_limits(&self, market_id: &str) -> Result<Option<RiskLimits>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT max_open_orders, max_position, max_market_notional, max_total_exposure, max_order_value
+            FROM market_risk_limits
+            WHERE market_id = $1
+            "#,
+            market_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|row| RiskLimits {
+            max_open_orders: row.max_open_orders.map(|limit| limit.max(0) as u32),
+            max_position: row.max_position,
+            max_market_notional: row.max_market_notional,
+            max_total_exposure: row.max_total_exposure,
+            max_order_value: row.max_order_value,
+        }))
+    }
+    
+    /// Saves the risk limits for a market, replacing any it had
+    async fn save_market_risk_limits(&self, market_id: &str, limits: &RiskLimits) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO market_risk_limits (market_id, max_open_orders, max_position, max_market_notional, max_total_exposure, max_order_value)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (market_id) DO UPDATE SET
+                max_open_orders = $2,
+                max_position = $3,
+                max_market_notional = $4,
+                max_total_exposure = $5,
+                max_order_value = $6
+            "#,
+            market_id,
+            limits.max_open_orders.map(|limit| limit as i32),
+            limits.max_position,
+            limits.max_market_notional,
+            limits.max_total_exposure,
+            limits.max_order_value
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved risk limits for market {}", market_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save risk limits for market {}: {}", market_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Saves an API key, updating it if it exists
+    async fn save_api_key(&self, api_key: &ApiKey) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO api_keys (key_id, user_id, secret, label, created_at, revoked_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (key_id) DO UPDATE SET
+                label = $4,
+                revoked_at = $6
+            "#,
+            api_key.key_id,
+            api_key.user_id.to_string(),
+            api_key.secret,
+            api_key.label,
+            api_key.created_at,
+            api_key.revoked_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved API key {} for user {}", api_key.key_id, api_key.user_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save API key {} for user {}: {}", api_key.key_id, api_key.user_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Gets an API key by its ID
+    async fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT key_id, user_id, secret, label, created_at, revoked_at
+            FROM api_keys
+            WHERE key_id = $1
+            "#,
+            key_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|row| ApiKey {
+            key_id: row.key_id,
+            user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+            secret: row.secret,
+            label: row.label,
+            created_at: row.created_at,
+            revoked_at: row.revoked_at,
+        }))
+    }
+    
+    /// Gets a user's API keys, newest first
+    async fn get_api_keys_for_user(&self, user_id: Uuid) -> Result<Vec<ApiKey>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT key_id, user_id, secret, label, created_at, revoked_at
+            FROM api_keys
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let api_keys = rows.into_iter().map(|row| {
+            ApiKey {
+                key_id: row.key_id,
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                secret: row.secret,
+                label: row.label,
+                created_at: row.created_at,
+                revoked_at: row.revoked_at,
+            }
+        }).collect();
+        
+        Ok(api_keys)
+    }
+    
+    /// Saves the nonce a request signed with an API key carried, returning false if the key has already used it
+    async fn save_request_nonce(&self, key_id: &str, nonce: &str, created_at: chrono::DateTime<chrono::Utc>) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO api_request_nonces (key_id, nonce, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key_id, nonce) DO NOTHING
+            "#,
+            key_id,
+            nonce,
+            created_at
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Deletes request nonces saved before a time, returning how many were deleted
+    async fn delete_request_nonces_before(&self, before: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM api_request_nonces
+            WHERE created_at < $1
+            "#,
+            before
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Saves a role granted to a user, keeping the original grant if they already hold it
+    async fn save_role_grant(&self, grant: &RoleGrant) -> Result<()> {
+        let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role, granted_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, role) DO NOTHING
+            "#,
+            grant.user_id.to_string(),
+            i32::from(grant.role),
+            grant.granted_at
+        )
+        .execute(&self.pool)
+        .await;
+        
+        match result {
+            Ok(_) => {
+                debug!("Saved {:?} role for user {}", grant.role, grant.user_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to save {:?} role for user {}: {}", grant.role, grant.user_id, e);
+                Err(anyhow!(e))
+            }
+        }
+    }
+    
+    /// Removes a role from a user, returning whether they held it
+    async fn delete_role_grant(&self, user_id: Uuid, role: Role) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_roles
+            WHERE user_id = $1 AND role = $2
+            "#,
+            user_id.to_string(),
+            i32::from(role)
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Gets the roles granted to a user, oldest first
+    async fn get_role_grants_for_user(&self, user_id: Uuid) -> Result<Vec<RoleGrant>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id, role, granted_at
+            FROM user_roles
+            WHERE user_id = $1
+            ORDER BY granted_at ASC
+            "#,
+            user_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let grants = rows.into_iter().map(|row| {
+            RoleGrant {
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                role: Role::from(row.role),
+                granted_at: row.granted_at,
+            }
+        }).collect();
+        
+        Ok(grants)
+    }
+    
+    /// Gets the grants of a role to any user, oldest first
+    async fn get_role_grants(&self, role: Role) -> Result<Vec<RoleGrant>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id, role, granted_at
+            FROM user_roles
+            WHERE role = $1
+            ORDER BY granted_at ASC
+            "#,
+            i32::from(role)
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let grants = rows.into_iter().map(|row| {
+            RoleGrant {
+                user_id: Uuid::parse_str(&row.user_id).unwrap_or_else(|_| Uuid::nil()),
+                role: Role::from(row.role),
+                granted_at: row.granted_at,
+            }
+        }).collect();
+        
+        Ok(grants)
+    }
+}
+
+impl SqlxRepository {
+    /// Creates a new repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Turns the ledger refusing to overdraw an account at commit into an error naming the account
+    fn ledger_commit_error(e: sqlx::Error) -> anyhow::Error {
+        match e.as_database_error() {
+            Some(db_error) if db_error.code().as_deref() == Some("23514") => anyhow!("{}", db_error.message()),
+            _ => anyhow!(e),
+        }
+    }
+    
+    /// Saves user balances, transactions and ledger entries within a transaction
+    ///
+    /// The error is a `BalanceConflict` if any of the balances changed since it was read.
+    async fn insert_ledger_entries(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        balances: &[UserBalance],
+        transactions: &[BalanceTransaction],
+        entries: &[LedgerEntry],
+    ) -> Result<()> {
+        for balance in balances {
+            let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+                r#"
+                INSERT INTO user_balances (
+                    user_id, available_balance, reserved_balance, winnings_balance,
+                    promo_credits, reserved_promo, promo_reserves, promo_positions, updated_at, version
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::BIGINT + 1)
+                ON CONFLICT (user_id) DO UPDATE SET
+                    available_balance = $2,
+                    reserved_balance = $3,
+                    winnings_balance = $4,
+                    promo_credits = $5,
+                    reserved_promo = $6,
+                    promo_reserves = $7,
+                    promo_positions = $8,
+                    updated_at = $9,
+                    version = user_balances.version + 1
+                WHERE user_balances.version = $10
+                "#,
+                balance.user_id.to_string(),
+                balance.available_balance,
+                balance.reserved_balance,
+                balance.winnings_balance,
+                serde_json::to_value(&balance.promo_credits)?,
+                balance.reserved_promo,
+                serde_json::to_value(&balance.promo_reserves)?,
+                serde_json::to_value(&balance.promo_positions)?,
+                balance.updated_at,
+                balance.version
+            )
+            .execute(&mut **tx)
+            .await;
+            
+            // Nothing is written if the balance changed since it was read
+            match result {
+                Ok(result) if result.rows_affected() == 0 => {
+                    debug!("User balance for user {} changed since version {}", balance.user_id, balance.version);
+                    return Err(BalanceConflict { user_id: balance.user_id, version: balance.version }.into());
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error!("Failed to save user balance for user {}: {}", balance.user_id, e);
+                    return Err(anyhow!(e));
+                }
+            }
+        }
+        
+        for transaction in transactions {
+            let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+                r#"
+                INSERT INTO balance_transactions (
+                    id, user_id, amount, transaction_type, 
+                    reference_id, description, created_at, available_change
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                transaction.transaction_id.to_string(),
+                transaction.user_id.to_string(),
+                transaction.amount,
+                i32::from(transaction.transaction_type),
+                transaction.reference_id,
+                transaction.description,
+                transaction.created_at,
+                transaction.available_change
+            )
+            .execute(&mut **tx)
+            .await;
+            
+            if let Err(e) = result {
+                error!("Failed to save balance transaction {}: {}", transaction.transaction_id, e);
+                return Err(anyhow!(e));
+            }
+        }
+        
+        for entry in entries {
+            let result: Result<sqlx::postgres::PgQueryResult, sqlx::Error> = sqlx::query!(
+                r#"
+                INSERT INTO ledger_entries (
+                    id, debit_account_type, debit_owner_id, debit_holder_id,
+                    credit_account_type, credit_owner_id, credit_holder_id, amount,
+                    transaction_type, reference_id, description, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                "#,
+                entry.entry_id.to_string(),
+                i32::from(entry.debit_account.account_type),
+                entry.debit_account.owner_id,
+                entry.debit_account.holder_id,
+                i32::from(entry.credit_account.account_type),
+                entry.credit_account.owner_id,
+                entry.credit_account.holder_id,
+                entry.amount,
+                i32::from(entry.transaction_type),
+                entry.reference_id,
+                entry.description,
+                entry.created_at
+            )
+            .execute(&mut **tx)
+            .await;
+            
+            if let Err(e) = result {
+                error!("Failed to save ledger entry {}: {}", entry.entry_id, e);
+                return Err(anyhow!(e));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Helper method to get active orders for a market
+    async fn get_active_orders_for_market(&self, market_id: &str) -> Result<Vec<Order>> {
+        let order_rows = sqlx::query!(
+            r#"
+            SELECT 
+                id, user_id, market_id, side, outcome, 
+                price, quantity, remaining_quantity, covered_quantity, status,
+                time_in_force, client_order_id, created_at, updated_at
             FROM orders
             WHERE market_id = $1 AND status < 3
             "#,
@@ -451,6 +2494,9 @@ impl SqlxRepository {
                 price: row.price,
                 quantity: row.quantity as u32,
                 remaining_quantity: row.remaining_quantity as u32,
+                covered_quantity: row.covered_quantity as u32,
+                time_in_force: TimeInForce::from(row.time_in_force),
+                client_order_id: row.client_order_id,
                 status: OrderStatus::from(row.status),
                 created_at: row.created_at,
                 updated_at: row.updated_at,
@@ -9,10 +9,15 @@ pub use db::{PgPool, Repository, SqlxRepository};
 // Re-export model types
 pub use models::{
     Market,
+    Event,
+    Position,
+    amm::LmsrMarketMaker,
     order::{Order, OrderSide, OrderStatus, OutcomeSide},
     trade::Trade,
-    balance::{UserBalance, BalanceTransaction, TransactionType},
+    balance::{UserBalance, BalanceConflict, BalanceTransaction, TransactionType},
+    ledger::{LedgerAccount, LedgerEntry},
+    idempotency::IdempotencyRecord,
 };
 
-pub use services::{MatchingEngine, OrderService, BotService, BotStrategy, BotConfig, SettlementService, BalanceService};
-pub use api::{ApiResponse, WebSocketEvent, WebSocketServer}; 
\ No newline at end of file
+pub use services::{MatchingEngine, OrderService, BotService, BotStrategy, BotConfig, SettlementService, BalanceService, AmmService, EventService, PositionService, PortfolioService, LedgerService, IdempotencyService, ReconciliationService, WithdrawalService, PayoutProvider, LocalPayoutProvider, DepositService, PaymentProvider, MockPaymentProvider, PromoService, TaxService, RiskService, AuthService};
+pub use api::{ApiResponse, RateLimit, RateLimiter, WebSocketEvent, WebSocketServer}; 
\ No newline at end of file
@@ -1,18 +1,29 @@
+// The combined API filter nests deeper than the default limit allows
+#![recursion_limit = "256"]
+
 use std::sync::Arc;
 use std::env;
-use log::info;
-use tokio::sync::{mpsc, Mutex};
+use std::path::Path;
+use std::time::Duration;
+use log::{info, warn};
+use tokio::sync::Mutex;
 use warp::{self, Filter};
-use uuid::Uuid;
 use dotenv::dotenv;
+use rust_decimal::Decimal;
 
 use prediction_engine::{
     BotConfig, BotService, 
     MatchingEngine, OrderService, SettlementService,
-    WebSocketServer, SqlxRepository, BalanceService
+    WebSocketServer, SqlxRepository, BalanceService, AmmService, EventService, PositionService,
+    PortfolioService, LedgerService, IdempotencyService, ReconciliationService,
+    WithdrawalService, LocalPayoutProvider, DepositService, MockPaymentProvider,
+    PromoService, TaxService, RiskService, AuthService, RateLimit, RateLimiter
 };
-use prediction_engine::api::routes;
+use prediction_engine::models::MarkPriceSource;
+use prediction_engine::api::{rate_limited, routes};
+use prediction_engine::api::auth::{AuthenticatedUser, handle_rejection, optionally_authenticated};
 use prediction_engine::db::create_pg_pool;
+use prediction_engine::services::auth_service::MAX_TIMESTAMP_SKEW_SECS;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -34,26 +45,173 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let pg_pool = create_pg_pool().await?;
     let repository = Arc::new(SqlxRepository::new(pg_pool));
     
+    // Limit how fast each API key, user and IP can enter orders and query data
+    let rate_limit = |per_second_var: &str, default_per_second: f64, burst_var: &str, default_burst: u32| {
+        let per_second = env::var(per_second_var)
+            .ok()
+            .and_then(|rate| rate.parse::<f64>().ok())
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(default_per_second);
+        let burst = env::var(burst_var)
+            .ok()
+            .and_then(|burst| burst.parse::<u32>().ok())
+            .filter(|burst| *burst > 0)
+            .unwrap_or(default_burst);
+        RateLimit::new(per_second, burst)
+    };
+    let rate_limiter = Arc::new(RateLimiter::new(
+        rate_limit("RATE_LIMIT_ORDERS_PER_SEC", 10.0, "RATE_LIMIT_ORDERS_BURST", 20),
+        rate_limit("RATE_LIMIT_QUERIES_PER_SEC", 50.0, "RATE_LIMIT_QUERIES_BURST", 100)
+    ));
+    
     // Create WebSocket server for real-time notifications
-    let ws_server = Arc::new(WebSocketServer::new(1000));
+    let ws_server = Arc::new(WebSocketServer::new(1000, Arc::clone(&rate_limiter)));
     
     // Create channels for event notifications
     let trade_sender = ws_server.get_trade_receiver(Arc::clone(&repository));
-    let (payout_sender, _payout_receiver) = mpsc::channel::<(Uuid, rust_decimal::Decimal)>(100);
+    let event_update_sender = ws_server.get_event_update_receiver();
+    let market_status_sender = ws_server.get_market_status_receiver();
+    let payout_sender = ws_server.get_payout_receiver();
     
     // Create services
     let matching_engine = Arc::new(Mutex::new(MatchingEngine::new(trade_sender)));
     let balance_service = Arc::new(BalanceService::new(Arc::clone(&repository)));
+    let position_service = Arc::new(PositionService::new(Arc::clone(&repository)));
+    let amm_service = Arc::new(AmmService::new(
+        Arc::clone(&repository),
+        Arc::clone(&balance_service)
+    ));
+    let risk_service = Arc::new(RiskService::new(Arc::clone(&repository)));
     let order_service = Arc::new(OrderService::new(
         Arc::clone(&repository), 
         Arc::clone(&matching_engine),
-        Arc::clone(&balance_service)
+        Arc::clone(&balance_service),
+        Arc::clone(&amm_service),
+        Arc::clone(&position_service),
+        Arc::clone(&risk_service),
+        market_status_sender
+    ));
+    
+    // Withhold TDS on net winnings from settlement payouts (30% unless configured)
+    let tds_rate = env::var("TDS_RATE")
+        .ok()
+        .and_then(|rate| rate.parse::<Decimal>().ok())
+        .unwrap_or_else(|| Decimal::new(30, 2));
+    let tax_service = Arc::new(TaxService::new(
+        Arc::clone(&repository),
+        Arc::clone(&balance_service),
+        tds_rate
     ));
     let settlement_service = Arc::new(SettlementService::new(
         payout_sender, 
+        Arc::clone(&repository),
+        Arc::clone(&balance_service),
+        Arc::clone(&order_service),
+        Arc::clone(&position_service),
+        Arc::clone(&tax_service)
+    ));
+    
+    let event_service = Arc::new(EventService::new(
+        Arc::clone(&repository),
+        Arc::clone(&order_service),
+        Arc::clone(&settlement_service),
+        event_update_sender
+    ));
+    
+    // Route market events to subscribers of the events that own them
+    for event in event_service.get_all_events().await? {
+        ws_server.register_event_markets(&event.event_id, &event.market_ids).await;
+    }
+    
+    // Mark open positions at the configured price source
+    let mark_source = env::var("MARK_PRICE_SOURCE")
+        .ok()
+        .and_then(|source| source.parse::<MarkPriceSource>().ok())
+        .unwrap_or_default();
+    let portfolio_service = Arc::new(PortfolioService::new(
+        Arc::clone(&repository),
+        Arc::clone(&order_service),
+        Arc::clone(&balance_service),
+        Arc::clone(&position_service),
+        mark_source
+    ));
+    
+    let ledger_service = Arc::new(LedgerService::new(Arc::clone(&repository)));
+    let idempotency_service = Arc::new(IdempotencyService::new(Arc::clone(&repository)));
+    let auth_service = Arc::new(AuthService::new(Arc::clone(&repository), Arc::clone(&rate_limiter)));
+    
+    // Forget the nonces of requests too old to be accepted again
+    auth_service.start_schedule(Duration::from_secs(MAX_TIMESTAMP_SKEW_SECS as u64));
+    
+    // Make the configured user the first admin, so there is someone to issue keys and grant roles;
+    // their key's secret goes to a file only the server's user can read, never to the output
+    if let Ok(admin_user_id) = env::var("ADMIN_USER_ID") {
+        let key_file = env::var("ADMIN_KEY_FILE").unwrap_or_else(|_| "admin_api_key.json".to_string());
+        match uuid::Uuid::parse_str(&admin_user_id) {
+            Ok(admin_user_id) => {
+                // Printed rather than logged, so the operator sees where the key is whatever the log level
+                if let Some(api_key) = auth_service.bootstrap_admin(admin_user_id, Path::new(&key_file)).await? {
+                    println!(
+                        "Made user {} the first admin with API key {}; its secret is in {}, move it somewhere safe",
+                        admin_user_id, api_key.key_id, key_file
+                    );
+                }
+            }
+            Err(e) => warn!("ADMIN_USER_ID is not a valid user ID: {}", e),
+        }
+    }
+    
+    // Hold withdrawals for review and pay approved ones out, up to a daily limit per user
+    let withdrawal_daily_limit = env::var("WITHDRAWAL_DAILY_LIMIT")
+        .ok()
+        .and_then(|limit| limit.parse::<Decimal>().ok())
+        .unwrap_or_else(|| Decimal::from(10_000));
+    let withdrawal_service = Arc::new(WithdrawalService::new(
+        Arc::clone(&repository),
+        Arc::clone(&balance_service),
+        Arc::new(LocalPayoutProvider::new()),
+        withdrawal_daily_limit
+    ));
+    
+    // Take deposits through the payment provider, crediting them when its signed webhook confirms them;
+    // only a development setup may fall back to the well-known development secret
+    let dev_mode = env::var("DEV_MODE").map(|value| value == "true" || value == "1").unwrap_or(false);
+    let webhook_secret = match env::var("DEPOSIT_WEBHOOK_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ if dev_mode => {
+            warn!("DEPOSIT_WEBHOOK_SECRET is not set, using the development secret");
+            "dev-webhook-secret".to_string()
+        }
+        _ => return Err("DEPOSIT_WEBHOOK_SECRET must be set (set DEV_MODE=true to use the development secret)".into()),
+    };
+    let deposit_service = Arc::new(DepositService::new(
+        Arc::clone(&repository),
+        Arc::clone(&balance_service),
+        Arc::new(MockPaymentProvider::new(webhook_secret))
+    ));
+    
+    // Remove expired promotional credit on a schedule (every minute unless configured; 0 turns it off)
+    let promo_service = Arc::new(PromoService::new(
         Arc::clone(&repository),
         Arc::clone(&balance_service)
     ));
+    let promo_expiry_interval = env::var("PROMO_EXPIRY_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(60);
+    if promo_expiry_interval > 0 {
+        promo_service.start_schedule(Duration::from_secs(promo_expiry_interval));
+    }
+    
+    // Reconcile balances, orders and positions on a schedule (every hour unless configured; 0 turns it off)
+    let reconciliation_service = Arc::new(ReconciliationService::new(Arc::clone(&repository)));
+    let reconciliation_interval = env::var("RECONCILIATION_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(3600);
+    if reconciliation_interval > 0 {
+        reconciliation_service.start_schedule(Duration::from_secs(reconciliation_interval));
+    }
     
     // Create bot service with default configuration
     let bot_config = BotConfig::default();
@@ -64,21 +222,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Arc::clone(&order_service),
         Arc::clone(&bot_service),
         Arc::clone(&settlement_service),
+        Arc::clone(&amm_service),
+        Arc::clone(&event_service),
+        Arc::clone(&position_service),
+        Arc::clone(&portfolio_service),
+        Arc::clone(&ledger_service),
+        Arc::clone(&idempotency_service),
+        Arc::clone(&reconciliation_service),
+        Arc::clone(&withdrawal_service),
+        Arc::clone(&deposit_service),
+        Arc::clone(&promo_service),
+        Arc::clone(&tax_service),
+        Arc::clone(&risk_service),
+        Arc::clone(&auth_service),
+        Arc::clone(&rate_limiter),
     );
     
     // WebSocket handler
     let ws_server_clone = Arc::clone(&ws_server);
-    let ws_route = warp::path("ws")
-        .and(warp::ws())
-        .map(move |ws: warp::ws::Ws| {
+    // Connections signed with an API key can subscribe to their user's events
+    let ws_upgrade = warp::ws()
+        .and(warp::addr::remote())
+        .and(optionally_authenticated(Arc::clone(&auth_service)))
+        .map(move |ws: warp::ws::Ws, remote: Option<std::net::SocketAddr>, user: Option<AuthenticatedUser>| {
             let ws_server = Arc::clone(&ws_server_clone);
             ws.on_upgrade(move |websocket| async move {
-                ws_server.handle_connection(websocket).await;
+                ws_server.handle_connection(websocket, remote, user.map(|user| user.user_id)).await;
             })
-        });
+        })
+        .recover(handle_rejection);
+    let ws_route = warp::path("ws").and(rate_limited(ws_upgrade, Arc::clone(&rate_limiter)));
     
-    // Combine all routes
-    let routes = api_routes.or(ws_route);
+    // Combine all routes, matching the WebSocket path first so no request is counted against two rate limits
+    let routes = ws_route.or(api_routes);
     
     // Get port from environment or use default
     let port = env::var("PORT")
@@ -0,0 +1,323 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Number of decimal places used for AMM cash amounts
+const AMM_PRECISION: u32 = 8;
+
+/// A Logarithmic Market Scoring Rule (LMSR) automated market maker for one market
+///
+/// The market maker always quotes every outcome. Its cost function is
+/// `C(q) = b * ln(sum(exp(q_i / b)))`, where `q_i` is the number of shares of
+/// outcome `i` it has sold. Buying `k` shares of an outcome costs `C(q + k) - C(q)`.
+/// The maximum amount the market maker can lose is `b * ln(N)`, which is funded
+/// up front as a subsidy from the house account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LmsrMarketMaker {
+    /// ID of the market this market maker quotes
+    pub market_id: String,
+
+    /// House account that funds the subsidy and acts as counterparty
+    pub house_account_id: Uuid,
+
+    /// Liquidity parameter `b` (higher means deeper liquidity and more subsidy)
+    pub liquidity: Decimal,
+
+    /// Subsidy reserved from the house account (the worst-case loss bound)
+    pub subsidy: Decimal,
+
+    /// Net shares sold to traders per outcome, indexed by outcome
+    pub quantities: Vec<Decimal>,
+
+    /// Net cash the market maker has taken in from trades
+    pub collected: Decimal,
+
+    /// When the market maker was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the market maker was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A price quote from the market maker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmmQuote {
+    /// Number of shares quoted
+    pub quantity: u32,
+
+    /// Total cash paid (for a buy) or received (for a sell)
+    pub amount: Decimal,
+
+    /// Average price per share
+    pub average_price: Decimal,
+
+    /// Marginal price of the outcome after the trade
+    pub price_after: Decimal,
+}
+
+impl LmsrMarketMaker {
+    /// Creates a new market maker with no inventory
+    pub fn new(market_id: String, house_account_id: Uuid, liquidity: Decimal, num_outcomes: usize) -> Self {
+        let now = Utc::now();
+        let mut market_maker = Self {
+            market_id,
+            house_account_id,
+            liquidity,
+            subsidy: Decimal::ZERO,
+            quantities: vec![Decimal::ZERO; num_outcomes],
+            collected: Decimal::ZERO,
+            created_at: now,
+            updated_at: now,
+        };
+        market_maker.subsidy = market_maker.max_loss();
+        market_maker
+    }
+
+    /// Gets the liquidity parameter as a float
+    fn b(&self) -> f64 {
+        self.liquidity.to_f64().unwrap_or(0.0)
+    }
+
+    /// Gets the outstanding quantities as floats
+    fn quantities_f64(&self) -> Vec<f64> {
+        self.quantities.iter().map(|q| q.to_f64().unwrap_or(0.0)).collect()
+    }
+
+    /// Computes `ln(sum(exp(x_i)))` without overflowing
+    fn log_sum_exp(values: impl Iterator<Item = f64> + Clone) -> f64 {
+        let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+        if max == f64::NEG_INFINITY {
+            return max;
+        }
+        max + values.map(|v| (v - max).exp()).sum::<f64>().ln()
+    }
+
+    /// Evaluates the LMSR cost function for a set of quantities
+    fn cost(&self, quantities: &[f64]) -> f64 {
+        let b = self.b();
+        b * Self::log_sum_exp(quantities.iter().map(|q| q / b))
+    }
+
+    /// Converts a float amount to a decimal, rounding in the given direction
+    fn to_decimal(value: f64, strategy: RoundingStrategy) -> Decimal {
+        Decimal::from_f64(value)
+            .unwrap_or(Decimal::ZERO)
+            .round_dp_with_strategy(AMM_PRECISION, strategy)
+    }
+
+    /// Gets the maximum amount the market maker can lose, `b * ln(N)`
+    pub fn max_loss(&self) -> Decimal {
+        let n = self.quantities.len() as f64;
+        Self::to_decimal(self.b() * n.ln(), RoundingStrategy::AwayFromZero)
+    }
+
+    /// Gets the current marginal price of an outcome
+    pub fn price(&self, outcome: usize) -> Decimal {
+        let b = self.b();
+        let quantities = self.quantities_f64();
+        let log_total = Self::log_sum_exp(quantities.iter().map(|q| q / b));
+        Self::to_decimal((quantities[outcome] / b - log_total).exp(), RoundingStrategy::MidpointNearestEven)
+    }
+
+    /// Gets the current marginal prices of all outcomes
+    pub fn prices(&self) -> Vec<Decimal> {
+        (0..self.quantities.len()).map(|outcome| self.price(outcome)).collect()
+    }
+
+    /// Gets the market maker's loss if the given outcome wins at current inventory
+    pub fn loss_if_outcome_wins(&self, outcome: usize) -> Decimal {
+        self.quantities[outcome] - self.collected
+    }
+
+    /// Gets the market maker's worst-case loss across all outcomes at current inventory
+    pub fn worst_case_loss(&self) -> Decimal {
+        (0..self.quantities.len())
+            .map(|outcome| self.loss_if_outcome_wins(outcome))
+            .max()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Quotes the cost of buying shares of an outcome from the market maker
+    pub fn quote_buy(&self, outcome: usize, quantity: u32) -> AmmQuote {
+        let mut quantities = self.quantities_f64();
+        let before = self.cost(&quantities);
+        quantities[outcome] += quantity as f64;
+        let amount = Self::to_decimal(self.cost(&quantities) - before, RoundingStrategy::AwayFromZero);
+        self.build_quote(outcome, quantity, amount, &quantities)
+    }
+
+    /// Quotes the proceeds of selling shares of an outcome to the market maker
+    pub fn quote_sell(&self, outcome: usize, quantity: u32) -> AmmQuote {
+        let mut quantities = self.quantities_f64();
+        let before = self.cost(&quantities);
+        quantities[outcome] -= quantity as f64;
+        let amount = Self::to_decimal(before - self.cost(&quantities), RoundingStrategy::ToZero);
+        self.build_quote(outcome, quantity, amount, &quantities)
+    }
+
+    /// Builds a quote from the post-trade quantities
+    fn build_quote(&self, outcome: usize, quantity: u32, amount: Decimal, quantities_after: &[f64]) -> AmmQuote {
+        let b = self.b();
+        let log_total = Self::log_sum_exp(quantities_after.iter().map(|q| q / b));
+        let price_after = (quantities_after[outcome] / b - log_total).exp();
+        let average_price = if quantity > 0 {
+            (amount / Decimal::from(quantity)).round_dp(AMM_PRECISION)
+        } else {
+            Decimal::ZERO
+        };
+
+        AmmQuote {
+            quantity,
+            amount,
+            average_price,
+            price_after: Self::to_decimal(price_after, RoundingStrategy::MidpointNearestEven),
+        }
+    }
+
+    /// Computes the quantity at which the marginal price of an outcome reaches `limit`
+    ///
+    /// Returns the signed change in the outcome's quantity; positive means the
+    /// market maker would sell shares to reach the limit.
+    fn quantity_to_price(&self, outcome: usize, limit: Decimal) -> f64 {
+        let b = self.b();
+        let quantities = self.quantities_f64();
+        let limit = limit.to_f64().unwrap_or(0.0);
+        let log_others = Self::log_sum_exp(
+            quantities.iter().enumerate().filter(|(i, _)| *i != outcome).map(|(_, q)| q / b),
+        );
+        b * (limit.ln() - (1.0 - limit).ln() + log_others) - quantities[outcome]
+    }
+
+    /// Gets the largest number of shares (up to `quantity`) a buyer can take
+    /// without the marginal price rising above `limit`
+    pub fn max_buy_quantity(&self, outcome: usize, limit: Decimal, quantity: u32) -> u32 {
+        if limit <= Decimal::ZERO {
+            return 0;
+        }
+        if limit >= Decimal::ONE {
+            return quantity;
+        }
+        let shares = self.quantity_to_price(outcome, limit).floor();
+        shares.clamp(0.0, quantity as f64) as u32
+    }
+
+    /// Gets the largest number of shares (up to `quantity`) a seller can place
+    /// without the marginal price falling below `limit`
+    pub fn max_sell_quantity(&self, outcome: usize, limit: Decimal, quantity: u32) -> u32 {
+        if limit <= Decimal::ZERO {
+            return quantity;
+        }
+        if limit >= Decimal::ONE {
+            return 0;
+        }
+        let shares = (-self.quantity_to_price(outcome, limit)).floor();
+        shares.clamp(0.0, quantity as f64) as u32
+    }
+
+    /// Records a sale of shares to a trader
+    pub fn apply_buy(&mut self, outcome: usize, quantity: u32, amount: Decimal) {
+        self.quantities[outcome] += Decimal::from(quantity);
+        self.collected += amount;
+        self.updated_at = Utc::now();
+    }
+
+    /// Records a purchase of shares from a trader
+    pub fn apply_sell(&mut self, outcome: usize, quantity: u32, amount: Decimal) {
+        self.quantities[outcome] -= Decimal::from(quantity);
+        self.collected -= amount;
+        self.updated_at = Utc::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smallest step a quoted amount or price can be off by from rounding
+    fn tolerance() -> Decimal {
+        Decimal::new(1, AMM_PRECISION - 1)
+    }
+
+    fn market_maker(num_outcomes: usize) -> LmsrMarketMaker {
+        LmsrMarketMaker::new("market".to_string(), Uuid::nil(), Decimal::from(100), num_outcomes)
+    }
+
+    fn buy(market_maker: &mut LmsrMarketMaker, outcome: usize, quantity: u32) -> AmmQuote {
+        let quote = market_maker.quote_buy(outcome, quantity);
+        market_maker.apply_buy(outcome, quantity, quote.amount);
+        quote
+    }
+
+    #[test]
+    fn new_market_maker_prices_outcomes_equally() {
+        let market_maker = market_maker(3);
+        let prices = market_maker.prices();
+
+        for price in &prices {
+            assert!((*price - prices[0]).abs() <= tolerance());
+        }
+        assert!((prices.iter().sum::<Decimal>() - Decimal::ONE).abs() <= tolerance());
+        assert_eq!(market_maker.quote_buy(0, 10).amount, market_maker.quote_buy(2, 10).amount);
+    }
+
+    #[test]
+    fn subsidy_covers_the_worst_case_loss() {
+        let mut market_maker = market_maker(2);
+        buy(&mut market_maker, 0, 2_000);
+
+        assert!(market_maker.loss_if_outcome_wins(0) <= market_maker.subsidy);
+        assert!(market_maker.worst_case_loss() <= market_maker.max_loss());
+    }
+
+    #[test]
+    fn buying_raises_the_outcome_price() {
+        let mut market_maker = market_maker(2);
+        let before = market_maker.price(0);
+        let quote = buy(&mut market_maker, 0, 50);
+
+        assert!(market_maker.price(0) > before);
+        assert!(market_maker.price(1) < Decimal::ONE - before);
+        assert_eq!(quote.price_after, market_maker.price(0));
+        assert!(quote.average_price > before && quote.average_price < quote.price_after);
+        assert!((market_maker.prices().iter().sum::<Decimal>() - Decimal::ONE).abs() <= tolerance());
+    }
+
+    #[test]
+    fn selling_back_returns_what_was_paid() {
+        let mut market_maker = market_maker(2);
+        let bought = buy(&mut market_maker, 1, 40);
+        let sold = market_maker.quote_sell(1, 40);
+
+        assert!(sold.amount <= bought.amount);
+        assert!(bought.amount - sold.amount <= tolerance());
+        assert!((sold.price_after - Decimal::new(5, 1)).abs() <= tolerance());
+    }
+
+    #[test]
+    fn max_buy_quantity_stops_at_the_limit_price() {
+        let market_maker = market_maker(2);
+        let limit = Decimal::new(7, 1);
+        let quantity = market_maker.max_buy_quantity(0, limit, 10_000);
+
+        assert!(quantity > 0 && quantity < 10_000);
+        assert!(market_maker.quote_buy(0, quantity).price_after <= limit);
+        assert!(market_maker.quote_buy(0, quantity + 1).price_after > limit);
+        assert_eq!(market_maker.max_buy_quantity(0, Decimal::new(4, 1), 10_000), 0);
+        assert_eq!(market_maker.max_buy_quantity(0, limit, 5), 5);
+    }
+
+    #[test]
+    fn max_sell_quantity_stops_at_the_limit_price() {
+        let mut market_maker = market_maker(2);
+        buy(&mut market_maker, 0, 100);
+        let limit = Decimal::new(4, 1);
+        let quantity = market_maker.max_sell_quantity(0, limit, 10_000);
+
+        assert!(quantity > 0 && quantity < 10_000);
+        assert!(market_maker.quote_sell(0, quantity).price_after >= limit);
+        assert!(market_maker.quote_sell(0, quantity + 1).price_after < limit);
+        assert_eq!(market_maker.max_sell_quantity(0, Decimal::new(9, 1), 10_000), 0);
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// Key a user signs their API requests with
+///
+/// Requests carry the key ID and an HMAC-SHA256 signature made with the secret,
+/// which is only shown to the user when the key is issued.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Public ID of the key, sent with every request
+    pub key_id: String,
+    
+    /// User the key acts for
+    pub user_id: Uuid,
+    
+    /// Secret requests are signed with
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    
+    /// What the key is for (e.g. "trading bot")
+    pub label: Option<String>,
+    
+    /// When the key was issued
+    pub created_at: DateTime<Utc>,
+    
+    /// When the key was revoked, if it has been
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Creates a new key with a random ID and secret
+    pub fn new(user_id: Uuid, label: Option<String>) -> Self {
+        Self {
+            key_id: format!("ak_{}", random_hex(12)),
+            user_id,
+            secret: random_hex(32),
+            label,
+            created_at: Utc::now(),
+            revoked_at: None,
+        }
+    }
+    
+    /// Checks if the key can still be used
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+    
+    /// Signs a request: the hex HMAC-SHA256 of its canonical form (see `canonical_request`)
+    pub fn sign(&self, timestamp: &str, nonce: &str, method: &str, path: &str, body: &[u8]) -> String {
+        hex::encode(self.mac(timestamp, nonce, method, path, body).finalize().into_bytes())
+    }
+    
+    /// Checks a request's signature
+    pub fn verify(&self, timestamp: &str, nonce: &str, method: &str, path: &str, body: &[u8], signature: &str) -> bool {
+        match hex::decode(signature.trim()) {
+            Ok(signature) => self.mac(timestamp, nonce, method, path, body).verify_slice(&signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+    
+    fn mac(&self, timestamp: &str, nonce: &str, method: &str, path: &str, body: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC takes a key of any length");
+        mac.update(&canonical_request(timestamp, nonce, method, path, body));
+        mac
+    }
+}
+
+/// A newly issued key, with the secret the user signs requests with
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    
+    /// Secret to sign requests with; it cannot be retrieved again
+    pub secret: String,
+}
+
+impl IssuedApiKey {
+    /// Wraps a new key to hand its secret to the user
+    pub fn new(api_key: ApiKey) -> Self {
+        let secret = api_key.secret.clone();
+        Self { api_key, secret }
+    }
+}
+
+/// What a request's signature is made over: its timestamp, nonce, method and path,
+/// each followed by a newline, then its body
+///
+/// None of the fields before the body may contain a newline, so each way of
+/// splitting a request into fields signs differently.
+pub fn canonical_request(timestamp: &str, nonce: &str, method: &str, path: &str, body: &[u8]) -> Vec<u8> {
+    let mut canonical = format!("{}\n{}\n{}\n{}\n", timestamp, nonce, method, path).into_bytes();
+    canonical.extend_from_slice(body);
+    canonical
+}
+
+/// Hex string of `bytes` random bytes
+fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    hex::encode(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const BODY: &[u8] = br#"{"quantity":10}"#;
+    
+    fn key() -> ApiKey {
+        ApiKey { secret: "secret".to_string(), ..ApiKey::new(Uuid::nil(), None) }
+    }
+    
+    #[test]
+    fn canonical_requests_put_each_field_on_its_own_line_before_the_body() {
+        assert_eq!(
+            canonical_request("1700000000", "nonce-1", "POST", "/api/orders?dry_run=true", BODY),
+            b"1700000000\nnonce-1\nPOST\n/api/orders?dry_run=true\n{\"quantity\":10}".to_vec()
+        );
+    }
+    
+    #[test]
+    fn signatures_are_the_hex_hmac_sha256_of_the_canonical_request() {
+        assert_eq!(
+            key().sign("1700000000", "nonce-1", "POST", "/api/orders?dry_run=true", BODY),
+            "c2ccfc55fddbb62f8b61b336bf191ce81d1b98056510f853f614fa4a24d2b185"
+        );
+    }
+    
+    #[test]
+    fn signatures_verify_only_for_the_request_they_were_made_over() {
+        let key = key();
+        let signature = key.sign("1700000000", "nonce-1", "POST", "/api/orders", BODY);
+        
+        assert!(key.verify("1700000000", "nonce-1", "POST", "/api/orders", BODY, &signature));
+        assert!(key.verify("1700000000", "nonce-1", "POST", "/api/orders", BODY, &format!(" {}\n", signature)));
+        assert!(!key.verify("1700000001", "nonce-1", "POST", "/api/orders", BODY, &signature));
+        assert!(!key.verify("1700000000", "nonce-2", "POST", "/api/orders", BODY, &signature));
+        assert!(!key.verify("1700000000", "nonce-1", "DELETE", "/api/orders", BODY, &signature));
+        assert!(!key.verify("1700000000", "nonce-1", "POST", "/api/orders?all=true", BODY, &signature));
+        assert!(!key.verify("1700000000", "nonce-1", "POST", "/api/orders", b"{}", &signature));
+    }
+    
+    #[test]
+    fn signatures_made_with_another_secret_or_not_in_hex_do_not_verify() {
+        let signature = ApiKey::new(Uuid::nil(), None).sign("1700000000", "nonce-1", "POST", "/api/orders", BODY);
+        
+        assert!(!key().verify("1700000000", "nonce-1", "POST", "/api/orders", BODY, &signature));
+        assert!(!key().verify("1700000000", "nonce-1", "POST", "/api/orders", BODY, "not-hex"));
+        assert!(!key().verify("1700000000", "nonce-1", "POST", "/api/orders", BODY, ""));
+    }
+}
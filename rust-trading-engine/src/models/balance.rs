@@ -9,14 +9,156 @@ pub struct UserBalance {
     /// User ID
     pub user_id: Uuid,
     
-    /// Available balance for trading
+    /// Available balance for trading: the cash, winnings and promotional credit buckets together
     pub available_balance: Decimal,
     
     /// Balance that is reserved for open orders
     pub reserved_balance: Decimal,
     
+    /// Part of the available balance won from trading and settlement, which can be withdrawn
+    #[serde(default)]
+    pub winnings_balance: Decimal,
+    
+    /// What is left of each grant of promotional credit in the available balance,
+    /// soonest-expiring first; it can be traded but not withdrawn
+    #[serde(default)]
+    pub promo_credits: Vec<PromoHolding>,
+    
+    /// Part of the reserved balance paid for with promotional credit
+    #[serde(default)]
+    pub reserved_promo: Decimal,
+    
+    /// Which market, and which order in it, each part of the reserved promotional credit is held for
+    #[serde(default)]
+    pub promo_reserves: Vec<PromoReserve>,
+    
+    /// Promotional credit paid for shares in each market, which goes back to the
+    /// promotional credit bucket when the shares are sold or paid out
+    #[serde(default)]
+    pub promo_positions: Vec<PromoPosition>,
+    
     /// When the balance was last updated
     pub updated_at: DateTime<Utc>,
+    
+    /// Number of times the balance has been saved; a save only succeeds if no other
+    /// save has happened since this version was read
+    pub version: i64,
+}
+
+/// What is left of one grant of promotional credit in a user's available balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoHolding {
+    /// Grant the credit came from
+    pub credit_id: Uuid,
+    
+    /// When whatever is left of the grant is removed
+    pub expires_at: DateTime<Utc>,
+    
+    /// Amount granted
+    pub amount: Decimal,
+    
+    /// Amount of the grant not yet spent
+    pub remaining: Decimal,
+}
+
+/// Promotional credit a market holds as a user's collateral
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromoReserve {
+    /// Market holding the credit
+    pub market_id: String,
+    
+    /// Order or group conversion the credit was reserved for, if any
+    pub order_id: Option<Uuid>,
+    
+    /// Amount reserved
+    pub amount: Decimal,
+}
+
+/// Promotional credit paid for the shares a user holds in a market
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromoPosition {
+    /// Market the shares are in
+    pub market_id: String,
+    
+    /// Amount paid and not yet returned by sales or payouts
+    pub amount: Decimal,
+}
+
+/// Error returned when a user balance was changed by someone else since it was read
+#[derive(Debug, thiserror::Error)]
+#[error("Balance of user {user_id} changed since version {version} was read")]
+pub struct BalanceConflict {
+    /// User whose balance changed
+    pub user_id: Uuid,
+    
+    /// Version the failed save expected to replace
+    pub version: i64,
+}
+
+/// Part of a user's available balance, which decides what the funds can be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BalanceBucket {
+    /// Deposited funds, which can be traded and withdrawn
+    Cash,
+    
+    /// Trade proceeds and settlement payouts, which can be traded and withdrawn
+    Winnings,
+    
+    /// Promotional credit such as joining bonuses, which can be traded but not withdrawn
+    Promo,
+}
+
+impl BalanceBucket {
+    /// Every bucket
+    pub const ALL: [BalanceBucket; 3] = [BalanceBucket::Cash, BalanceBucket::Winnings, BalanceBucket::Promo];
+}
+
+/// Order in which buckets are spent on orders: promotional credit first, winnings last
+pub const SPEND_PRIORITY: [BalanceBucket; 3] = [BalanceBucket::Promo, BalanceBucket::Cash, BalanceBucket::Winnings];
+
+/// Order in which buckets are spent on withdrawals and complete sets, which promotional credit cannot pay for
+pub const WITHDRAWAL_PRIORITY: [BalanceBucket; 2] = [BalanceBucket::Cash, BalanceBucket::Winnings];
+
+/// How an amount taken from or added to an available balance is split across its buckets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketAmounts {
+    /// Amount in the cash bucket
+    pub cash: Decimal,
+    
+    /// Amount in the winnings bucket
+    pub winnings: Decimal,
+    
+    /// Amount in the promotional credit bucket
+    pub promo: Decimal,
+}
+
+impl BucketAmounts {
+    /// Gets the amount in a bucket
+    pub fn get(&self, bucket: BalanceBucket) -> Decimal {
+        match bucket {
+            BalanceBucket::Cash => self.cash,
+            BalanceBucket::Winnings => self.winnings,
+            BalanceBucket::Promo => self.promo,
+        }
+    }
+    
+    /// Gets a mutable reference to the amount in a bucket
+    fn get_mut(&mut self, bucket: BalanceBucket) -> &mut Decimal {
+        match bucket {
+            BalanceBucket::Cash => &mut self.cash,
+            BalanceBucket::Winnings => &mut self.winnings,
+            BalanceBucket::Promo => &mut self.promo,
+        }
+    }
+    
+    /// Gets the non-zero amounts with their buckets
+    pub fn non_zero(&self) -> Vec<(BalanceBucket, Decimal)> {
+        BalanceBucket::ALL
+            .into_iter()
+            .map(|bucket| (bucket, self.get(bucket)))
+            .filter(|(_, amount)| !amount.is_zero())
+            .collect()
+    }
 }
 
 /// Transaction type for balance ledger
@@ -25,7 +167,7 @@ pub enum TransactionType {
     /// Deposit funds into account
     Deposit,
     
-    /// Withdraw funds from account
+    /// Hold funds for a withdrawal request until it is reviewed
     Withdraw,
     
     /// Reserve funds for an order
@@ -36,6 +178,39 @@ pub enum TransactionType {
     
     /// Settlement payout for market resolution
     SettlementPayout,
+    
+    /// Payment for shares bought in a trade
+    TradeDebit,
+    
+    /// Proceeds from shares sold in a trade
+    TradeCredit,
+    
+    /// Subsidy reserved from the house account to fund an automated market maker
+    AmmSubsidy,
+    
+    /// Amount owed on a short position when a market settles
+    SettlementDebit,
+    
+    /// Units locked in a market to mint complete sets of its outcome shares
+    CompleteSetMint,
+    
+    /// Units returned from a market for redeeming complete sets
+    CompleteSetRedeem,
+    
+    /// Funds held for an approved withdrawal paid out of the platform
+    WithdrawalPayout,
+    
+    /// Funds held for a rejected withdrawal returned to the available balance
+    WithdrawalReturn,
+    
+    /// Promotional credit granted to a user
+    PromoCredit,
+    
+    /// Promotional credit removed when it expired
+    PromoExpiry,
+    
+    /// Tax deducted at source from a settlement payout
+    TaxWithholding,
 }
 
 impl From<i32> for TransactionType {
@@ -46,6 +221,17 @@ impl From<i32> for TransactionType {
             2 => TransactionType::OrderReserve,
             3 => TransactionType::OrderRelease,
             4 => TransactionType::SettlementPayout,
+            5 => TransactionType::TradeDebit,
+            6 => TransactionType::TradeCredit,
+            7 => TransactionType::AmmSubsidy,
+            8 => TransactionType::SettlementDebit,
+            9 => TransactionType::CompleteSetMint,
+            10 => TransactionType::CompleteSetRedeem,
+            11 => TransactionType::WithdrawalPayout,
+            12 => TransactionType::WithdrawalReturn,
+            13 => TransactionType::PromoCredit,
+            14 => TransactionType::PromoExpiry,
+            15 => TransactionType::TaxWithholding,
             _ => panic!("Invalid TransactionType value: {}", value),
         }
     }
@@ -59,6 +245,17 @@ impl From<TransactionType> for i32 {
             TransactionType::OrderReserve => 2,
             TransactionType::OrderRelease => 3,
             TransactionType::SettlementPayout => 4,
+            TransactionType::TradeDebit => 5,
+            TransactionType::TradeCredit => 6,
+            TransactionType::AmmSubsidy => 7,
+            TransactionType::SettlementDebit => 8,
+            TransactionType::CompleteSetMint => 9,
+            TransactionType::CompleteSetRedeem => 10,
+            TransactionType::WithdrawalPayout => 11,
+            TransactionType::WithdrawalReturn => 12,
+            TransactionType::PromoCredit => 13,
+            TransactionType::PromoExpiry => 14,
+            TransactionType::TaxWithholding => 15,
         }
     }
 }
@@ -75,6 +272,10 @@ pub struct BalanceTransaction {
     /// Amount of the transaction
     pub amount: Decimal,
     
+    /// How much the transaction changed the user's available balance
+    #[serde(default)]
+    pub available_change: Decimal,
+    
     /// Type of transaction
     pub transaction_type: TransactionType,
     
@@ -95,7 +296,13 @@ impl UserBalance {
             user_id,
             available_balance: initial_balance,
             reserved_balance: Decimal::ZERO,
+            winnings_balance: Decimal::ZERO,
+            promo_credits: Vec::new(),
+            reserved_promo: Decimal::ZERO,
+            promo_reserves: Vec::new(),
+            promo_positions: Vec::new(),
             updated_at: Utc::now(),
+            version: 0,
         }
     }
     
@@ -104,54 +311,289 @@ impl UserBalance {
         self.available_balance + self.reserved_balance
     }
     
+    /// Gets the part of the available balance that was deposited
+    pub fn cash_balance(&self) -> Decimal {
+        self.available_balance - self.winnings_balance - self.promo_balance()
+    }
+    
+    /// Gets the part of the available balance that is promotional credit
+    pub fn promo_balance(&self) -> Decimal {
+        self.promo_credits.iter().map(|holding| holding.remaining).sum()
+    }
+    
+    /// Gets the part of the available balance that can be withdrawn
+    pub fn withdrawable_balance(&self) -> Decimal {
+        self.available_balance - self.promo_balance()
+    }
+    
+    /// Gets the funds in each bucket of the available balance
+    pub fn buckets(&self) -> BucketAmounts {
+        BucketAmounts {
+            cash: self.cash_balance(),
+            winnings: self.winnings_balance,
+            promo: self.promo_balance(),
+        }
+    }
+    
     /// Checks if user has enough available funds
     pub fn has_sufficient_funds(&self, amount: Decimal) -> bool {
         self.available_balance >= amount
     }
     
-    /// Reserves funds for an order
-    pub fn reserve_funds(&mut self, amount: Decimal) -> Result<(), String> {
+    /// Reserves funds for an order, spending the buckets in `SPEND_PRIORITY` order
+    ///
+    /// Any promotional credit spent is recorded against the market and order
+    /// (`None` for collateral held outside any order), so it goes back to the
+    /// promotional credit bucket rather than out as cash when the reserve is released.
+    pub fn reserve_funds(&mut self, amount: Decimal, market_id: &str, order_id: Option<Uuid>) -> Result<BucketAmounts, String> {
         if !self.has_sufficient_funds(amount) {
             return Err(format!("Insufficient funds: available {}, required {}", self.available_balance, amount));
         }
         
-        self.available_balance -= amount;
+        let taken = self.take_from_buckets(amount, &SPEND_PRIORITY);
         self.reserved_balance += amount;
+        self.reserved_promo += taken.promo;
+        if taken.promo > Decimal::ZERO {
+            match self.promo_reserves.iter_mut().find(|reserve| reserve.market_id == market_id && reserve.order_id == order_id) {
+                Some(reserve) => reserve.amount += taken.promo,
+                None => self.promo_reserves.push(PromoReserve {
+                    market_id: market_id.to_string(),
+                    order_id,
+                    amount: taken.promo,
+                }),
+            }
+        }
         self.updated_at = Utc::now();
         
-        Ok(())
+        Ok(taken)
     }
     
-    /// Releases reserved funds back to available balance
-    pub fn release_funds(&mut self, amount: Decimal) -> Result<(), String> {
+    /// Releases funds reserved in a market for an order back to available balance
+    ///
+    /// The order's own promotional credit is released first, back to the promotional
+    /// credit bucket; the rest is released as cash. With no order, everything the
+    /// market holds for the user counts as the reserve.
+    pub fn release_funds(&mut self, amount: Decimal, market_id: &str, order_id: Option<Uuid>) -> Result<BucketAmounts, String> {
         if self.reserved_balance < amount {
             return Err(format!("Cannot release more than reserved: reserved {}, release amount {}", self.reserved_balance, amount));
         }
         
+        let promo = self.take_reserved_promo(amount, market_id, order_id);
         self.reserved_balance -= amount;
         self.available_balance += amount;
+        self.return_promo(promo);
         self.updated_at = Utc::now();
         
-        Ok(())
+        Ok(BucketAmounts { cash: amount - promo, winnings: Decimal::ZERO, promo })
+    }
+    
+    /// Consumes funds reserved in a market for an order (e.g., to pay for a filled order),
+    /// returning how much of them was promotional credit
+    ///
+    /// The order's own promotional credit is consumed before the rest of its reserve.
+    pub fn consume_reserved_funds(&mut self, amount: Decimal, market_id: &str, order_id: Option<Uuid>) -> Result<Decimal, String> {
+        if self.reserved_balance < amount {
+            return Err(format!("Cannot consume more than reserved: reserved {}, consume amount {}", self.reserved_balance, amount));
+        }
+        
+        let promo = self.take_reserved_promo(amount, market_id, order_id);
+        self.reserved_balance -= amount;
+        self.updated_at = Utc::now();
+        
+        Ok(promo)
+    }
+    
+    /// Takes the promotional credit out of an amount leaving a reserve
+    ///
+    /// Credit recorded against the reserve is taken first, then any reserved before
+    /// credit was recorded against reserves at all.
+    fn take_reserved_promo(&mut self, amount: Decimal, market_id: &str, order_id: Option<Uuid>) -> Decimal {
+        let mut promo = Decimal::ZERO;
+        for reserve in self.promo_reserves.iter_mut()
+            .filter(|reserve| reserve.market_id == market_id && (order_id.is_none() || reserve.order_id == order_id))
+        {
+            let take = (amount - promo).min(reserve.amount);
+            reserve.amount -= take;
+            promo += take;
+        }
+        self.promo_reserves.retain(|reserve| !reserve.amount.is_zero());
+        
+        let recorded: Decimal = self.promo_reserves.iter().map(|reserve| reserve.amount).sum();
+        let unrecorded = (self.reserved_promo - promo - recorded).max(Decimal::ZERO);
+        promo += (amount - promo).min(unrecorded);
+        
+        self.reserved_promo -= promo;
+        promo
     }
     
-    /// Adds funds to the available balance
+    /// Records promotional credit paid for shares in a market
+    pub fn add_promo_position(&mut self, market_id: &str, amount: Decimal) {
+        if amount <= Decimal::ZERO {
+            return;
+        }
+        
+        match self.promo_positions.iter_mut().find(|position| position.market_id == market_id) {
+            Some(position) => position.amount += amount,
+            None => self.promo_positions.push(PromoPosition { market_id: market_id.to_string(), amount }),
+        }
+        self.updated_at = Utc::now();
+    }
+    
+    /// Forgets the promotional credit paid into a market that has settled
+    ///
+    /// Whatever its payouts did not return was lost with the shares.
+    pub fn settle_promo(&mut self, market_id: &str) {
+        self.promo_positions.retain(|position| position.market_id != market_id);
+        self.updated_at = Utc::now();
+    }
+    
+    /// Adds funds straight to the reserved balance (e.g., proceeds kept in a market maker's pool)
+    pub fn add_reserved_funds(&mut self, amount: Decimal) {
+        self.reserved_balance += amount;
+        self.updated_at = Utc::now();
+    }
+    
+    /// Adds funds to the available balance as cash
     pub fn add_funds(&mut self, amount: Decimal) {
         self.available_balance += amount;
         self.updated_at = Utc::now();
     }
     
-    /// Withdraws funds from available balance
-    pub fn withdraw_funds(&mut self, amount: Decimal) -> Result<(), String> {
-        if !self.has_sufficient_funds(amount) {
-            return Err(format!("Insufficient funds: available {}, withdraw amount {}", self.available_balance, amount));
+    /// Adds trade proceeds or a settlement payout from a market to the available balance
+    ///
+    /// Promotional credit that paid for shares in the market is returned to the
+    /// promotional credit bucket first, so selling or settling them never turns it
+    /// into cash; only the rest is winnings.
+    pub fn add_winnings(&mut self, market_id: &str, amount: Decimal) -> BucketAmounts {
+        let promo = match self.promo_positions.iter_mut().find(|position| position.market_id == market_id) {
+            Some(position) => {
+                let promo = amount.min(position.amount);
+                position.amount -= promo;
+                promo
+            }
+            None => Decimal::ZERO,
+        };
+        self.promo_positions.retain(|position| !position.amount.is_zero());
+        
+        self.available_balance += amount;
+        self.winnings_balance += amount - promo;
+        self.return_promo(promo);
+        self.updated_at = Utc::now();
+        
+        BucketAmounts { cash: Decimal::ZERO, winnings: amount - promo, promo }
+    }
+    
+    /// Takes tax withheld from winnings out of the available balance
+    pub fn withhold_from_winnings(&mut self, amount: Decimal) -> Result<(), String> {
+        if self.winnings_balance < amount {
+            return Err(format!("Cannot withhold more than the winnings: winnings {}, withhold amount {}", self.winnings_balance, amount));
         }
         
         self.available_balance -= amount;
+        self.winnings_balance -= amount;
         self.updated_at = Utc::now();
         
         Ok(())
     }
+    
+    /// Adds a grant of promotional credit to the available balance
+    pub fn add_promo(&mut self, credit_id: Uuid, amount: Decimal, expires_at: DateTime<Utc>) {
+        self.available_balance += amount;
+        self.promo_credits.push(PromoHolding { credit_id, expires_at, amount, remaining: amount });
+        self.promo_credits.sort_by_key(|holding| holding.expires_at);
+        self.updated_at = Utc::now();
+    }
+    
+    /// Removes the grants of promotional credit that have expired, returning how much was left of them
+    pub fn expire_promo(&mut self, now: DateTime<Utc>) -> Decimal {
+        let expired: Decimal = self.promo_credits.iter()
+            .filter(|holding| holding.expires_at <= now)
+            .map(|holding| holding.remaining)
+            .sum();
+        self.promo_credits.retain(|holding| holding.expires_at > now);
+        self.available_balance -= expired;
+        self.updated_at = Utc::now();
+        expired
+    }
+    
+    /// Puts promotional credit back into the grants it was spent from
+    ///
+    /// Grants that have not expired are refilled up to what was granted, soonest-expiring
+    /// first. Whatever they cannot take goes back into the last grant, and is removed
+    /// with it if it has expired.
+    fn return_promo(&mut self, amount: Decimal) {
+        let now = Utc::now();
+        let mut remaining = amount;
+        for holding in self.promo_credits.iter_mut().filter(|holding| holding.expires_at > now) {
+            let refill = remaining.min(holding.amount - holding.remaining);
+            holding.remaining += refill;
+            remaining -= refill;
+        }
+        
+        if remaining.is_zero() {
+            return;
+        }
+        match self.promo_credits.last_mut() {
+            Some(holding) => holding.remaining += remaining,
+            None => self.promo_credits.push(PromoHolding {
+                credit_id: Uuid::nil(),
+                expires_at: now,
+                amount: remaining,
+                remaining,
+            }),
+        }
+    }
+    
+    /// Takes funds from available balance, spending the buckets in `SPEND_PRIORITY` order
+    pub fn withdraw_funds(&mut self, amount: Decimal) -> Result<BucketAmounts, String> {
+        if !self.has_sufficient_funds(amount) {
+            return Err(format!("Insufficient funds: available {}, withdraw amount {}", self.available_balance, amount));
+        }
+        
+        let taken = self.take_from_buckets(amount, &SPEND_PRIORITY);
+        self.updated_at = Utc::now();
+        
+        Ok(taken)
+    }
+    
+    /// Takes funds from the withdrawable buckets only, in `WITHDRAWAL_PRIORITY` order
+    pub fn withdraw_withdrawable_funds(&mut self, amount: Decimal) -> Result<BucketAmounts, String> {
+        if self.withdrawable_balance() < amount {
+            return Err(format!(
+                "Insufficient withdrawable funds: withdrawable {}, withdraw amount {} (promotional credit cannot be withdrawn)",
+                self.withdrawable_balance(), amount
+            ));
+        }
+        
+        let taken = self.take_from_buckets(amount, &WITHDRAWAL_PRIORITY);
+        self.updated_at = Utc::now();
+        
+        Ok(taken)
+    }
+    
+    /// Takes an amount out of the available balance, emptying each bucket in turn
+    fn take_from_buckets(&mut self, amount: Decimal, priority: &[BalanceBucket]) -> BucketAmounts {
+        let available = self.buckets();
+        let mut taken = BucketAmounts::default();
+        let mut remaining = amount;
+        for &bucket in priority {
+            let take = remaining.min(available.get(bucket));
+            *taken.get_mut(bucket) = take;
+            remaining -= take;
+        }
+        
+        // Promotional credit is spent soonest-expiring grant first
+        let mut promo = taken.promo;
+        for holding in &mut self.promo_credits {
+            let spent = promo.min(holding.remaining);
+            holding.remaining -= spent;
+            promo -= spent;
+        }
+        
+        self.available_balance -= amount;
+        self.winnings_balance -= taken.winnings;
+        taken
+    }
 }
 
 impl BalanceTransaction {
@@ -167,10 +609,137 @@ impl BalanceTransaction {
             transaction_id: Uuid::new_v4(),
             user_id,
             amount,
+            available_change: Decimal::ZERO,
             transaction_type,
             reference_id,
             description,
             created_at: Utc::now(),
         }
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+    
+    /// A balance holding `cash` deposited and `promo` of promotional credit expiring tomorrow
+    fn balance(cash: &str, promo: &str) -> UserBalance {
+        let mut balance = UserBalance::new(Uuid::nil(), dec(cash));
+        balance.add_promo(Uuid::new_v4(), dec(promo), Utc::now() + Duration::days(1));
+        balance
+    }
+    
+    fn buckets(cash: &str, winnings: &str, promo: &str) -> BucketAmounts {
+        BucketAmounts { cash: dec(cash), winnings: dec(winnings), promo: dec(promo) }
+    }
+    
+    #[test]
+    fn orders_spend_promotional_credit_then_cash_then_winnings() {
+        let mut balance = balance("10", "5");
+        balance.add_winnings("other", dec("10"));
+        
+        let taken = balance.reserve_funds(dec("18"), "market", Some(Uuid::new_v4())).unwrap();
+        
+        assert_eq!(taken, buckets("10", "3", "5"));
+        assert_eq!(balance.buckets(), buckets("0", "7", "0"));
+        assert_eq!(balance.reserved_balance, dec("18"));
+        assert_eq!(balance.reserved_promo, dec("5"));
+    }
+    
+    #[test]
+    fn withdrawals_cannot_spend_promotional_credit() {
+        let mut balance = balance("10", "5");
+        
+        assert!(balance.withdraw_withdrawable_funds(dec("12")).is_err());
+        assert_eq!(balance.withdraw_withdrawable_funds(dec("10")).unwrap(), buckets("10", "0", "0"));
+        assert_eq!(balance.buckets(), buckets("0", "0", "5"));
+    }
+    
+    #[test]
+    fn cancelling_an_order_returns_its_promotional_credit_to_the_promotional_bucket() {
+        let mut balance = balance("10", "5");
+        let order_id = Uuid::new_v4();
+        balance.reserve_funds(dec("8"), "market", Some(order_id)).unwrap();
+        
+        let released = balance.release_funds(dec("8"), "market", Some(order_id)).unwrap();
+        
+        assert_eq!(released, buckets("3", "0", "5"));
+        assert_eq!(balance.buckets(), buckets("10", "0", "5"));
+        assert_eq!(balance.reserved_promo, Decimal::ZERO);
+        assert!(balance.promo_reserves.is_empty());
+    }
+    
+    #[test]
+    fn filling_a_cash_order_leaves_another_orders_promotional_credit_reserved() {
+        let mut balance = balance("10", "5");
+        let promo_order = Uuid::new_v4();
+        let cash_order = Uuid::new_v4();
+        balance.reserve_funds(dec("5"), "market", Some(promo_order)).unwrap();
+        balance.reserve_funds(dec("4"), "market", Some(cash_order)).unwrap();
+        
+        let promo = balance.consume_reserved_funds(dec("4"), "market", Some(cash_order)).unwrap();
+        
+        assert_eq!(promo, Decimal::ZERO);
+        assert_eq!(balance.reserved_promo, dec("5"));
+        assert_eq!(balance.release_funds(dec("5"), "market", Some(promo_order)).unwrap(), buckets("0", "0", "5"));
+    }
+    
+    #[test]
+    fn releasing_a_market_without_an_order_takes_any_of_its_promotional_credit() {
+        let mut balance = balance("10", "5");
+        balance.reserve_funds(dec("3"), "market", Some(Uuid::new_v4())).unwrap();
+        balance.reserve_funds(dec("4"), "other", Some(Uuid::new_v4())).unwrap();
+        
+        let released = balance.release_funds(dec("3"), "market", None).unwrap();
+        
+        assert_eq!(released, buckets("0", "0", "3"));
+        assert_eq!(balance.reserved_promo, dec("2"));
+    }
+    
+    #[test]
+    fn selling_shares_bought_with_promotional_credit_returns_it_before_paying_winnings() {
+        let mut balance = balance("0", "10");
+        let order_id = Uuid::new_v4();
+        balance.reserve_funds(dec("10"), "market", Some(order_id)).unwrap();
+        let promo = balance.consume_reserved_funds(dec("10"), "market", Some(order_id)).unwrap();
+        balance.add_promo_position("market", promo);
+        
+        let added = balance.add_winnings("market", dec("12"));
+        
+        assert_eq!(added, buckets("0", "2", "10"));
+        assert_eq!(balance.withdrawable_balance(), dec("2"));
+        assert!(balance.promo_positions.is_empty());
+    }
+    
+    #[test]
+    fn payouts_after_settlement_are_all_winnings() {
+        let mut balance = balance("0", "10");
+        balance.add_promo_position("market", dec("10"));
+        balance.settle_promo("market");
+        
+        assert_eq!(balance.add_winnings("market", dec("5")), buckets("0", "5", "0"));
+    }
+    
+    #[test]
+    fn expiry_removes_only_expired_promotional_credit() {
+        let mut balance = balance("10", "5");
+        balance.add_promo(Uuid::new_v4(), dec("3"), Utc::now() - Duration::days(1));
+        
+        assert_eq!(balance.expire_promo(Utc::now()), dec("3"));
+        assert_eq!(balance.buckets(), buckets("10", "0", "5"));
+    }
+    
+    #[test]
+    fn tax_is_withheld_only_from_winnings() {
+        let mut balance = balance("10", "0");
+        balance.add_winnings("market", dec("4"));
+        
+        assert!(balance.withhold_from_winnings(dec("5")).is_err());
+        balance.withhold_from_winnings(dec("1")).unwrap();
+        assert_eq!(balance.buckets(), buckets("10", "3", "0"));
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Where a deposit is in its payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DepositStatus {
+    /// Waiting for the payment provider to confirm the payment
+    Pending,
+    
+    /// Paid and credited to the user's balance
+    Completed,
+    
+    /// The payment failed or was abandoned; nothing was credited
+    Failed,
+}
+
+impl From<i32> for DepositStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => DepositStatus::Pending,
+            1 => DepositStatus::Completed,
+            2 => DepositStatus::Failed,
+            _ => panic!("Invalid DepositStatus value: {}", value),
+        }
+    }
+}
+
+impl From<DepositStatus> for i32 {
+    fn from(value: DepositStatus) -> Self {
+        match value {
+            DepositStatus::Pending => 0,
+            DepositStatus::Completed => 1,
+            DepositStatus::Failed => 2,
+        }
+    }
+}
+
+/// A user's intent to pay funds onto the platform through a payment provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deposit {
+    /// Unique ID for the deposit
+    pub deposit_id: Uuid,
+    
+    /// User depositing
+    pub user_id: Uuid,
+    
+    /// Amount to credit once paid
+    pub amount: Decimal,
+    
+    /// Where the deposit is in its payment
+    pub status: DepositStatus,
+    
+    /// The payment provider's reference for the payment
+    pub provider_reference: Option<String>,
+    
+    /// Where the user completes the payment with the provider
+    pub checkout_url: Option<String>,
+    
+    /// Why the payment failed, as reported by the provider
+    pub failure_reason: Option<String>,
+    
+    /// When the deposit was started
+    pub created_at: DateTime<Utc>,
+    
+    /// When the provider confirmed or failed the payment
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Deposit {
+    /// Creates a new pending deposit
+    pub fn new(user_id: Uuid, amount: Decimal) -> Self {
+        Self {
+            deposit_id: Uuid::new_v4(),
+            user_id,
+            amount,
+            status: DepositStatus::Pending,
+            provider_reference: None,
+            checkout_url: None,
+            failure_reason: None,
+            created_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+    
+    /// Checks if the deposit is still waiting for the provider
+    pub fn is_pending(&self) -> bool {
+        self.status == DepositStatus::Pending
+    }
+    
+    /// Marks the deposit paid and credited
+    pub fn complete(&mut self) {
+        self.status = DepositStatus::Completed;
+        self.completed_at = Some(Utc::now());
+    }
+    
+    /// Marks the deposit failed
+    pub fn fail(&mut self, reason: Option<String>) {
+        self.status = DepositStatus::Failed;
+        self.failure_reason = reason;
+        self.completed_at = Some(Utc::now());
+    }
+}
+
+/// A payment the provider has set up for a deposit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentIntent {
+    /// The provider's reference for the payment
+    pub provider_reference: String,
+    
+    /// Where the user completes the payment
+    pub checkout_url: String,
+}
+
+/// The outcome of a payment, as reported by the provider's webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositCallback {
+    /// Deposit the payment was for
+    pub deposit_id: Uuid,
+    
+    /// The provider's reference for the payment
+    pub provider_reference: String,
+    
+    /// Amount the provider collected
+    pub amount: Decimal,
+    
+    /// Whether the payment went through
+    pub status: DepositStatus,
+    
+    /// Why the payment failed, if it did
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::market::MarketStatus;
+
+/// Represents the status of an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventStatus {
+    /// Event markets are open for trading
+    Open,
+    
+    /// Trading is paused on all of the event's markets
+    Paused,
+    
+    /// Trading has ended on all of the event's markets
+    Closed,
+    
+    /// The event was called off and its markets cancelled
+    Cancelled,
+}
+
+impl From<i32> for EventStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => EventStatus::Open,
+            1 => EventStatus::Paused,
+            2 => EventStatus::Closed,
+            3 => EventStatus::Cancelled,
+            _ => panic!("Invalid EventStatus value: {}", value),
+        }
+    }
+}
+
+impl From<EventStatus> for i32 {
+    fn from(value: EventStatus) -> Self {
+        match value {
+            EventStatus::Open => 0,
+            EventStatus::Paused => 1,
+            EventStatus::Closed => 2,
+            EventStatus::Cancelled => 3,
+        }
+    }
+}
+
+/// A real-world event, such as a cricket match, that owns a set of markets
+///
+/// Status changes on the event fan out to every market it owns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Unique identifier for the event
+    pub event_id: String,
+    
+    /// Display name of the event
+    pub name: String,
+    
+    /// Description of the event
+    pub description: String,
+    
+    /// Current status of the event
+    pub status: EventStatus,
+    
+    /// IDs of the markets the event owns
+    pub market_ids: Vec<String>,
+    
+    /// When the event is scheduled to start
+    pub starts_at: Option<DateTime<Utc>>,
+    
+    /// When the event was created
+    pub created_at: DateTime<Utc>,
+    
+    /// When the event was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Event {
+    /// Creates a new open event with no markets
+    pub fn new(
+        event_id: String,
+        name: String,
+        description: String,
+        starts_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            event_id,
+            name,
+            description,
+            status: EventStatus::Open,
+            market_ids: Vec::new(),
+            starts_at,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    /// Checks if markets can still be added to the event
+    pub fn accepts_markets(&self) -> bool {
+        matches!(self.status, EventStatus::Open | EventStatus::Paused)
+    }
+    
+    /// Moves the event to a new status if the transition is allowed
+    pub fn transition(&mut self, status: EventStatus) -> Result<(), String> {
+        let allowed = match status {
+            EventStatus::Open => self.status == EventStatus::Paused,
+            EventStatus::Paused => self.status == EventStatus::Open,
+            EventStatus::Closed => matches!(self.status, EventStatus::Open | EventStatus::Paused),
+            EventStatus::Cancelled => self.status != EventStatus::Cancelled,
+        };
+        
+        if !allowed {
+            return Err(format!(
+                "Event {} cannot move from {:?} to {:?}",
+                self.event_id, self.status, status
+            ));
+        }
+        
+        self.status = status;
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+}
+
+/// Status of one of an event's markets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStatusSummary {
+    /// ID of the market
+    pub market_id: String,
+    
+    /// The market's question
+    pub question: String,
+    
+    /// Current status of the market
+    pub status: MarketStatus,
+}
+
+/// Status of an event and all of its markets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventStatusSummary {
+    /// ID of the event
+    pub event_id: String,
+    
+    /// Display name of the event
+    pub name: String,
+    
+    /// Current status of the event
+    pub status: EventStatus,
+    
+    /// Status of each market the event owns
+    pub markets: Vec<MarketStatusSummary>,
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A request made with an idempotency key and, once it has finished, its response
+///
+/// Keys are scoped to the method and path they were used on, so the same key can
+/// be used once on each endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    /// Method and path of the request (e.g. `POST /api/orders`)
+    pub endpoint: String,
+    
+    /// Key the client sent with the request
+    pub idempotency_key: String,
+    
+    /// Body of the first request made with the key
+    pub request_body: String,
+    
+    /// HTTP status of the response, once the request has finished
+    pub status_code: Option<u16>,
+    
+    /// Body of the response, once the request has finished
+    pub response_body: Option<String>,
+    
+    /// When the key was first used
+    pub created_at: DateTime<Utc>,
+    
+    /// When the response was stored
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl IdempotencyRecord {
+    /// Creates a record for a request that has not finished yet
+    pub fn new(endpoint: String, idempotency_key: String, request_body: String) -> Self {
+        Self {
+            endpoint,
+            idempotency_key,
+            request_body,
+            status_code: None,
+            response_body: None,
+            created_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+    
+    /// Checks if the request has finished and its response is stored
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+}
@@ -0,0 +1,351 @@
+use std::fmt;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::balance::{BalanceBucket, BalanceTransaction, TransactionType};
+
+/// Kind of account money can be held in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LedgerAccountType {
+    /// Money outside the platform; deposits come from it and withdrawals go to it
+    External,
+    
+    /// A user's deposited cash, free to trade or withdraw
+    UserAvailable,
+    
+    /// A user's funds reserved for orders and short positions
+    UserReserved,
+    
+    /// Funds paid into a market for shares, held until they are paid out
+    MarketEscrow,
+    
+    /// Fees collected by the house
+    HouseFees,
+    
+    /// A market maker's pool: the subsidy set aside from the house account plus what it has taken in from trading
+    AmmSubsidy,
+    
+    /// Funds a user has reserved in a market for orders and short positions
+    MarketCollateral,
+    
+    /// A user's funds held for withdrawals awaiting review
+    PendingWithdrawal,
+    
+    /// A user's trade proceeds and settlement payouts, free to trade or withdraw
+    UserWinnings,
+    
+    /// A user's promotional credit, free to trade but not to withdraw
+    UserPromo,
+    
+    /// Tax withheld from users' winnings, owed to the tax authority
+    TaxLiability,
+}
+
+impl From<i32> for LedgerAccountType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => LedgerAccountType::External,
+            1 => LedgerAccountType::UserAvailable,
+            2 => LedgerAccountType::UserReserved,
+            3 => LedgerAccountType::MarketEscrow,
+            4 => LedgerAccountType::HouseFees,
+            5 => LedgerAccountType::AmmSubsidy,
+            6 => LedgerAccountType::MarketCollateral,
+            7 => LedgerAccountType::PendingWithdrawal,
+            8 => LedgerAccountType::UserWinnings,
+            9 => LedgerAccountType::UserPromo,
+            10 => LedgerAccountType::TaxLiability,
+            _ => panic!("Invalid LedgerAccountType value: {}", value),
+        }
+    }
+}
+
+impl From<LedgerAccountType> for i32 {
+    fn from(value: LedgerAccountType) -> Self {
+        match value {
+            LedgerAccountType::External => 0,
+            LedgerAccountType::UserAvailable => 1,
+            LedgerAccountType::UserReserved => 2,
+            LedgerAccountType::MarketEscrow => 3,
+            LedgerAccountType::HouseFees => 4,
+            LedgerAccountType::AmmSubsidy => 5,
+            LedgerAccountType::MarketCollateral => 6,
+            LedgerAccountType::PendingWithdrawal => 7,
+            LedgerAccountType::UserWinnings => 8,
+            LedgerAccountType::UserPromo => 9,
+            LedgerAccountType::TaxLiability => 10,
+        }
+    }
+}
+
+/// An account in the ledger
+///
+/// User accounts are owned by a user ID and market accounts by a market ID;
+/// the external, house fee and tax liability accounts have no owner. Collateral accounts and
+/// market maker pools are owned by the market and also name the user whose
+/// funds they hold.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LedgerAccount {
+    /// Kind of account
+    pub account_type: LedgerAccountType,
+    
+    /// User or market the account belongs to
+    pub owner_id: Option<String>,
+    
+    /// User whose funds a market account holds
+    pub holder_id: Option<String>,
+}
+
+impl LedgerAccount {
+    /// The account money enters and leaves the platform through
+    pub fn external() -> Self {
+        Self { account_type: LedgerAccountType::External, owner_id: None, holder_id: None }
+    }
+    
+    /// A user's available cash
+    pub fn user_available(user_id: Uuid) -> Self {
+        Self { account_type: LedgerAccountType::UserAvailable, owner_id: Some(user_id.to_string()), holder_id: None }
+    }
+    
+    /// A user's available winnings
+    pub fn user_winnings(user_id: Uuid) -> Self {
+        Self { account_type: LedgerAccountType::UserWinnings, owner_id: Some(user_id.to_string()), holder_id: None }
+    }
+    
+    /// A user's available promotional credit
+    pub fn user_promo(user_id: Uuid) -> Self {
+        Self { account_type: LedgerAccountType::UserPromo, owner_id: Some(user_id.to_string()), holder_id: None }
+    }
+    
+    /// The account holding one bucket of a user's available balance
+    pub fn user_bucket(user_id: Uuid, bucket: BalanceBucket) -> Self {
+        match bucket {
+            BalanceBucket::Cash => Self::user_available(user_id),
+            BalanceBucket::Winnings => Self::user_winnings(user_id),
+            BalanceBucket::Promo => Self::user_promo(user_id),
+        }
+    }
+    
+    /// A user's reserved funds
+    pub fn user_reserved(user_id: Uuid) -> Self {
+        Self { account_type: LedgerAccountType::UserReserved, owner_id: Some(user_id.to_string()), holder_id: None }
+    }
+    
+    /// Funds held by a market
+    pub fn market_escrow(market_id: &str) -> Self {
+        Self { account_type: LedgerAccountType::MarketEscrow, owner_id: Some(market_id.to_string()), holder_id: None }
+    }
+    
+    /// Fees collected by the house
+    pub fn house_fees() -> Self {
+        Self { account_type: LedgerAccountType::HouseFees, owner_id: None, holder_id: None }
+    }
+    
+    /// Pool funding a market's automated market maker, held for the house account that funds it
+    pub fn amm_subsidy(market_id: &str, house_account_id: Uuid) -> Self {
+        Self {
+            account_type: LedgerAccountType::AmmSubsidy,
+            owner_id: Some(market_id.to_string()),
+            holder_id: Some(house_account_id.to_string()),
+        }
+    }
+    
+    /// A user's collateral held by a market
+    pub fn market_collateral(market_id: &str, user_id: Uuid) -> Self {
+        Self {
+            account_type: LedgerAccountType::MarketCollateral,
+            owner_id: Some(market_id.to_string()),
+            holder_id: Some(user_id.to_string()),
+        }
+    }
+    
+    /// A user's funds held for withdrawals awaiting review
+    pub fn pending_withdrawal(user_id: Uuid) -> Self {
+        Self { account_type: LedgerAccountType::PendingWithdrawal, owner_id: Some(user_id.to_string()), holder_id: None }
+    }
+    
+    /// Tax withheld from users and owed to the tax authority
+    pub fn tax_liability() -> Self {
+        Self { account_type: LedgerAccountType::TaxLiability, owner_id: None, holder_id: None }
+    }
+    
+    /// Checks if the account holds one bucket of a user's available balance
+    pub fn is_user_available_account(&self) -> bool {
+        matches!(
+            self.account_type,
+            LedgerAccountType::UserAvailable | LedgerAccountType::UserWinnings | LedgerAccountType::UserPromo
+        )
+    }
+    
+    /// Checks if the account belongs to a market
+    pub fn is_market_account(&self) -> bool {
+        matches!(
+            self.account_type,
+            LedgerAccountType::MarketEscrow | LedgerAccountType::AmmSubsidy | LedgerAccountType::MarketCollateral
+        )
+    }
+}
+
+impl fmt::Display for LedgerAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.owner_id, &self.holder_id) {
+            (Some(owner_id), Some(holder_id)) => write!(f, "{:?}:{}:{}", self.account_type, owner_id, holder_id),
+            (Some(owner_id), None) => write!(f, "{:?}:{}", self.account_type, owner_id),
+            _ => write!(f, "{:?}", self.account_type),
+        }
+    }
+}
+
+/// One movement of money in the ledger
+///
+/// Balances are what the platform holds for each account, so the debited account
+/// loses `amount` and the credited account gains it. Entries are never changed
+/// once written; mistakes are corrected with further entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Unique ID for the entry
+    pub entry_id: Uuid,
+    
+    /// Account the money leaves
+    pub debit_account: LedgerAccount,
+    
+    /// Account the money goes to
+    pub credit_account: LedgerAccount,
+    
+    /// Amount moved (always positive)
+    pub amount: Decimal,
+    
+    /// Type of transaction that caused the movement
+    pub transaction_type: TransactionType,
+    
+    /// Reference to related entity (order_id, market_id, etc.)
+    pub reference_id: Option<String>,
+    
+    /// Description of the movement
+    pub description: String,
+    
+    /// When the entry was written
+    pub created_at: DateTime<Utc>,
+}
+
+impl LedgerEntry {
+    /// Creates a new ledger entry
+    pub fn new(
+        debit_account: LedgerAccount,
+        credit_account: LedgerAccount,
+        amount: Decimal,
+        transaction_type: TransactionType,
+        reference_id: Option<String>,
+        description: String,
+    ) -> Self {
+        Self {
+            entry_id: Uuid::new_v4(),
+            debit_account,
+            credit_account,
+            amount,
+            transaction_type,
+            reference_id,
+            description,
+            created_at: Utc::now(),
+        }
+    }
+    
+    /// Creates the ledger entry for a balance transaction
+    pub fn for_transaction(transaction: &BalanceTransaction, debit_account: LedgerAccount, credit_account: LedgerAccount) -> Self {
+        Self::new(
+            debit_account,
+            credit_account,
+            transaction.amount,
+            transaction.transaction_type,
+            transaction.reference_id.clone(),
+            transaction.description.clone(),
+        )
+    }
+}
+
+/// The balance of a ledger account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerBalance {
+    /// The account
+    pub account: LedgerAccount,
+    
+    /// Credits less debits
+    pub balance: Decimal,
+}
+
+/// Result of checking that the ledger conserves money and agrees with user balances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerCheck {
+    /// Sum of every account balance, including the external account (zero when money is conserved)
+    pub net_balance: Decimal,
+    
+    /// Money held on the platform: everything that came in from the external account
+    pub money_on_platform: Decimal,
+    
+    /// Money held in user accounts
+    pub held_by_users: Decimal,
+    
+    /// Money held in market escrow
+    pub held_in_escrow: Decimal,
+    
+    /// Money held as house fees
+    pub held_as_fees: Decimal,
+    
+    /// Money held in market maker pools
+    pub held_as_subsidies: Decimal,
+    
+    /// Money held as collateral in markets
+    pub held_as_collateral: Decimal,
+    
+    /// Money held for withdrawals awaiting review
+    pub held_for_withdrawals: Decimal,
+    
+    /// Tax withheld from winnings and owed to the tax authority
+    pub held_for_tax: Decimal,
+    
+    /// Promotional credit held by users, included in `held_by_users`
+    pub held_as_promo: Decimal,
+    
+    /// Problems found; empty when every invariant holds
+    pub violations: Vec<String>,
+    
+    /// When the check ran
+    pub checked_at: DateTime<Utc>,
+}
+
+impl LedgerCheck {
+    /// Checks if every invariant holds
+    pub fn is_consistent(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// What a market holds and what it could owe
+///
+/// A market is fully collateralized when it holds enough to pay every long share
+/// of whichever outcome wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketEscrow {
+    /// The market
+    pub market_id: String,
+    
+    /// Funds paid in for shares and not yet paid out
+    pub escrow: Decimal,
+    
+    /// Collateral reserved by users for orders and short positions
+    pub collateral: Decimal,
+    
+    /// Funds in the market maker's pool
+    pub market_maker_pool: Decimal,
+    
+    /// Everything the market holds
+    pub total_held: Decimal,
+    
+    /// Most the market could have to pay out on resolution
+    pub max_liability: Decimal,
+    
+    /// Whether the market holds at least its maximum liability
+    pub is_fully_collateralized: bool,
+}
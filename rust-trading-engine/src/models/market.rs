@@ -4,7 +4,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
 use uuid::Uuid;
 
-use crate::models::order::{Order, OrderSide, OrderStatus, OutcomeSide};
+use crate::models::order::{Order, OrderSide, OutcomeSide};
+use crate::models::event::EventStatus;
 
 /// Represents the status of a prediction market
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -26,6 +27,9 @@ pub enum MarketStatus {
     
     /// Market has been cancelled (e.g., due to unforeseen circumstances)
     Cancelled,
+    
+    /// Categorical market has been resolved to one of its outcomes
+    Resolved,
 }
 
 impl From<i32> for MarketStatus {
@@ -37,6 +41,7 @@ impl From<i32> for MarketStatus {
             3 => MarketStatus::ResolvedYes,
             4 => MarketStatus::ResolvedNo,
             5 => MarketStatus::Cancelled,
+            6 => MarketStatus::Resolved,
             _ => panic!("Invalid MarketStatus value: {}", value),
         }
     }
@@ -51,89 +56,166 @@ impl From<MarketStatus> for i32 {
             MarketStatus::ResolvedYes => 3,
             MarketStatus::ResolvedNo => 4,
             MarketStatus::Cancelled => 5,
+            MarketStatus::Resolved => 6,
         }
     }
 }
 
-/// Represents the order book for a prediction market
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderBook {
-    /// Buy orders for Yes outcome (sorted by price descending)
-    pub yes_bids: BTreeMap<Decimal, VecDeque<Order>>,
+/// Where a market sources liquidity for incoming orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LiquidityMode {
+    /// Orders only match against other orders in the order book
+    OrderBook,
     
-    /// Sell orders for Yes outcome (sorted by price ascending)
-    pub yes_asks: BTreeMap<Decimal, VecDeque<Order>>,
+    /// Orders only fill against the automated market maker
+    Amm,
     
-    /// Buy orders for No outcome (sorted by price descending)
-    pub no_bids: BTreeMap<Decimal, VecDeque<Order>>,
+    /// Orders match against the order book first, then the automated market maker
+    Hybrid,
+}
+
+impl From<i32> for LiquidityMode {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => LiquidityMode::OrderBook,
+            1 => LiquidityMode::Amm,
+            2 => LiquidityMode::Hybrid,
+            _ => panic!("Invalid LiquidityMode value: {}", value),
+        }
+    }
+}
+
+impl From<LiquidityMode> for i32 {
+    fn from(value: LiquidityMode) -> Self {
+        match value {
+            LiquidityMode::OrderBook => 0,
+            LiquidityMode::Amm => 1,
+            LiquidityMode::Hybrid => 2,
+        }
+    }
+}
+
+/// Bids and asks for a single outcome
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutcomeBook {
+    /// Buy orders (best bid is the highest price)
+    pub bids: BTreeMap<Decimal, VecDeque<Order>>,
     
-    /// Sell orders for No outcome (sorted by price ascending)
-    pub no_asks: BTreeMap<Decimal, VecDeque<Order>>,
+    /// Sell orders (best ask is the lowest price)
+    pub asks: BTreeMap<Decimal, VecDeque<Order>>,
+}
+
+impl OutcomeBook {
+    /// Gets the book side that holds orders of the given side
+    pub fn side_mut(&mut self, side: OrderSide) -> &mut BTreeMap<Decimal, VecDeque<Order>> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+}
+
+/// Liquidity found by walking one side of an outcome's book
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BookWalk {
+    /// Shares the resting orders can fill
+    pub quantity: u32,
+    
+    /// Total cash exchanged for those shares
+    pub amount: Decimal,
+    
+    /// Price of the first level reached
+    pub best_price: Option<Decimal>,
+    
+    /// Price of the last level needed
+    pub worst_price: Option<Decimal>,
+}
+
+/// How a market pays out when it resolves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarketType {
+    /// Pays 1 per share of the single winning outcome (binary markets are the two-outcome case)
+    Categorical,
+    
+    /// Pays Long and Short shares linearly on where a numeric value lands between two bounds
+    Scalar,
+}
+
+impl From<i32> for MarketType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => MarketType::Categorical,
+            1 => MarketType::Scalar,
+            _ => panic!("Invalid MarketType value: {}", value),
+        }
+    }
+}
+
+impl From<MarketType> for i32 {
+    fn from(value: MarketType) -> Self {
+        match value {
+            MarketType::Categorical => 0,
+            MarketType::Scalar => 1,
+        }
+    }
+}
+
+/// Represents the order book for a prediction market, with one book per outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    /// Books indexed by outcome
+    pub outcomes: Vec<OutcomeBook>,
 }
 
 impl OrderBook {
-    /// Creates a new, empty order book
-    pub fn new() -> Self {
+    /// Creates a new, empty order book for a market with `num_outcomes` outcomes
+    pub fn new(num_outcomes: usize) -> Self {
         Self {
-            yes_bids: BTreeMap::new(),
-            yes_asks: BTreeMap::new(),
-            no_bids: BTreeMap::new(),
-            no_asks: BTreeMap::new(),
+            outcomes: vec![OutcomeBook::default(); num_outcomes],
         }
     }
 
+    /// Gets the book for an outcome
+    pub fn outcome_book(&self, outcome: OutcomeSide) -> Option<&OutcomeBook> {
+        self.outcomes.get(outcome.index())
+    }
+
+    /// Gets the book for an outcome, growing the order book if needed
+    pub fn outcome_book_mut(&mut self, outcome: OutcomeSide) -> &mut OutcomeBook {
+        if outcome.index() >= self.outcomes.len() {
+            self.outcomes.resize_with(outcome.index() + 1, OutcomeBook::default);
+        }
+        &mut self.outcomes[outcome.index()]
+    }
+
     /// Adds an order to the appropriate section of the order book
     pub fn add_order(&mut self, order: Order) {
         if !order.is_active() {
             return;
         }
 
-        let book = match (order.side, order.outcome) {
-            (OrderSide::Buy, OutcomeSide::Yes) => &mut self.yes_bids,
-            (OrderSide::Sell, OutcomeSide::Yes) => &mut self.yes_asks,
-            (OrderSide::Buy, OutcomeSide::No) => &mut self.no_bids,
-            (OrderSide::Sell, OutcomeSide::No) => &mut self.no_asks,
-        };
-
-        book.entry(order.price)
-            .or_insert_with(VecDeque::new)
+        self.outcome_book_mut(order.outcome)
+            .side_mut(order.side)
+            .entry(order.price)
+            .or_default()
             .push_back(order);
     }
 
     /// Gets the best bid and ask prices for a specific outcome
     pub fn get_best_prices(&self, outcome: OutcomeSide) -> (Option<Decimal>, Option<Decimal>) {
-        match outcome {
-            OutcomeSide::Yes => {
-                let best_bid = self.yes_bids.keys().next().cloned();
-                let best_ask = self.yes_asks.keys().next().cloned();
-                (best_bid, best_ask)
-            }
-            OutcomeSide::No => {
-                let best_bid = self.no_bids.keys().next().cloned();
-                let best_ask = self.no_asks.keys().next().cloned();
-                (best_bid, best_ask)
-            }
-        }
+        (self.get_best_bid_price(outcome), self.get_best_ask_price(outcome))
     }
 
-    /// Gets the best Yes bid price
-    pub fn get_best_yes_bid_price(&self) -> Option<Decimal> {
-        self.yes_bids.keys().next().cloned()
-    }
-    
-    /// Gets the best Yes ask price
-    pub fn get_best_yes_ask_price(&self) -> Option<Decimal> {
-        self.yes_asks.keys().next().cloned()
+    /// Gets the best (highest) bid price for an outcome
+    pub fn get_best_bid_price(&self, outcome: OutcomeSide) -> Option<Decimal> {
+        self.outcome_book(outcome)
+            .and_then(|book| book.bids.keys().next_back().cloned())
     }
     
-    /// Gets the best No bid price
-    pub fn get_best_no_bid_price(&self) -> Option<Decimal> {
-        self.no_bids.keys().next().cloned()
-    }
-    
-    /// Gets the best No ask price
-    pub fn get_best_no_ask_price(&self) -> Option<Decimal> {
-        self.no_asks.keys().next().cloned()
+    /// Gets the best (lowest) ask price for an outcome
+    pub fn get_best_ask_price(&self, outcome: OutcomeSide) -> Option<Decimal> {
+        self.outcome_book(outcome)
+            .and_then(|book| book.asks.keys().next().cloned())
     }
 
     /// Gets the mid price for a specific outcome
@@ -149,31 +231,77 @@ impl OrderBook {
 
     /// Gets the implied probability for a Yes outcome
     pub fn get_implied_probability(&self) -> Option<Decimal> {
-        self.get_mid_price(OutcomeSide::Yes)
+        self.get_mid_price(OutcomeSide::YES)
+    }
+
+    /// Walks the book an incoming order of `side` would match against, best price first
+    ///
+    /// Orders from `user_id` are skipped, since the matching engine never matches a
+    /// user against themselves.
+    pub fn walk(&self, outcome: OutcomeSide, side: OrderSide, quantity: u32, user_id: Uuid) -> BookWalk {
+        let mut walk = BookWalk::default();
+        let book = match self.outcome_book(outcome) {
+            Some(book) => book,
+            None => return walk,
+        };
+        
+        let levels: Box<dyn Iterator<Item = (&Decimal, &VecDeque<Order>)>> = match side {
+            OrderSide::Buy => Box::new(book.asks.iter()),
+            OrderSide::Sell => Box::new(book.bids.iter().rev()),
+        };
+        
+        for (price, orders) in levels {
+            let available: u32 = orders.iter()
+                .filter(|order| order.user_id != user_id)
+                .map(|order| order.remaining_quantity)
+                .sum();
+            let filled = available.min(quantity - walk.quantity);
+            if filled == 0 {
+                continue;
+            }
+            
+            walk.best_price.get_or_insert(*price);
+            walk.worst_price = Some(*price);
+            walk.quantity += filled;
+            walk.amount += *price * Decimal::from(filled);
+            
+            if walk.quantity == quantity {
+                break;
+            }
+        }
+        
+        walk
+    }
+
+    /// Iterates over every resting order in the book
+    pub fn all_orders(&self) -> impl Iterator<Item = &Order> {
+        self.outcomes.iter()
+            .flat_map(|book| book.bids.values().chain(book.asks.values()))
+            .flat_map(|orders| orders.iter())
     }
 
     /// Removes an order from the order book by ID
     pub fn remove_order(&mut self, order_id: Uuid) -> Option<Order> {
         // Check all order queues
-        for &outcome in &[OutcomeSide::Yes, OutcomeSide::No] {
-            for &side in &[OrderSide::Buy, OrderSide::Sell] {
-                let book = match (side, outcome) {
-                    (OrderSide::Buy, OutcomeSide::Yes) => &mut self.yes_bids,
-                    (OrderSide::Sell, OutcomeSide::Yes) => &mut self.yes_asks,
-                    (OrderSide::Buy, OutcomeSide::No) => &mut self.no_bids,
-                    (OrderSide::Sell, OutcomeSide::No) => &mut self.no_asks,
-                };
-
+        for outcome_book in self.outcomes.iter_mut() {
+            for book in [&mut outcome_book.bids, &mut outcome_book.asks] {
                 // Look through each price level
-                for orders in book.values_mut() {
+                let mut found = None;
+                for (price, orders) in book.iter_mut() {
                     // Find the order by ID
                     if let Some(pos) = orders.iter().position(|o| o.order_id == order_id) {
-                        let order = orders.remove(pos).unwrap();
-                        // If this was the last order at this price level, the map entry will be
-                        // automatically removed when the orders VecDeque is dropped
-                        return Some(order);
+                        found = Some((*price, orders.remove(pos)));
+                        break;
                     }
                 }
+                
+                if let Some((price, order)) = found {
+                    // Drop the price level if this was its last order
+                    if book.get(&price).is_some_and(|orders| orders.is_empty()) {
+                        book.remove(&price);
+                    }
+                    return order;
+                }
             }
         }
 
@@ -207,6 +335,24 @@ pub struct Market {
     /// Detailed description of the market
     pub description: String,
     
+    /// Names of the market's outcomes, indexed by `OutcomeSide`
+    pub outcomes: Vec<String>,
+    
+    /// How the market pays out when it resolves
+    pub market_type: MarketType,
+    
+    /// The mutually exclusive group this market belongs to, if any
+    pub group_id: Option<String>,
+    
+    /// The event that owns this market, if any
+    pub event_id: Option<String>,
+    
+    /// Lower bound of the range (scalar markets only)
+    pub lower_bound: Option<Decimal>,
+    
+    /// Upper bound of the range (scalar markets only)
+    pub upper_bound: Option<Decimal>,
+    
     /// Current status of the market
     pub status: MarketStatus,
     
@@ -227,36 +373,101 @@ pub struct Market {
     
     /// The outcome the market was resolved to (if resolved)
     pub resolution: Option<OutcomeSide>,
+    
+    /// The value a scalar market was resolved to (if resolved)
+    pub resolved_value: Option<Decimal>,
+    
+    /// Where the market sources liquidity for incoming orders
+    pub liquidity_mode: LiquidityMode,
+    
+    /// Whether sell orders may exceed the shares the seller holds
+    pub allow_shorting: bool,
 }
 
 impl Market {
-    /// Creates a new prediction market
+    /// Account on the other side of every complete set minted or redeemed
+    ///
+    /// It holds one short share of each outcome per set outstanding, backed by the
+    /// unit locked when the set was minted rather than by a balance of its own.
+    pub const COMPLETE_SET_ISSUER_ID: Uuid = Uuid::nil();
+
+    /// Creates a new binary (Yes/No) prediction market
     pub fn new(
         market_id: String,
         question: String,
         description: String,
         close_time: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self::new_categorical(
+            market_id,
+            question,
+            description,
+            vec!["Yes".to_string(), "No".to_string()],
+            close_time,
+        )
+    }
+
+    /// Creates a new prediction market with the given named outcomes
+    pub fn new_categorical(
+        market_id: String,
+        question: String,
+        description: String,
+        outcomes: Vec<String>,
+        close_time: Option<DateTime<Utc>>,
     ) -> Self {
         let now = Utc::now();
         Self {
             market_id,
             question,
             description,
+            order_book: OrderBook::new(outcomes.len()),
+            outcomes,
+            market_type: MarketType::Categorical,
+            group_id: None,
+            event_id: None,
+            lower_bound: None,
+            upper_bound: None,
             status: MarketStatus::Open,
-            order_book: OrderBook::new(),
             created_at: now,
             updated_at: now,
             close_time,
             resolved_at: None,
             resolution: None,
+            resolved_value: None,
+            liquidity_mode: LiquidityMode::OrderBook,
+            allow_shorting: true,
         }
     }
 
+    /// Creates a new scalar market that settles on a value between two bounds
+    pub fn new_scalar(
+        market_id: String,
+        question: String,
+        description: String,
+        lower_bound: Decimal,
+        upper_bound: Decimal,
+        close_time: Option<DateTime<Utc>>,
+    ) -> Self {
+        let mut market = Self::new_categorical(
+            market_id,
+            question,
+            description,
+            vec!["Long".to_string(), "Short".to_string()],
+            close_time,
+        );
+        market.market_type = MarketType::Scalar;
+        market.lower_bound = Some(lower_bound);
+        market.upper_bound = Some(upper_bound);
+        market
+    }
+
     /// Creates a Market from database fields and orders
+    #[allow(clippy::too_many_arguments)]
     pub fn from_db(
         id: String,
         question: String,
         description: String,
+        outcomes: Vec<String>,
         status: MarketStatus,
         created_at: DateTime<Utc>,
         updated_at: DateTime<Utc>,
@@ -269,13 +480,22 @@ impl Market {
             market_id: id,
             question,
             description,
+            order_book: OrderBook::new(outcomes.len()),
+            outcomes,
+            market_type: MarketType::Categorical,
+            group_id: None,
+            event_id: None,
+            lower_bound: None,
+            upper_bound: None,
             status,
-            order_book: OrderBook::new(),
             created_at,
             updated_at,
             close_time,
             resolved_at,
             resolution,
+            resolved_value: None,
+            liquidity_mode: LiquidityMode::OrderBook,
+            allow_shorting: true,
         };
         
         // Populate order book with active orders
@@ -293,23 +513,184 @@ impl Market {
         self.status == MarketStatus::Open
     }
 
+    /// Validates a list of outcome names for a new market
+    pub fn validate_outcomes(outcomes: &[String]) -> Result<(), String> {
+        if outcomes.len() < 2 {
+            return Err("A market needs at least two outcomes".to_string());
+        }
+        
+        for (i, name) in outcomes.iter().enumerate() {
+            if name.trim().is_empty() {
+                return Err("Outcome names cannot be empty".to_string());
+            }
+            if outcomes[..i].contains(name) {
+                return Err(format!("Duplicate outcome name: {}", name));
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Validates the bounds of a scalar market
+    pub fn validate_bounds(&self) -> Result<(), String> {
+        if self.market_type != MarketType::Scalar {
+            return Ok(());
+        }
+        
+        match (self.lower_bound, self.upper_bound) {
+            (Some(lower), Some(upper)) if lower < upper => Ok(()),
+            (Some(_), Some(_)) => Err("Lower bound must be below upper bound".to_string()),
+            _ => Err("Scalar markets need a lower and an upper bound".to_string()),
+        }
+    }
+
+    /// Checks if this is a scalar market
+    pub fn is_scalar(&self) -> bool {
+        self.market_type == MarketType::Scalar
+    }
+
+    /// Gets the number of outcomes in the market
+    pub fn num_outcomes(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// Checks if this is a binary (Yes/No) market
+    pub fn is_binary(&self) -> bool {
+        self.market_type == MarketType::Categorical && self.outcomes.len() == 2
+    }
+
+    /// Checks if an outcome belongs to this market
+    pub fn has_outcome(&self, outcome: OutcomeSide) -> bool {
+        outcome.index() < self.outcomes.len()
+    }
+
+    /// Looks up an outcome by name
+    pub fn outcome_by_name(&self, name: &str) -> Option<OutcomeSide> {
+        self.outcomes.iter()
+            .position(|outcome| outcome == name)
+            .map(|index| OutcomeSide::new(index as u32))
+    }
+
+    /// Gets the name of an outcome
+    pub fn outcome_name(&self, outcome: OutcomeSide) -> Option<&str> {
+        self.outcomes.get(outcome.index()).map(|name| name.as_str())
+    }
+
+    /// Checks if the market routes orders to an automated market maker
+    pub fn uses_amm(&self) -> bool {
+        matches!(self.liquidity_mode, LiquidityMode::Amm | LiquidityMode::Hybrid)
+    }
+
+    /// Checks if the market routes orders to the order book
+    pub fn uses_order_book(&self) -> bool {
+        matches!(self.liquidity_mode, LiquidityMode::OrderBook | LiquidityMode::Hybrid)
+    }
+
     /// Checks if the market has been resolved
     pub fn is_resolved(&self) -> bool {
-        matches!(self.status, MarketStatus::ResolvedYes | MarketStatus::ResolvedNo)
+        matches!(self.status, MarketStatus::ResolvedYes | MarketStatus::ResolvedNo | MarketStatus::Resolved)
+    }
+
+    /// Checks if the market has been given a resolution but is not fully settled yet
+    pub fn is_settling(&self) -> bool {
+        !self.is_resolved() && (self.resolution.is_some() || self.resolved_value.is_some())
     }
 
     /// Resolves the market to a specific outcome
     pub fn resolve(&mut self, outcome: OutcomeSide) {
         let now = Utc::now();
-        self.status = match outcome {
-            OutcomeSide::Yes => MarketStatus::ResolvedYes,
-            OutcomeSide::No => MarketStatus::ResolvedNo,
+        self.status = match (self.is_binary(), outcome) {
+            (true, OutcomeSide::YES) => MarketStatus::ResolvedYes,
+            (true, OutcomeSide::NO) => MarketStatus::ResolvedNo,
+            _ => MarketStatus::Resolved,
         };
         self.resolved_at = Some(now);
         self.resolution = Some(outcome);
         self.updated_at = now;
     }
 
+    /// Resolves a scalar market to the final value of the underlying quantity
+    pub fn resolve_scalar(&mut self, value: Decimal) {
+        let now = Utc::now();
+        self.status = MarketStatus::Resolved;
+        self.resolved_at = Some(now);
+        self.resolved_value = Some(value);
+        self.updated_at = now;
+    }
+
+    /// Gets the fraction of 1 unit paid per Long share for a scalar value
+    ///
+    /// Values at or below the lower bound pay 0 and values at or above the upper
+    /// bound pay 1; Short shares receive the remainder.
+    pub fn scalar_long_payout(&self, value: Decimal) -> Option<Decimal> {
+        let (lower, upper) = (self.lower_bound?, self.upper_bound?);
+        if upper <= lower {
+            return None;
+        }
+        let fraction = (value - lower) / (upper - lower);
+        Some(fraction.max(Decimal::ZERO).min(Decimal::ONE))
+    }
+
+    /// Splits the unit price of a complete set across the market's outcomes
+    ///
+    /// Outcomes are priced evenly, with any rounding remainder on the last one so
+    /// the prices always add up to exactly 1.
+    pub fn complete_set_prices(&self) -> Vec<Decimal> {
+        let count = self.num_outcomes();
+        let share = (Decimal::ONE / Decimal::from(count)).round_dp(8);
+        
+        let mut prices = vec![share; count];
+        prices[count - 1] = Decimal::ONE - share * Decimal::from(count - 1);
+        prices
+    }
+
+    /// Gets the amount paid per share of an outcome once the market is resolved
+    pub fn payout_per_share(&self, outcome: OutcomeSide) -> Option<Decimal> {
+        match self.market_type {
+            MarketType::Categorical => {
+                let winner = self.resolution?;
+                Some(if outcome == winner { Decimal::ONE } else { Decimal::ZERO })
+            }
+            MarketType::Scalar => {
+                let long_payout = self.scalar_long_payout(self.resolved_value?)?;
+                match outcome {
+                    OutcomeSide::LONG => Some(long_payout),
+                    OutcomeSide::SHORT => Some(Decimal::ONE - long_payout),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Pauses trading on the market
+    pub fn pause(&mut self) {
+        self.status = MarketStatus::Paused;
+        self.updated_at = Utc::now();
+    }
+
+    /// Resumes trading on a paused market
+    pub fn resume(&mut self) {
+        self.status = MarketStatus::Open;
+        self.updated_at = Utc::now();
+    }
+
+    /// Applies an owning event's status to the market
+    ///
+    /// Only markets in a state the event status applies to are changed; for
+    /// example resuming an event leaves a market that was closed on its own
+    /// closed, and cancelling an event leaves a market that is already settling
+    /// to finish settling. Returns whether the market's status changed.
+    pub fn apply_event_status(&mut self, status: EventStatus) -> bool {
+        match status {
+            EventStatus::Open if self.status == MarketStatus::Paused => self.resume(),
+            EventStatus::Paused if self.status == MarketStatus::Open => self.pause(),
+            EventStatus::Closed if matches!(self.status, MarketStatus::Open | MarketStatus::Paused) => self.close(),
+            EventStatus::Cancelled if !self.is_resolved() && !self.is_settling() && self.status != MarketStatus::Cancelled => self.cancel(),
+            _ => return false,
+        }
+        true
+    }
+
     /// Cancels the market
     pub fn cancel(&mut self) {
         self.status = MarketStatus::Cancelled;
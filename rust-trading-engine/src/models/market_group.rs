@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A group of binary markets that are mutually exclusive and exhaustive
+///
+/// Exactly one market in the group resolves Yes. A No share in one market is
+/// therefore worth the same as one Yes share in every other market of the group,
+/// and can be converted into them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketGroup {
+    /// Unique identifier for the group
+    pub group_id: String,
+    
+    /// Display name of the group
+    pub name: String,
+    
+    /// Description of the group
+    pub description: String,
+    
+    /// IDs of the markets in the group
+    pub market_ids: Vec<String>,
+    
+    /// Account that takes the other side of No to Yes conversions
+    pub house_account_id: Uuid,
+    
+    /// When the group was created
+    pub created_at: DateTime<Utc>,
+    
+    /// When the group was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MarketGroup {
+    /// Creates a new market group
+    pub fn new(
+        group_id: String,
+        name: String,
+        description: String,
+        market_ids: Vec<String>,
+        house_account_id: Uuid,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            group_id,
+            name,
+            description,
+            market_ids,
+            house_account_id,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    /// Checks if a market belongs to the group
+    pub fn contains(&self, market_id: &str) -> bool {
+        self.market_ids.iter().any(|id| id == market_id)
+    }
+    
+    /// Gets the IDs of every other market in the group
+    pub fn siblings_of<'a>(&'a self, market_id: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.market_ids.iter().filter(move |id| id.as_str() != market_id)
+    }
+}
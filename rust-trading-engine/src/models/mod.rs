@@ -2,9 +2,41 @@ pub mod order;
 pub mod trade;
 pub mod market;
 pub mod balance;
+pub mod amm;
+pub mod market_group;
+pub mod event;
+pub mod settlement;
+pub mod position;
+pub mod portfolio;
+pub mod ledger;
+pub mod idempotency;
+pub mod reconciliation;
+pub mod withdrawal;
+pub mod deposit;
+pub mod promo;
+pub mod tax;
+pub mod risk;
+pub mod api_key;
+pub mod role;
 
 // Re-export common types
-pub use order::{Order, OrderSide, OrderStatus, OutcomeSide};
+pub use order::{Order, OrderSide, OrderStatus, OutcomeSide, TimeInForce};
 pub use trade::Trade;
-pub use market::{Market, MarketStatus, OrderBook};
-pub use balance::{UserBalance, BalanceTransaction, TransactionType}; 
\ No newline at end of file
+pub use market::{BookWalk, Market, MarketStatus, MarketType, OrderBook, LiquidityMode};
+pub use balance::{UserBalance, BalanceBucket, BalanceConflict, BalanceTransaction, BucketAmounts, PromoHolding, TransactionType};
+pub use amm::{LmsrMarketMaker, AmmQuote};
+pub use market_group::MarketGroup;
+pub use settlement::{PositionSettlement, SettlementEntry, SettlementReport};
+pub use position::Position;
+pub use portfolio::{MarkPriceSource, MarketPortfolio, Portfolio, PortfolioTotals, PositionValuation};
+pub use ledger::{LedgerAccount, LedgerAccountType, LedgerBalance, LedgerCheck, LedgerEntry, MarketEscrow};
+pub use idempotency::IdempotencyRecord;
+pub use withdrawal::{Withdrawal, WithdrawalStatus};
+pub use deposit::{Deposit, DepositCallback, DepositStatus, PaymentIntent};
+pub use promo::PromoCredit;
+pub use tax::{TaxStatement, TaxWithholding};
+pub use risk::{RiskLimits, RiskRejection, RiskTier};
+pub use api_key::{ApiKey, IssuedApiKey};
+pub use role::{Role, RoleGrant};
+pub use reconciliation::{Discrepancy, DiscrepancyKind, ReconciliationMetrics, ReconciliationReport};
+pub use event::{Event, EventStatus, EventStatusSummary, MarketStatusSummary}; 
\ No newline at end of file
@@ -32,32 +32,95 @@ impl From<OrderSide> for i32 {
     }
 }
 
-/// Side of market outcome (yes or no)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum OutcomeSide {
-    /// Yes outcome
-    Yes,
+/// Index of an outcome within its market
+///
+/// Binary markets have two outcomes, `YES` (0) and `NO` (1), and scalar markets
+/// have `LONG` (0) and `SHORT` (1). Categorical markets have one index per named
+/// outcome, in the order listed on the market.
+/// Deserializes from the index or, for binary markets, from `"Yes"`/`"No"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OutcomeSide(u32);
+
+impl OutcomeSide {
+    /// Yes outcome of a binary market
+    pub const YES: OutcomeSide = OutcomeSide(0);
+    
+    /// No outcome of a binary market
+    pub const NO: OutcomeSide = OutcomeSide(1);
+    
+    /// Long outcome of a scalar market (pays more the higher the value)
+    pub const LONG: OutcomeSide = OutcomeSide(0);
+    
+    /// Short outcome of a scalar market (pays more the lower the value)
+    pub const SHORT: OutcomeSide = OutcomeSide(1);
+    
+    /// Creates an outcome from its index in the market
+    pub fn new(index: u32) -> Self {
+        Self(index)
+    }
     
-    /// No outcome
-    No,
+    /// Gets the index of this outcome in the market
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
 }
 
 impl From<i32> for OutcomeSide {
     fn from(value: i32) -> Self {
-        match value {
-            0 => OutcomeSide::Yes,
-            1 => OutcomeSide::No,
-            _ => panic!("Invalid OutcomeSide value: {}", value),
+        if value < 0 {
+            panic!("Invalid OutcomeSide value: {}", value);
         }
+        OutcomeSide(value as u32)
     }
 }
 
 impl From<OutcomeSide> for i32 {
     fn from(value: OutcomeSide) -> Self {
-        match value {
-            OutcomeSide::Yes => 0,
-            OutcomeSide::No => 1,
+        value.0 as i32
+    }
+}
+
+impl Serialize for OutcomeSide {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for OutcomeSide {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OutcomeVisitor;
+        
+        impl serde::de::Visitor<'_> for OutcomeVisitor {
+            type Value = OutcomeSide;
+            
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an outcome index or \"Yes\"/\"No\"")
+            }
+            
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                u32::try_from(value)
+                    .map(OutcomeSide)
+                    .map_err(|_| E::custom(format!("outcome index out of range: {}", value)))
+            }
+            
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                u32::try_from(value)
+                    .map(OutcomeSide)
+                    .map_err(|_| E::custom(format!("outcome index out of range: {}", value)))
+            }
+            
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                match value {
+                    "Yes" => Ok(OutcomeSide::YES),
+                    "No" => Ok(OutcomeSide::NO),
+                    _ => value.parse::<u32>()
+                        .map(OutcomeSide)
+                        .map_err(|_| E::custom(format!("invalid outcome: {}", value))),
+                }
+            }
         }
+        
+        deserializer.deserialize_any(OutcomeVisitor)
     }
 }
 
@@ -105,6 +168,36 @@ impl From<OrderStatus> for i32 {
     }
 }
 
+/// How long an order stays on the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Any unfilled remainder rests on the book until filled or cancelled
+    #[default]
+    GoodTilCancelled,
+    
+    /// Fills what it can immediately and cancels the remainder
+    ImmediateOrCancel,
+}
+
+impl From<i32> for TimeInForce {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => TimeInForce::GoodTilCancelled,
+            1 => TimeInForce::ImmediateOrCancel,
+            _ => panic!("Invalid TimeInForce value: {}", value),
+        }
+    }
+}
+
+impl From<TimeInForce> for i32 {
+    fn from(value: TimeInForce) -> Self {
+        match value {
+            TimeInForce::GoodTilCancelled => 0,
+            TimeInForce::ImmediateOrCancel => 1,
+        }
+    }
+}
+
 /// A trading order in the prediction market
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -120,7 +213,7 @@ pub struct Order {
     /// Whether this is a buy or sell order
     pub side: OrderSide,
     
-    /// Whether this order is for the Yes or No outcome
+    /// The outcome this order is for
     pub outcome: OutcomeSide,
     
     /// Price of the order (between 0.0 and 1.0)
@@ -132,6 +225,19 @@ pub struct Order {
     /// Remaining quantity to be filled
     pub remaining_quantity: u32,
     
+    /// Shares of a sell order backed by shares the seller already holds rather than
+    /// by collateral; these are filled first
+    #[serde(default)]
+    pub covered_quantity: u32,
+    
+    /// How long the order stays on the book
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    
+    /// ID the client chose for the order, unique among the user's orders
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    
     /// Current status of the order
     pub status: OrderStatus,
     
@@ -143,6 +249,9 @@ pub struct Order {
 }
 
 impl Order {
+    /// Longest client order ID accepted
+    pub const MAX_CLIENT_ORDER_ID_LEN: usize = 64;
+    
     /// Creates a new order
     pub fn new(
         user_id: Uuid,
@@ -162,6 +271,9 @@ impl Order {
             price,
             quantity,
             remaining_quantity: quantity,
+            covered_quantity: 0,
+            time_in_force: TimeInForce::GoodTilCancelled,
+            client_order_id: None,
             status: OrderStatus::Open,
             created_at: now,
             updated_at: now,
@@ -210,6 +322,36 @@ impl Order {
         self.updated_at = Utc::now();
     }
 
+    /// Gets the number of shares filled so far
+    pub fn filled_quantity(&self) -> u32 {
+        self.quantity - self.remaining_quantity
+    }
+
+    /// Gets the number of held shares still locked by the unfilled part of a sell order
+    pub fn locked_shares(&self) -> u32 {
+        self.covered_quantity.saturating_sub(self.filled_quantity())
+    }
+
+    /// Gets the number of unfilled shares of a sell order that are backed by collateral
+    pub fn uncovered_remaining_quantity(&self) -> u32 {
+        self.remaining_quantity - self.locked_shares()
+    }
+
+    /// Gets the number of filled shares of a sell order that were sold short
+    pub fn short_filled_quantity(&self) -> u32 {
+        self.filled_quantity().saturating_sub(self.covered_quantity)
+    }
+
+    /// Gets the funds reserved for the unfilled part of the order
+    ///
+    /// Buys reserve their price per share; sells reserve one unit per share sold short.
+    pub fn reserve_amount(&self) -> Decimal {
+        match self.side {
+            OrderSide::Buy => self.price * Decimal::from(self.remaining_quantity),
+            OrderSide::Sell => Decimal::from(self.uncovered_remaining_quantity()),
+        }
+    }
+
     /// Cancels this order
     pub fn cancel(&mut self) {
         self.status = OrderStatus::Cancelled;
@@ -0,0 +1,167 @@
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::market::MarketStatus;
+use crate::models::order::OutcomeSide;
+
+/// Where the mark price of an open position comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MarkPriceSource {
+    /// Midpoint of the best bid and ask, falling back to the last trade
+    #[default]
+    Mid,
+    
+    /// Price of the last trade, falling back to the midpoint
+    LastTrade,
+}
+
+impl FromStr for MarkPriceSource {
+    type Err = String;
+    
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Mid" | "mid" => Ok(MarkPriceSource::Mid),
+            "LastTrade" | "last_trade" | "last" => Ok(MarkPriceSource::LastTrade),
+            _ => Err(format!("Unknown mark price source: {}", value)),
+        }
+    }
+}
+
+/// A position valued at its mark price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionValuation {
+    /// The outcome held
+    pub outcome: OutcomeSide,
+    
+    /// Net shares held (negative means short)
+    pub quantity: i64,
+    
+    /// Average price paid per share held long, or received per share held short
+    pub average_entry_price: Decimal,
+    
+    /// Price the position is marked at, if the market has one
+    pub mark_price: Option<Decimal>,
+    
+    /// Value of the shares at the mark price (negative for a short position)
+    pub market_value: Decimal,
+    
+    /// Profit or loss if the position were closed at the mark price
+    pub unrealized_pnl: Decimal,
+    
+    /// Profit or loss already realized
+    pub realized_pnl: Decimal,
+}
+
+impl PositionValuation {
+    /// Values a position at a mark price, or at its entry price if there is no mark
+    pub fn new(
+        outcome: OutcomeSide,
+        quantity: i64,
+        average_entry_price: Decimal,
+        realized_pnl: Decimal,
+        mark_price: Option<Decimal>,
+    ) -> Self {
+        let shares = Decimal::from(quantity);
+        let price = mark_price.unwrap_or(average_entry_price);
+        
+        Self {
+            outcome,
+            quantity,
+            average_entry_price,
+            mark_price,
+            market_value: price * shares,
+            unrealized_pnl: (price - average_entry_price) * shares,
+            realized_pnl,
+        }
+    }
+}
+
+/// A user's holdings and reserved funds in one market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketPortfolio {
+    /// The market
+    pub market_id: String,
+    
+    /// Current status of the market
+    pub status: MarketStatus,
+    
+    /// Valued positions, one per outcome the user has traded
+    pub positions: Vec<PositionValuation>,
+    
+    /// Funds reserved for the user's resting orders, short positions and market maker subsidy
+    pub reserved_collateral: Decimal,
+    
+    /// Combined value of the positions
+    pub market_value: Decimal,
+    
+    /// Combined unrealized profit or loss
+    pub unrealized_pnl: Decimal,
+    
+    /// Combined realized profit or loss
+    pub realized_pnl: Decimal,
+}
+
+/// Account-wide totals of a portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioTotals {
+    /// Funds free to trade
+    pub available_balance: Decimal,
+    
+    /// Part of the available balance that was deposited
+    pub cash_balance: Decimal,
+    
+    /// Part of the available balance won from trading and settlement
+    pub winnings_balance: Decimal,
+    
+    /// Part of the available balance that is promotional credit
+    pub promo_balance: Decimal,
+    
+    /// Part of the available balance that can be withdrawn (cash and winnings)
+    pub withdrawable_balance: Decimal,
+    
+    /// Funds reserved across all markets
+    pub reserved_balance: Decimal,
+    
+    /// `available_balance + reserved_balance`, as in `UserBalance::total_balance`
+    pub total_balance: Decimal,
+    
+    /// Reserved funds attributed to a market
+    pub reserved_in_markets: Decimal,
+    
+    /// Reserved funds that could not be attributed to any market (zero when the books reconcile)
+    pub reserved_unallocated: Decimal,
+    
+    /// Combined value of all positions
+    pub market_value: Decimal,
+    
+    /// `total_balance + market_value`
+    pub equity: Decimal,
+    
+    /// Combined unrealized profit or loss
+    pub unrealized_pnl: Decimal,
+    
+    /// Combined realized profit or loss
+    pub realized_pnl: Decimal,
+}
+
+/// A user's portfolio marked to market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portfolio {
+    /// The user
+    pub user_id: Uuid,
+    
+    /// Where mark prices came from
+    pub mark_source: MarkPriceSource,
+    
+    /// Holdings per market
+    pub markets: Vec<MarketPortfolio>,
+    
+    /// Account totals
+    pub totals: PortfolioTotals,
+    
+    /// When the portfolio was valued
+    pub valued_at: DateTime<Utc>,
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::order::{OrderSide, OutcomeSide};
+
+/// A user's holding of one outcome in a market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    /// Unique identifier for this position
+    pub id: Uuid,
+    
+    /// The user holding the position
+    pub user_id: Uuid,
+    
+    /// The market the position is in
+    pub market_id: String,
+    
+    /// The outcome held
+    pub market_option_id: OutcomeSide,
+    
+    /// Net shares held (negative means short)
+    pub quantity: i64,
+    
+    /// Average price paid per share held long, or received per share held short
+    pub average_entry_price: Decimal,
+    
+    /// Profit or loss realized by reducing or settling the position
+    pub realized_pnl: Decimal,
+    
+    /// When the position was opened
+    pub created_at: DateTime<Utc>,
+    
+    /// When the position was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Position {
+    /// Creates an empty position
+    pub fn new(user_id: Uuid, market_id: String, outcome: OutcomeSide) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            market_id,
+            market_option_id: outcome,
+            quantity: 0,
+            average_entry_price: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    /// Checks if the position holds no shares
+    pub fn is_flat(&self) -> bool {
+        self.quantity == 0
+    }
+    
+    /// Applies one side of a trade to the position
+    ///
+    /// Trading in the direction of the position moves the average entry price;
+    /// trading against it realizes PnL on the shares closed at the average entry
+    /// price. Any excess opens a position the other way at the trade price.
+    pub fn apply_trade(&mut self, side: OrderSide, price: Decimal, quantity: u32) {
+        let delta = match side {
+            OrderSide::Buy => quantity as i64,
+            OrderSide::Sell => -(quantity as i64),
+        };
+        
+        if self.quantity == 0 || self.quantity.signum() == delta.signum() {
+            let held = Decimal::from(self.quantity.abs());
+            let added = Decimal::from(quantity);
+            self.average_entry_price = (self.average_entry_price * held + price * added) / (held + added);
+        } else {
+            let closed = self.quantity.abs().min(delta.abs());
+            let closed_pnl = (price - self.average_entry_price) * Decimal::from(closed);
+            if self.quantity > 0 {
+                self.realized_pnl += closed_pnl;
+            } else {
+                self.realized_pnl -= closed_pnl;
+            }
+            
+            if delta.abs() > self.quantity.abs() {
+                self.average_entry_price = price;
+            } else if delta.abs() == self.quantity.abs() {
+                self.average_entry_price = Decimal::ZERO;
+            }
+        }
+        
+        self.quantity += delta;
+        self.updated_at = Utc::now();
+    }
+    
+    /// Closes the position at the amount each share pays out on resolution
+    pub fn settle(&mut self, payout_per_share: Decimal) {
+        self.realized_pnl += (payout_per_share - self.average_entry_price) * Decimal::from(self.quantity);
+        self.quantity = 0;
+        self.average_entry_price = Decimal::ZERO;
+        self.updated_at = Utc::now();
+    }
+    
+    /// Closes the position as if it had never been traded, for a cancelled market
+    pub fn void(&mut self) {
+        self.quantity = 0;
+        self.average_entry_price = Decimal::ZERO;
+        self.realized_pnl = Decimal::ZERO;
+        self.updated_at = Utc::now();
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Promotional credit granted to a user, such as a joining bonus
+///
+/// The credit can be traded but not withdrawn, and whatever of it is left
+/// unspent is removed when it expires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromoCredit {
+    /// Unique ID for the grant
+    pub credit_id: Uuid,
+    
+    /// User the credit was granted to
+    pub user_id: Uuid,
+    
+    /// Amount granted
+    pub amount: Decimal,
+    
+    /// Why the credit was granted (e.g. "Joining bonus")
+    pub reason: String,
+    
+    /// When the credit was granted
+    pub granted_at: DateTime<Utc>,
+    
+    /// When whatever is left of the credit is removed
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PromoCredit {
+    /// Creates a new grant of promotional credit
+    pub fn new(user_id: Uuid, amount: Decimal, reason: String, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            credit_id: Uuid::new_v4(),
+            user_id,
+            amount,
+            reason,
+            granted_at: Utc::now(),
+            expires_at,
+        }
+    }
+    
+    /// Checks if the credit has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::order::OutcomeSide;
+
+/// What a reconciliation discrepancy was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DiscrepancyKind {
+    /// Reserved balance differs from what the user's orders, short sales and market maker pools hold
+    ReservedBalance,
+    
+    /// Available balance differs from the replayed balance transactions
+    AvailableBalance,
+    
+    /// Position differs from the replayed trades
+    Position,
+}
+
+/// A value that differs from what rebuilding it from its history gives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discrepancy {
+    /// What differs
+    pub kind: DiscrepancyKind,
+    
+    /// User whose records differ
+    pub user_id: Uuid,
+    
+    /// Market of the position, for position discrepancies
+    pub market_id: Option<String>,
+    
+    /// Outcome of the position, for position discrepancies
+    pub outcome: Option<OutcomeSide>,
+    
+    /// Value rebuilt from history
+    pub expected: Decimal,
+    
+    /// Value on record
+    pub actual: Decimal,
+}
+
+impl Discrepancy {
+    /// Creates a balance discrepancy
+    pub fn balance(kind: DiscrepancyKind, user_id: Uuid, expected: Decimal, actual: Decimal) -> Self {
+        Self {
+            kind,
+            user_id,
+            market_id: None,
+            outcome: None,
+            expected,
+            actual,
+        }
+    }
+    
+    /// Creates a position discrepancy
+    pub fn position(user_id: Uuid, market_id: String, outcome: OutcomeSide, expected: i64, actual: i64) -> Self {
+        Self {
+            kind: DiscrepancyKind::Position,
+            user_id,
+            market_id: Some(market_id),
+            outcome: Some(outcome),
+            expected: Decimal::from(expected),
+            actual: Decimal::from(actual),
+        }
+    }
+}
+
+/// Result of one reconciliation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Unique ID for the run
+    pub run_id: Uuid,
+    
+    /// Whether the run was started on demand rather than by the schedule
+    pub on_demand: bool,
+    
+    /// Number of users checked
+    pub users_checked: usize,
+    
+    /// Discrepancies found; empty when everything reconciles
+    pub discrepancies: Vec<Discrepancy>,
+    
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    
+    /// When the run finished
+    pub finished_at: DateTime<Utc>,
+}
+
+impl ReconciliationReport {
+    /// Checks if everything reconciled
+    pub fn is_reconciled(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+    
+    /// Counts the discrepancies of each kind
+    pub fn counts_by_kind(&self) -> BTreeMap<DiscrepancyKind, usize> {
+        let mut counts = BTreeMap::new();
+        for discrepancy in &self.discrepancies {
+            *counts.entry(discrepancy.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// Counters describing reconciliation runs since the engine started
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconciliationMetrics {
+    /// Runs that finished
+    pub runs_completed: u64,
+    
+    /// Runs that failed before finishing
+    pub runs_failed: u64,
+    
+    /// Discrepancies found across all finished runs
+    pub discrepancies_found: u64,
+    
+    /// Users checked by the last finished run
+    pub last_users_checked: usize,
+    
+    /// Discrepancies of each kind found by the last finished run
+    pub last_discrepancies: BTreeMap<DiscrepancyKind, usize>,
+    
+    /// How long the last finished run took, in milliseconds
+    pub last_duration_ms: i64,
+    
+    /// When the last run finished
+    pub last_run_at: Option<DateTime<Utc>>,
+    
+    /// Error from the last failed run
+    pub last_error: Option<String>,
+}
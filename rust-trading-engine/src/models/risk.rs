@@ -0,0 +1,84 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Limits an order must stay within before it reaches the book
+///
+/// A limit left unset does not apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskLimits {
+    /// Most orders a user can have resting across all markets
+    pub max_open_orders: Option<u32>,
+    
+    /// Most shares a user can hold, long or short, in one outcome of a market
+    pub max_position: Option<i64>,
+    
+    /// Most a user can have committed to one market: positions at their entry price plus open orders
+    pub max_market_notional: Option<Decimal>,
+    
+    /// Most a user can have committed across all markets
+    pub max_total_exposure: Option<Decimal>,
+    
+    /// Most a single order can be worth (price times quantity)
+    pub max_order_value: Option<Decimal>,
+}
+
+impl RiskLimits {
+    /// Combines two sets of limits, keeping the tighter of each
+    pub fn tightest(&self, other: &RiskLimits) -> RiskLimits {
+        fn min<T: PartialOrd + Copy>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(if b < a { b } else { a }),
+                (a, b) => a.or(b),
+            }
+        }
+        
+        RiskLimits {
+            max_open_orders: min(self.max_open_orders, other.max_open_orders),
+            max_position: min(self.max_position, other.max_position),
+            max_market_notional: min(self.max_market_notional, other.max_market_notional),
+            max_total_exposure: min(self.max_total_exposure, other.max_total_exposure),
+            max_order_value: min(self.max_order_value, other.max_order_value),
+        }
+    }
+}
+
+/// A named set of limits that users are assigned to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskTier {
+    /// Name of the tier (e.g. "standard")
+    pub name: String,
+    
+    /// Limits for users in the tier
+    pub limits: RiskLimits,
+}
+
+impl RiskTier {
+    /// Tier users are in until they are assigned another
+    pub const DEFAULT: &'static str = "standard";
+}
+
+/// Why the risk checks turned an order away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "reason")]
+pub enum RiskRejection {
+    /// The user already has as many resting orders as they may
+    #[error("Risk limit: user {user_id} has {open_orders} open orders, the limit is {limit}")]
+    MaxOpenOrders { user_id: Uuid, open_orders: u32, limit: u32 },
+    
+    /// The order could take the user's position in the outcome past the limit
+    #[error("Risk limit: order could take the position in market {market_id} to {position} shares, the limit is {limit}")]
+    MaxPosition { market_id: String, position: i64, limit: i64 },
+    
+    /// The order would commit more to the market than allowed
+    #[error("Risk limit: order would commit {notional} to market {market_id}, the limit is {limit}")]
+    MaxMarketNotional { market_id: String, notional: Decimal, limit: Decimal },
+    
+    /// The order would commit more across all markets than allowed
+    #[error("Risk limit: order would take total exposure to {exposure}, the limit is {limit}")]
+    MaxTotalExposure { exposure: Decimal, limit: Decimal },
+    
+    /// The order is worth more than a single order may be
+    #[error("Risk limit: order value {value} exceeds the limit of {limit}")]
+    MaxOrderValue { value: Decimal, limit: Decimal },
+}
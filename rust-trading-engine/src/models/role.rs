@@ -0,0 +1,94 @@
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What a user is allowed to do through the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Role {
+    /// Trades and manages their own funds; every user is a trader
+    Trader,
+    
+    /// Runs bots and automated market makers
+    MarketMaker,
+    
+    /// Lists and runs markets and events, reviews withdrawals and checks the books
+    Operator,
+    
+    /// Resolves markets
+    Resolver,
+    
+    /// Does anything, including managing API keys and roles
+    Admin,
+}
+
+impl Role {
+    /// Checks if holding this role allows what `role` does
+    pub fn grants(&self, role: Role) -> bool {
+        *self == role || *self == Role::Admin || role == Role::Trader
+    }
+}
+
+impl From<i32> for Role {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Role::Trader,
+            1 => Role::MarketMaker,
+            2 => Role::Operator,
+            3 => Role::Resolver,
+            4 => Role::Admin,
+            _ => panic!("Invalid Role value: {}", value),
+        }
+    }
+}
+
+impl From<Role> for i32 {
+    fn from(value: Role) -> Self {
+        match value {
+            Role::Trader => 0,
+            Role::MarketMaker => 1,
+            Role::Operator => 2,
+            Role::Resolver => 3,
+            Role::Admin => 4,
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+    
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Trader" | "trader" => Ok(Role::Trader),
+            "MarketMaker" | "market_maker" | "market-maker" => Ok(Role::MarketMaker),
+            "Operator" | "operator" => Ok(Role::Operator),
+            "Resolver" | "resolver" => Ok(Role::Resolver),
+            "Admin" | "admin" => Ok(Role::Admin),
+            _ => Err(format!("Unknown role: {}", value)),
+        }
+    }
+}
+
+/// A role granted to a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleGrant {
+    /// User holding the role
+    pub user_id: Uuid,
+    
+    /// Role granted
+    pub role: Role,
+    
+    /// When the role was granted
+    pub granted_at: DateTime<Utc>,
+}
+
+impl RoleGrant {
+    /// Grants a user a role from now
+    pub fn new(user_id: Uuid, role: Role) -> Self {
+        Self {
+            user_id,
+            role,
+            granted_at: Utc::now(),
+        }
+    }
+}
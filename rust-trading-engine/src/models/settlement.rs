@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::order::OutcomeSide;
+use crate::models::tax::TaxWithholding;
+
+/// One user's result from settling a market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementEntry {
+    /// The user being settled
+    pub user_id: Uuid,
+    
+    /// Net shares held per outcome, indexed by outcome (negative means short)
+    pub positions: Vec<i64>,
+    
+    /// Net cash paid into the market through trades (negative if the user received more than they paid)
+    pub cost_basis: Decimal,
+    
+    /// Value of the positions at resolution (negative when a short position pays out)
+    pub payout: Decimal,
+    
+    /// Profit or loss realized by settlement (`payout - cost_basis`)
+    pub realized_pnl: Decimal,
+}
+
+impl SettlementEntry {
+    /// Creates an entry for a user with no position
+    pub fn new(user_id: Uuid, num_outcomes: usize) -> Self {
+        Self {
+            user_id,
+            positions: vec![0; num_outcomes],
+            cost_basis: Decimal::ZERO,
+            payout: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+        }
+    }
+    
+    /// Gets the net position in an outcome
+    pub fn position(&self, outcome: OutcomeSide) -> i64 {
+        self.positions.get(outcome.index()).copied().unwrap_or(0)
+    }
+}
+
+/// One user's settlement in a market, saved in the same transaction as the funds it moves
+///
+/// A user with a saved settlement entry has been settled, so settling the market
+/// again after a failure carries on with the users it has not reached yet.
+#[derive(Debug, Clone)]
+pub struct PositionSettlement {
+    /// The market being settled
+    pub market_id: String,
+    
+    /// The user's result
+    pub entry: SettlementEntry,
+    
+    /// Tax withheld from the payout, if the user is taxed on it
+    pub withholding: Option<TaxWithholding>,
+    
+    /// When the market was settled
+    pub settled_at: DateTime<Utc>,
+}
+
+impl PositionSettlement {
+    /// Creates an untaxed settlement of a user's entry
+    pub fn new(market_id: String, entry: SettlementEntry, settled_at: DateTime<Utc>) -> Self {
+        Self {
+            market_id,
+            entry,
+            withholding: None,
+            settled_at,
+        }
+    }
+    
+    /// Gets the tax withheld from the payout
+    pub fn tax(&self) -> Decimal {
+        self.withholding.as_ref().map(|withholding| withholding.amount).unwrap_or(Decimal::ZERO)
+    }
+}
+
+/// Report of how a resolved market was settled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementReport {
+    /// The settled market
+    pub market_id: String,
+    
+    /// The winning outcome (categorical markets)
+    pub resolution: Option<OutcomeSide>,
+    
+    /// The value the market resolved to (scalar markets)
+    pub resolved_value: Option<Decimal>,
+    
+    /// Per-user settlement results
+    pub entries: Vec<SettlementEntry>,
+    
+    /// When the market was settled
+    pub settled_at: DateTime<Utc>,
+}
+
+impl SettlementReport {
+    /// Gets the total amount paid out to holders of winning shares
+    pub fn total_payout(&self) -> Decimal {
+        self.entries.iter()
+            .map(|entry| entry.payout.max(Decimal::ZERO))
+            .sum()
+    }
+    
+    /// Gets the total amount collected from holders of short positions
+    pub fn total_collected(&self) -> Decimal {
+        self.entries.iter()
+            .map(|entry| (-entry.payout).max(Decimal::ZERO))
+            .sum()
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Gets the Indian financial year a moment falls in, as the year it starts in
+///
+/// Financial years run from 1 April to 31 March in Indian Standard Time, so
+/// 2025 is the year from 1 April 2025 to 31 March 2026.
+pub fn financial_year(at: DateTime<Utc>) -> i32 {
+    let ist = FixedOffset::east_opt(5 * 3600 + 30 * 60).expect("IST is a valid offset");
+    let local = at.with_timezone(&ist);
+    if local.month() >= 4 {
+        local.year()
+    } else {
+        local.year() - 1
+    }
+}
+
+/// Tax deducted at source (TDS) from one settlement payout
+///
+/// Every settlement that wins or loses the user money is recorded, so that
+/// losses offset winnings in the same financial year. What is withheld brings
+/// the tax on the year's net winnings so far up to the configured rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxWithholding {
+    /// Unique ID for the withholding
+    pub withholding_id: Uuid,
+    
+    /// User the tax was withheld from
+    pub user_id: Uuid,
+    
+    /// Market whose settlement paid the winnings
+    pub market_id: String,
+    
+    /// Financial year the settlement falls in, as the year it starts in
+    pub financial_year: i32,
+    
+    /// What the settlement won (negative when it lost): the payout less what the user paid in
+    pub net_winnings: Decimal,
+    
+    /// Net winnings in the financial year up to and including this settlement
+    pub taxable_winnings: Decimal,
+    
+    /// Rate of tax applied
+    pub rate: Decimal,
+    
+    /// Tax withheld from the payout
+    pub amount: Decimal,
+    
+    /// When the tax was withheld
+    pub created_at: DateTime<Utc>,
+}
+
+impl TaxWithholding {
+    /// Creates a new withholding for a settlement
+    pub fn new(
+        user_id: Uuid,
+        market_id: String,
+        net_winnings: Decimal,
+        taxable_winnings: Decimal,
+        rate: Decimal,
+        amount: Decimal,
+    ) -> Self {
+        let created_at = Utc::now();
+        Self {
+            withholding_id: Uuid::new_v4(),
+            user_id,
+            market_id,
+            financial_year: financial_year(created_at),
+            net_winnings,
+            taxable_winnings,
+            rate,
+            amount,
+            created_at,
+        }
+    }
+}
+
+/// A user's winnings and the tax withheld from them in one financial year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxStatement {
+    /// The user
+    pub user_id: Uuid,
+    
+    /// Financial year, as the year it starts in
+    pub financial_year: i32,
+    
+    /// Financial year as it is usually written (e.g. "2025-26")
+    pub period: String,
+    
+    /// Winnings from settlements that won money
+    pub gross_winnings: Decimal,
+    
+    /// Losses from settlements that lost money
+    pub losses: Decimal,
+    
+    /// `gross_winnings - losses`
+    pub net_winnings: Decimal,
+    
+    /// Tax withheld over the year
+    pub tax_withheld: Decimal,
+    
+    /// Every settlement in the year, oldest first
+    pub withholdings: Vec<TaxWithholding>,
+}
+
+impl TaxStatement {
+    /// Builds a user's statement for a financial year from its withholdings
+    pub fn new(user_id: Uuid, financial_year: i32, withholdings: Vec<TaxWithholding>) -> Self {
+        let gross_winnings: Decimal = withholdings.iter().map(|w| w.net_winnings.max(Decimal::ZERO)).sum();
+        let losses: Decimal = withholdings.iter().map(|w| (-w.net_winnings).max(Decimal::ZERO)).sum();
+        Self {
+            user_id,
+            financial_year,
+            period: format!("{}-{:02}", financial_year, (financial_year + 1) % 100),
+            gross_winnings,
+            losses,
+            net_winnings: gross_winnings - losses,
+            tax_withheld: withholdings.iter().map(|w| w.amount).sum(),
+            withholdings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    #[test]
+    fn financial_years_start_on_the_first_of_april_in_india() {
+        // 31 March 2026 19:00 UTC is already 1 April 00:30 in India
+        assert_eq!(financial_year(Utc.with_ymd_and_hms(2026, 3, 31, 18, 0, 0).unwrap()), 2025);
+        assert_eq!(financial_year(Utc.with_ymd_and_hms(2026, 3, 31, 19, 0, 0).unwrap()), 2026);
+    }
+    
+    #[test]
+    fn statements_net_losses_against_winnings() {
+        let withholding = |net_winnings: i64, amount: i64| {
+            TaxWithholding::new(Uuid::nil(), "market".to_string(), Decimal::from(net_winnings), Decimal::ZERO, Decimal::ZERO, Decimal::from(amount))
+        };
+        let statement = TaxStatement::new(Uuid::nil(), 2025, vec![withholding(100, 30), withholding(-40, 0), withholding(20, 0)]);
+        
+        assert_eq!(statement.period, "2025-26");
+        assert_eq!(statement.gross_winnings, Decimal::from(120));
+        assert_eq!(statement.losses, Decimal::from(40));
+        assert_eq!(statement.net_winnings, Decimal::from(80));
+        assert_eq!(statement.tax_withheld, Decimal::from(30));
+    }
+}
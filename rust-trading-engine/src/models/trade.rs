@@ -26,7 +26,7 @@ pub struct Trade {
     /// User ID of the seller
     pub seller_id: Uuid,
     
-    /// Whether this trade is for the Yes or No outcome
+    /// The outcome this trade is for
     pub outcome: OutcomeSide,
     
     /// The execution price of the trade
@@ -41,6 +41,7 @@ pub struct Trade {
 
 impl Trade {
     /// Creates a new trade from two matched orders
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         market_id: String,
         buy_order_id: Uuid,
@@ -81,28 +82,17 @@ impl Trade {
         }
     }
     
-    /// Calculate the profit or loss for a user when the market is resolved
-    pub fn calculate_payout(&self, winner: OutcomeSide) -> (Uuid, Decimal, Uuid, Decimal) {
+    /// Calculates who is paid out for this trade when the market resolves
+    ///
+    /// The buyer holds the traded outcome and is paid the full quantity if it
+    /// wins; otherwise the seller's collateral is returned to the seller.
+    pub fn calculate_payout(&self, winner: OutcomeSide) -> (Uuid, Decimal) {
         let base = Decimal::from(self.quantity);
         
-        // For the yes side (if yes wins, they get full payout, otherwise zero)
-        let yes_user_id = match self.outcome {
-            OutcomeSide::Yes => self.buyer_id,
-            OutcomeSide::No => self.seller_id,
-        };
-        
-        // For the no side (if no wins, they get full payout, otherwise zero)
-        let no_user_id = match self.outcome {
-            OutcomeSide::Yes => self.seller_id,
-            OutcomeSide::No => self.buyer_id,
-        };
-        
-        // Calculate payouts based on the winner
-        let (yes_payout, no_payout) = match winner {
-            OutcomeSide::Yes => (base, Decimal::ZERO),
-            OutcomeSide::No => (Decimal::ZERO, base),
-        };
-        
-        (yes_user_id, yes_payout, no_user_id, no_payout)
+        if self.outcome == winner {
+            (self.buyer_id, base)
+        } else {
+            (self.seller_id, base)
+        }
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Where a withdrawal request is in its review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WithdrawalStatus {
+    /// Funds are held while the request waits for review
+    Pending,
+    
+    /// Approved and paid out
+    Approved,
+    
+    /// Rejected, with the held funds returned to the user
+    Rejected,
+}
+
+impl From<i32> for WithdrawalStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => WithdrawalStatus::Pending,
+            1 => WithdrawalStatus::Approved,
+            2 => WithdrawalStatus::Rejected,
+            _ => panic!("Invalid WithdrawalStatus value: {}", value),
+        }
+    }
+}
+
+impl From<WithdrawalStatus> for i32 {
+    fn from(value: WithdrawalStatus) -> Self {
+        match value {
+            WithdrawalStatus::Pending => 0,
+            WithdrawalStatus::Approved => 1,
+            WithdrawalStatus::Rejected => 2,
+        }
+    }
+}
+
+/// A user's request to take funds off the platform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Withdrawal {
+    /// Unique ID for the withdrawal
+    pub withdrawal_id: Uuid,
+    
+    /// User withdrawing
+    pub user_id: Uuid,
+    
+    /// Amount to pay out
+    pub amount: Decimal,
+    
+    /// Where the payout provider sends the funds (e.g. a bank account or UPI ID)
+    pub destination: String,
+    
+    /// Where the request is in its review
+    pub status: WithdrawalStatus,
+    
+    /// The payout provider's reference for the payout, once approved
+    pub payout_reference: Option<String>,
+    
+    /// Note left by the reviewer, such as why the request was rejected
+    pub review_note: Option<String>,
+    
+    /// When the request was made
+    pub created_at: DateTime<Utc>,
+    
+    /// When the request was approved or rejected
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl Withdrawal {
+    /// Creates a new pending withdrawal request
+    pub fn new(user_id: Uuid, amount: Decimal, destination: String) -> Self {
+        Self {
+            withdrawal_id: Uuid::new_v4(),
+            user_id,
+            amount,
+            destination,
+            status: WithdrawalStatus::Pending,
+            payout_reference: None,
+            review_note: None,
+            created_at: Utc::now(),
+            reviewed_at: None,
+        }
+    }
+    
+    /// Checks if the request is still waiting for review
+    pub fn is_pending(&self) -> bool {
+        self.status == WithdrawalStatus::Pending
+    }
+    
+    /// Marks the request approved and paid out under the provider's reference
+    pub fn approve(&mut self, payout_reference: String, review_note: Option<String>) {
+        self.status = WithdrawalStatus::Approved;
+        self.payout_reference = Some(payout_reference);
+        self.review_note = review_note;
+        self.reviewed_at = Some(Utc::now());
+    }
+    
+    /// Marks the request rejected
+    pub fn reject(&mut self, review_note: Option<String>) {
+        self.status = WithdrawalStatus::Rejected;
+        self.review_note = review_note;
+        self.reviewed_at = Some(Utc::now());
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use log::info;
+use rust_decimal::Decimal;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+
+use crate::models::{AmmQuote, LmsrMarketMaker, Order, OrderSide, OutcomeSide};
+use crate::services::balance_service::BalanceService;
+use crate::db::connection::Repository;
+
+/// Snapshot of a market maker's state with derived risk figures
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketMakerSummary {
+    /// The market maker state
+    pub market_maker: LmsrMarketMaker,
+
+    /// Current marginal price per outcome
+    pub prices: Vec<Decimal>,
+
+    /// Loss at current inventory if the worst outcome wins
+    pub worst_case_loss: Decimal,
+
+    /// Maximum possible loss, covered by the subsidy
+    pub max_loss: Decimal,
+}
+
+/// Service for running LMSR automated market makers
+pub struct AmmService<R: Repository> {
+    /// Database repository
+    repository: Arc<R>,
+
+    /// Balance service for funding subsidies
+    balance_service: Arc<BalanceService<R>>,
+
+    /// Market makers by market ID
+    market_makers: Arc<RwLock<HashMap<String, LmsrMarketMaker>>>,
+}
+
+impl<R: Repository> AmmService<R> {
+    /// Creates a new AMM service
+    pub fn new(repository: Arc<R>, balance_service: Arc<BalanceService<R>>) -> Self {
+        Self {
+            repository,
+            balance_service,
+            market_makers: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Creates a market maker for a market, reserving its subsidy from the house account
+    pub async fn create_market_maker(
+        &self,
+        market_id: &str,
+        num_outcomes: usize,
+        liquidity: Decimal,
+        house_account_id: Uuid,
+    ) -> Result<LmsrMarketMaker> {
+        if liquidity <= Decimal::ZERO {
+            return Err(anyhow!("Liquidity parameter must be positive"));
+        }
+
+        if self.get_market_maker(market_id).await?.is_some() {
+            return Err(anyhow!("Market {} already has a market maker", market_id));
+        }
+
+        let market_maker = LmsrMarketMaker::new(
+            market_id.to_string(),
+            house_account_id,
+            liquidity,
+            num_outcomes,
+        );
+
+        // Fund the worst-case loss from the house account
+        self.balance_service.reserve_amm_subsidy(
+            house_account_id,
+            market_maker.subsidy,
+            market_id
+        ).await?;
+
+        self.repository.save_market_maker(&market_maker).await?;
+
+        let mut market_makers = self.market_makers.write().await;
+        market_makers.insert(market_id.to_string(), market_maker.clone());
+
+        info!("Created market maker for market {} with liquidity {} and subsidy {}",
+            market_id, liquidity, market_maker.subsidy);
+        Ok(market_maker)
+    }
+
+    /// Gets the market maker for a market, if it has one
+    pub async fn get_market_maker(&self, market_id: &str) -> Result<Option<LmsrMarketMaker>> {
+        // Try to get from cache first
+        {
+            let market_makers = self.market_makers.read().await;
+            if let Some(market_maker) = market_makers.get(market_id) {
+                return Ok(Some(market_maker.clone()));
+            }
+        }
+
+        // If not in cache, get from repository
+        let market_maker = self.repository.get_market_maker(market_id).await?;
+        if let Some(market_maker) = &market_maker {
+            let mut market_makers = self.market_makers.write().await;
+            market_makers.insert(market_id.to_string(), market_maker.clone());
+        }
+
+        Ok(market_maker)
+    }
+
+    /// Gets a summary of a market maker's state and risk
+    pub async fn get_summary(&self, market_id: &str) -> Result<MarketMakerSummary> {
+        let market_maker = self.get_market_maker(market_id).await?
+            .ok_or_else(|| anyhow!("Market {} has no market maker", market_id))?;
+
+        Ok(MarketMakerSummary {
+            prices: market_maker.prices(),
+            worst_case_loss: market_maker.worst_case_loss(),
+            max_loss: market_maker.max_loss(),
+            market_maker,
+        })
+    }
+
+    /// Quotes a trade of `quantity` shares against the market maker
+    pub async fn quote(
+        &self,
+        market_id: &str,
+        outcome: OutcomeSide,
+        side: OrderSide,
+        quantity: u32,
+    ) -> Result<AmmQuote> {
+        let market_maker = self.get_market_maker(market_id).await?
+            .ok_or_else(|| anyhow!("Market {} has no market maker", market_id))?;
+
+        let index = outcome.index();
+        Ok(match side {
+            OrderSide::Buy => market_maker.quote_buy(index, quantity),
+            OrderSide::Sell => market_maker.quote_sell(index, quantity),
+        })
+    }
+
+    /// Quotes the largest fill the market maker can give an order within its limit price
+    pub async fn quote_order(&self, order: &Order) -> Result<Option<AmmQuote>> {
+        let market_maker = match self.get_market_maker(&order.market_id).await? {
+            Some(market_maker) => market_maker,
+            None => return Ok(None),
+        };
+
+        let index = order.outcome.index();
+        let quote = match order.side {
+            OrderSide::Buy => {
+                let quantity = market_maker.max_buy_quantity(index, order.price, order.remaining_quantity);
+                market_maker.quote_buy(index, quantity)
+            }
+            OrderSide::Sell => {
+                let quantity = market_maker.max_sell_quantity(index, order.price, order.remaining_quantity);
+                market_maker.quote_sell(index, quantity)
+            }
+        };
+
+        if quote.quantity == 0 || quote.amount <= Decimal::ZERO {
+            return Ok(None);
+        }
+
+        Ok(Some(quote))
+    }
+
+    /// Applies a fill to the market maker's inventory
+    ///
+    /// The cache stays write-locked from reading the inventory until the new one is
+    /// saved, so fills applied at the same time cannot overwrite each other.
+    pub async fn apply_fill(
+        &self,
+        market_id: &str,
+        outcome: OutcomeSide,
+        side: OrderSide,
+        quote: &AmmQuote,
+    ) -> Result<LmsrMarketMaker> {
+        let mut market_makers = self.market_makers.write().await;
+        let mut market_maker = match market_makers.get(market_id) {
+            Some(market_maker) => market_maker.clone(),
+            None => self.repository.get_market_maker(market_id).await?
+                .ok_or_else(|| anyhow!("Market {} has no market maker", market_id))?,
+        };
+
+        let index = outcome.index();
+        match side {
+            OrderSide::Buy => market_maker.apply_buy(index, quote.quantity, quote.amount),
+            OrderSide::Sell => market_maker.apply_sell(index, quote.quantity, quote.amount),
+        }
+
+        self.repository.save_market_maker(&market_maker).await?;
+        market_makers.insert(market_id.to_string(), market_maker.clone());
+
+        Ok(market_maker)
+    }
+}
@@ -0,0 +1,241 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use log::{debug, error, info};
+use tokio::time;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::api::rate_limit::{RateBudget, RateKey, RateLimited, RateLimiter};
+use crate::models::api_key::{ApiKey, IssuedApiKey};
+use crate::models::role::{Role, RoleGrant};
+use crate::db::connection::Repository;
+
+/// How far a request's timestamp can be from the server's clock, in seconds
+pub const MAX_TIMESTAMP_SKEW_SECS: i64 = 30;
+
+/// Longest nonce a request can carry
+pub const MAX_NONCE_LEN: usize = 64;
+
+/// The headers a signed request carries and the parts of it they are signed over
+pub struct RequestSignature<'a> {
+    /// API key the request claims to be signed with
+    pub key_id: &'a str,
+    
+    /// Unix timestamp, in seconds, the request was signed at
+    pub timestamp: &'a str,
+    
+    /// Value the key can only sign once
+    pub nonce: &'a str,
+    
+    /// Hex HMAC-SHA256 of the request
+    pub signature: &'a str,
+    
+    /// The request's method
+    pub method: &'a str,
+    
+    /// The request's path, with any query string
+    pub path: &'a str,
+    
+    /// The request's body
+    pub body: &'a [u8],
+}
+
+/// Service issuing API keys, checking the signatures of requests made with them
+/// and managing the roles of the users they act for
+///
+/// A request is signed with the hex HMAC-SHA256, keyed with the key's secret, of
+/// its Unix timestamp in seconds, a nonce, its method and its path (with any query
+/// string), each followed by a newline, then its body. Requests with a timestamp
+/// more than `MAX_TIMESTAMP_SKEW_SECS` from the server's clock are refused, and
+/// so is any request whose nonce its key has already used, so a captured request
+/// cannot be replayed. Nonces are kept until their requests' timestamps would be
+/// refused anyway.
+pub struct AuthService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Rate limiter that signed requests are counted against for their key and user
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl<R: Repository + Send + Sync + 'static> AuthService<R> {
+    /// Creates a new auth service
+    pub fn new(repository: Arc<R>, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self { repository, rate_limiter }
+    }
+    
+    /// Issues a new API key to a user, returning it with its secret
+    pub async fn issue_api_key(&self, user_id: Uuid, label: Option<String>) -> Result<IssuedApiKey> {
+        let api_key = ApiKey::new(user_id, label);
+        self.repository.save_api_key(&api_key).await?;
+        
+        info!("Issued API key {} to user {}", api_key.key_id, user_id);
+        Ok(IssuedApiKey::new(api_key))
+    }
+    
+    /// Gets a user's API keys, newest first
+    pub async fn get_api_keys_for_user(&self, user_id: Uuid) -> Result<Vec<ApiKey>> {
+        self.repository.get_api_keys_for_user(user_id).await
+    }
+    
+    /// Revokes one of a user's API keys, so requests signed with it are refused
+    pub async fn revoke_api_key(&self, user_id: Uuid, key_id: &str) -> Result<ApiKey> {
+        let mut api_key = self.repository.get_api_key(key_id).await?
+            .filter(|api_key| api_key.user_id == user_id)
+            .ok_or_else(|| anyhow!("API key {} not found", key_id))?;
+        if !api_key.is_active() {
+            return Err(anyhow!("API key {} is already revoked", key_id));
+        }
+        
+        api_key.revoked_at = Some(Utc::now());
+        self.repository.save_api_key(&api_key).await?;
+        
+        info!("Revoked API key {} of user {}", key_id, user_id);
+        Ok(api_key)
+    }
+    
+    /// Checks a request's signature and uses up its nonce, returning the key it was signed with
+    pub async fn authenticate(&self, request: &RequestSignature<'_>) -> Result<ApiKey> {
+        let RequestSignature { key_id, timestamp, nonce, signature, method, path, body } = *request;
+        let sent_at = timestamp.trim().parse::<i64>()
+            .map_err(|_| anyhow!("Invalid request timestamp"))?;
+        if (Utc::now().timestamp() - sent_at).abs() > MAX_TIMESTAMP_SKEW_SECS {
+            return Err(anyhow!("Request timestamp is more than {} seconds from server time", MAX_TIMESTAMP_SKEW_SECS));
+        }
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_LEN || nonce.contains('\n') {
+            return Err(anyhow!("Request nonce must be 1 to {} characters on one line", MAX_NONCE_LEN));
+        }
+        
+        let api_key = self.repository.get_api_key(key_id).await?
+            .filter(|api_key| api_key.is_active())
+            .ok_or_else(|| anyhow!("Invalid API key"))?;
+        if !api_key.verify(timestamp, nonce, method, path, body, signature) {
+            return Err(anyhow!("Invalid request signature"));
+        }
+        
+        // Only a correctly signed request uses up its nonce, so nobody else can burn a key's nonces
+        if !self.repository.save_request_nonce(key_id, nonce, Utc::now()).await? {
+            return Err(anyhow!("Request nonce has already been used"));
+        }
+        
+        Ok(api_key)
+    }
+    
+    /// Counts an authenticated request against the budgets of the key it was signed with and the key's user
+    pub fn check_rate_limit(&self, budget: RateBudget, api_key: &ApiKey) -> std::result::Result<(), RateLimited> {
+        let keys = [RateKey::ApiKey(api_key.key_id.clone()), RateKey::User(api_key.user_id)];
+        self.rate_limiter.check(budget, &keys)
+    }
+    
+    /// Deletes the nonces of requests whose timestamps would now be refused
+    pub async fn prune_request_nonces(&self) -> Result<u64> {
+        // A nonce is saved at most `MAX_TIMESTAMP_SKEW_SECS` before its request's
+        // timestamp, which is accepted until `MAX_TIMESTAMP_SKEW_SECS` after it
+        let before = Utc::now() - chrono::Duration::seconds(2 * MAX_TIMESTAMP_SKEW_SECS);
+        let deleted = self.repository.delete_request_nonces_before(before).await?;
+        
+        debug!("Pruned {} request nonces", deleted);
+        Ok(deleted)
+    }
+    
+    /// Prunes request nonces every `interval` in the background
+    pub fn start_schedule(self: &Arc<Self>, interval: Duration) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.prune_request_nonces().await {
+                    error!("Failed to prune request nonces: {}", e);
+                }
+            }
+        });
+        
+        info!("Pruning request nonces every {} seconds", interval.as_secs());
+    }
+    
+    /// Gets the roles a user holds: trader, plus any they have been granted
+    pub async fn get_roles(&self, user_id: Uuid) -> Result<Vec<Role>> {
+        let mut roles = vec![Role::Trader];
+        for grant in self.repository.get_role_grants_for_user(user_id).await? {
+            if !roles.contains(&grant.role) {
+                roles.push(grant.role);
+            }
+        }
+        Ok(roles)
+    }
+    
+    /// Grants a user a role
+    pub async fn grant_role(&self, user_id: Uuid, role: Role) -> Result<RoleGrant> {
+        if role == Role::Trader {
+            return Err(anyhow!("Every user is a trader"));
+        }
+        
+        let grant = RoleGrant::new(user_id, role);
+        self.repository.save_role_grant(&grant).await?;
+        
+        info!("Granted {:?} role to user {}", role, user_id);
+        Ok(grant)
+    }
+    
+    /// Takes a role away from a user, returning the roles they still hold
+    pub async fn revoke_role(&self, user_id: Uuid, role: Role) -> Result<Vec<Role>> {
+        if role == Role::Trader {
+            return Err(anyhow!("Every user is a trader"));
+        }
+        if !self.repository.delete_role_grant(user_id, role).await? {
+            return Err(anyhow!("User {} does not hold the {:?} role", user_id, role));
+        }
+        
+        info!("Revoked {:?} role of user {}", role, user_id);
+        self.get_roles(user_id).await
+    }
+    
+    /// Makes a user the first admin, issuing them an API key whose ID and secret are written to `key_file`
+    ///
+    /// Lets the first admin in, since keys and roles can only be managed by admins,
+    /// and does nothing once anyone holds the admin role. The file is created for
+    /// the server's user to read only, and never overwritten. The role is granted
+    /// last, so if anything before it fails the next start tries again.
+    pub async fn bootstrap_admin(&self, user_id: Uuid, key_file: &Path) -> Result<Option<ApiKey>> {
+        if let Some(grant) = self.repository.get_role_grants(Role::Admin).await?.first() {
+            debug!("Not bootstrapping an admin, user {} already is one", grant.user_id);
+            return Ok(None);
+        }
+        
+        let mut file = owner_only_file(key_file)
+            .map_err(|e| anyhow!("Cannot create admin key file {}: {}", key_file.display(), e))?;
+        let written = async {
+            let issued = self.issue_api_key(user_id, Some("bootstrap".to_string())).await?;
+            let contents = serde_json::json!({ "key_id": issued.api_key.key_id, "secret": issued.secret });
+            writeln!(file, "{}", contents)?;
+            file.sync_all()?;
+            Ok::<_, anyhow::Error>(issued.api_key)
+        }.await;
+        let api_key = match written {
+            Ok(api_key) => api_key,
+            Err(e) => {
+                let _ = fs::remove_file(key_file);
+                return Err(anyhow!("Failed to write admin key file {}: {}", key_file.display(), e));
+            }
+        };
+        
+        self.repository.save_role_grant(&RoleGrant::new(user_id, Role::Admin)).await?;
+        
+        info!("Made user {} the first admin", user_id);
+        Ok(Some(api_key))
+    }
+}
+
+/// Creates a new file only its owner can read or write, failing if it exists
+fn owner_only_file(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
@@ -1,12 +1,23 @@
 use std::sync::Arc;
-use log::{debug, info};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use rand::Rng;
 use rust_decimal::Decimal;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
 
-use crate::models::balance::{UserBalance, BalanceTransaction, TransactionType};
+use crate::models::balance::{UserBalance, BalanceConflict, BalanceTransaction, BucketAmounts, TransactionType};
+use crate::models::ledger::{LedgerAccount, LedgerEntry};
+use crate::models::settlement::PositionSettlement;
 use crate::db::connection::Repository;
 
+/// Longest pause, in milliseconds, between attempts at a contended balance update
+const MAX_RETRY_DELAY_MS: u64 = 50;
+
+/// Attempts at a contended balance update between warnings that it is still retrying
+const RETRY_WARNING_INTERVAL: u32 = 50;
+
 /// Service for managing user balances
 pub struct BalanceService<R: Repository> {
     /// Repository for database operations
@@ -25,169 +36,766 @@ impl<R: Repository> BalanceService<R> {
         match self.repository.get_user_balance(user_id).await {
             Ok(balance) => Ok(balance),
             Err(_) => {
-                // If not found, create a new balance with zero
+                // If not found, create a new balance with zero; another request may create it first
                 let balance = UserBalance::new(user_id, Decimal::ZERO);
-                self.repository.save_user_balance(&balance).await?;
-                Ok(balance)
+                if let Err(e) = self.repository.save_user_balance(&balance).await {
+                    if !e.is::<BalanceConflict>() {
+                        return Err(e);
+                    }
+                }
+                self.repository.get_user_balance(user_id).await
+            }
+        }
+    }
+    
+    /// Applies a change to a user's balance and saves it with the transactions and ledger entries it returns
+    ///
+    /// The balance is saved only if no one else changed it since it was read. If
+    /// someone did, the change is applied again to a fresh read until it is saved,
+    /// so concurrent updates are never lost and funds checks always see the latest
+    /// balance. Every conflict means another update was saved, so contending updates
+    /// always make progress and each one gets through in turn.
+    async fn update_balance<F>(&self, user_id: Uuid, change: F) -> Result<UserBalance>
+    where
+        F: FnMut(&mut UserBalance) -> Result<(Vec<BalanceTransaction>, Vec<LedgerEntry>)>,
+    {
+        self.update_balance_for(user_id, None, change).await
+    }
+    
+    /// Applies a change to a user's balance like `update_balance`, saving the
+    /// settlement it pays, if any, in the same transaction
+    async fn update_balance_for<F>(
+        &self,
+        user_id: Uuid,
+        settlement: Option<&PositionSettlement>,
+        mut change: F,
+    ) -> Result<UserBalance>
+    where
+        F: FnMut(&mut UserBalance) -> Result<(Vec<BalanceTransaction>, Vec<LedgerEntry>)>,
+    {
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            let mut balance = self.get_user_balance(user_id).await?;
+            let (mut transactions, entries) = change(&mut balance)?;
+            for transaction in &mut transactions {
+                transaction.available_change = available_change(transaction, &entries);
+            }
+            
+            let result = match settlement {
+                Some(settlement) => self.repository.post_settlement(&balance, &transactions, &entries, settlement).await,
+                None => self.repository.post_ledger_entries(&[balance.clone()], &transactions, &entries).await,
+            };
+            match result {
+                Ok(()) => {
+                    balance.version += 1;
+                    return Ok(balance);
+                }
+                Err(e) if e.is::<BalanceConflict>() => {
+                    debug!("Balance of user {} changed during update, retrying (attempt {})", user_id, attempt);
+                    if attempt.is_multiple_of(RETRY_WARNING_INTERVAL) {
+                        warn!("Balance of user {} is heavily contended, still retrying after {} attempts", user_id, attempt);
+                    }
+                    
+                    // Back off a little so contending writers spread out
+                    let delay = rand::thread_rng().gen_range(0..=(attempt as u64 * 5).min(MAX_RETRY_DELAY_MS));
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                }
+                Err(e) => return Err(e),
             }
         }
     }
     
     /// Adds funds to a user's balance (deposit)
-    pub async fn add_funds(&self, user_id: Uuid, amount: Decimal) -> Result<UserBalance> {
+    ///
+    /// A deposit with a reference is credited only once; repeating it returns the
+    /// user's balance without crediting it again.
+    pub async fn add_funds(&self, user_id: Uuid, amount: Decimal, reference_id: Option<&str>) -> Result<UserBalance> {
         if amount <= Decimal::ZERO {
             return Err(anyhow!("Amount must be positive"));
         }
         
-        // Get the user's current balance
-        let mut balance = self.get_user_balance(user_id).await?;
+        if let Some(balance) = self.find_credited_deposit(user_id, amount, reference_id).await? {
+            return Ok(balance);
+        }
+        
+        let result = self.update_balance(user_id, |balance| {
+            // Add the funds
+            balance.add_funds(amount);
+            
+            // Record the transaction and move the money in the ledger
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::Deposit,
+                reference_id.map(str::to_string),
+                "Deposit".to_string(),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::external(), LedgerAccount::user_available(user_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await;
         
-        // Add the funds
-        balance.add_funds(amount);
+        let balance = match result {
+            Ok(balance) => balance,
+            Err(e) => {
+                // A concurrent repeat of the deposit may have been credited first
+                return match self.find_credited_deposit(user_id, amount, reference_id).await? {
+                    Some(balance) => Ok(balance),
+                    None => Err(e),
+                };
+            }
+        };
         
-        // Save the updated balance
-        self.repository.save_user_balance(&balance).await?;
+        info!("Added {} to user {}'s balance", amount, user_id);
+        Ok(balance)
+    }
+    
+    /// Gets the user's balance if a deposit with this reference has already been credited
+    async fn find_credited_deposit(&self, user_id: Uuid, amount: Decimal, reference_id: Option<&str>) -> Result<Option<UserBalance>> {
+        let Some(reference_id) = reference_id else {
+            return Ok(None);
+        };
+        let Some(deposit) = self.repository.find_balance_transaction(user_id, TransactionType::Deposit, reference_id).await? else {
+            return Ok(None);
+        };
         
-        // Record the transaction
-        let transaction = BalanceTransaction::new(
-            user_id, 
+        if deposit.amount != amount {
+            return Err(anyhow!(
+                "Deposit {} was already credited to user {} for {}, not {}",
+                reference_id, user_id, deposit.amount, amount
+            ));
+        }
+        
+        info!("Deposit {} was already credited to user {}", reference_id, user_id);
+        Ok(Some(self.get_user_balance(user_id).await?))
+    }
+    
+    /// Holds funds from a user's withdrawable balance for a withdrawal until it is reviewed
+    pub async fn hold_withdrawal(&self, user_id: Uuid, amount: Decimal, withdrawal_id: Uuid) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
+        
+        let balance = self.update_balance(user_id, |balance| {
+            // Try to take the funds out of the withdrawable buckets
+            let taken = balance.withdraw_withdrawable_funds(amount).map_err(|e| anyhow!(e))?;
+            
+            // Record the transaction and move the money in the ledger
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::Withdraw,
+                Some(withdrawal_id.to_string()),
+                format!("Held for withdrawal {}", withdrawal_id),
+            );
+            let entries = entries_from_buckets(&transaction, taken, LedgerAccount::pending_withdrawal(user_id));
+            Ok((vec![transaction], entries))
+        }).await?;
+        
+        info!("Held {} from user {}'s balance for withdrawal {}", amount, user_id, withdrawal_id);
+        Ok(balance)
+    }
+    
+    /// Pays the funds held for an approved withdrawal out of the platform
+    pub async fn pay_withdrawal(&self, user_id: Uuid, amount: Decimal, withdrawal_id: Uuid) -> Result<()> {
+        // The ledger refuses to pay out more than is held for the user's withdrawals
+        let pending = LedgerAccount::pending_withdrawal(user_id);
+        let entry = LedgerEntry::new(
+            pending,
+            LedgerAccount::external(),
             amount,
-            TransactionType::Deposit, 
-            None,
-            "Deposit".to_string(),
+            TransactionType::WithdrawalPayout,
+            Some(withdrawal_id.to_string()),
+            format!("Paid out withdrawal {}", withdrawal_id),
         );
+        self.repository.post_ledger_entries(&[], &[], &[entry]).await?;
         
-        self.repository.save_balance_transaction(&transaction).await?;
+        info!("Paid out withdrawal {} of {} for user {}", withdrawal_id, amount, user_id);
+        Ok(())
+    }
+    
+    /// Returns the funds held for a rejected withdrawal to the user's available balance
+    pub async fn return_withdrawal(&self, user_id: Uuid, amount: Decimal, withdrawal_id: Uuid) -> Result<UserBalance> {
+        // The ledger refuses to return more than is held for the user's withdrawals
+        let pending = LedgerAccount::pending_withdrawal(user_id);
+        let balance = self.update_balance(user_id, |balance| {
+            balance.add_funds(amount);
+            
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::WithdrawalReturn,
+                Some(withdrawal_id.to_string()),
+                format!("Returned from withdrawal {}", withdrawal_id),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, pending.clone(), LedgerAccount::user_available(user_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
         
-        info!("Added {} to user {}'s balance", amount, user_id);
+        info!("Returned {} from withdrawal {} to user {}'s balance", amount, withdrawal_id, user_id);
         Ok(balance)
     }
     
-    /// Withdraws funds from a user's balance
-    pub async fn withdraw_funds(&self, user_id: Uuid, amount: Decimal) -> Result<UserBalance> {
+    /// Reserves funds for an order, moving them into the market as collateral
+    ///
+    /// Promotional credit is spent first, then cash, then winnings.
+    pub async fn reserve_funds(&self, user_id: Uuid, market_id: &str, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
         if amount <= Decimal::ZERO {
             return Err(anyhow!("Amount must be positive"));
         }
         
-        // Get the user's current balance
-        let mut balance = self.get_user_balance(user_id).await?;
+        let balance = self.update_balance(user_id, |balance| {
+            // Try to reserve the funds
+            let taken = balance.reserve_funds(amount, market_id, Some(order_id)).map_err(|e| anyhow!(e))?;
+            
+            // Record the transaction and move the money in the ledger
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::OrderReserve,
+                Some(order_id.to_string()),
+                format!("Reserved for order {}", order_id),
+            );
+            let entries = entries_from_buckets(&transaction, taken, LedgerAccount::market_collateral(market_id, user_id));
+            Ok((vec![transaction], entries))
+        }).await?;
         
-        // Try to withdraw the funds
-        if let Err(e) = balance.withdraw_funds(amount) {
-            return Err(anyhow!(e));
+        debug!("Reserved {} for order {} from user {}'s balance", amount, order_id, user_id);
+        Ok(balance)
+    }
+    
+    /// Reserves collateral from a group's house account in every market where a conversion hands out its Yes shares
+    ///
+    /// Handing out Yes shares leaves the house account short them, so like any short
+    /// seller it holds one unit per share as collateral in each market until the
+    /// market settles. Either every market is funded or none is.
+    pub async fn reserve_conversion_collateral(
+        &self,
+        house_account_id: Uuid,
+        market_ids: &[&str],
+        quantity: u32,
+        conversion_id: Uuid,
+    ) -> Result<UserBalance> {
+        let amount = Decimal::from(quantity);
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
         }
         
-        // Save the updated balance
-        self.repository.save_user_balance(&balance).await?;
+        let balance = self.update_balance(house_account_id, |balance| {
+            let mut transactions = Vec::with_capacity(market_ids.len());
+            let mut entries = Vec::new();
+            for market_id in market_ids {
+                let taken = balance.reserve_funds(amount, market_id, Some(conversion_id)).map_err(|e| anyhow!(e))?;
+                
+                let transaction = BalanceTransaction::new(
+                    house_account_id,
+                    amount,
+                    TransactionType::OrderReserve,
+                    Some(conversion_id.to_string()),
+                    format!("Collateral for Yes shares converted in market {}", market_id),
+                );
+                entries.extend(entries_from_buckets(&transaction, taken, LedgerAccount::market_collateral(market_id, house_account_id)));
+                transactions.push(transaction);
+            }
+            Ok((transactions, entries))
+        }).await?;
         
-        // Record the transaction
-        let transaction = BalanceTransaction::new(
-            user_id, 
-            amount,
-            TransactionType::Withdraw, 
-            None,
-            "Withdrawal".to_string(),
-        );
+        debug!("Reserved {} from house account {} in each of {} markets for conversion {}", amount, house_account_id, market_ids.len(), conversion_id);
+        Ok(balance)
+    }
+    
+    /// Releases collateral held by a market back to available (e.g., for cancelled orders)
+    ///
+    /// Promotional credit the order reserved is released first, back to the
+    /// promotional credit bucket.
+    pub async fn release_funds(&self, user_id: Uuid, market_id: &str, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
         
-        self.repository.save_balance_transaction(&transaction).await?;
+        let balance = self.update_balance(user_id, |balance| {
+            // Try to release the funds
+            let released = balance.release_funds(amount, market_id, Some(order_id)).map_err(|e| anyhow!(e))?;
+            
+            // Record the transaction and move the money in the ledger
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::OrderRelease,
+                Some(order_id.to_string()),
+                format!("Released from order {}", order_id),
+            );
+            let entries = entries_to_buckets(&transaction, LedgerAccount::market_collateral(market_id, user_id), released);
+            Ok((vec![transaction], entries))
+        }).await?;
         
-        info!("Withdrew {} from user {}'s balance", amount, user_id);
+        debug!("Released {} from order {} back to user {}'s balance", amount, order_id, user_id);
         Ok(balance)
     }
     
-    /// Reserves funds for an order
-    pub async fn reserve_funds(&self, user_id: Uuid, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
+    /// Consumes collateral to pay a market for a filled order
+    ///
+    /// Promotional credit the order reserved is spent first, and is recorded as paid
+    /// for shares in the market so selling them returns it as promotional credit.
+    pub async fn consume_reserved_funds(&self, user_id: Uuid, market_id: &str, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
         if amount <= Decimal::ZERO {
             return Err(anyhow!("Amount must be positive"));
         }
         
-        // Get the user's current balance
-        let mut balance = self.get_user_balance(user_id).await?;
+        let balance = self.update_balance(user_id, |balance| {
+            // Try to consume the reserved funds
+            let promo = balance.consume_reserved_funds(amount, market_id, Some(order_id)).map_err(|e| anyhow!(e))?;
+            balance.add_promo_position(market_id, promo);
+            
+            // Record the transaction and move the money into the market
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::TradeDebit,
+                Some(order_id.to_string()),
+                format!("Paid for fill of order {}", order_id),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::market_collateral(market_id, user_id), LedgerAccount::market_escrow(market_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
         
-        // Try to reserve the funds
-        if let Err(e) = balance.reserve_funds(amount) {
-            return Err(anyhow!(e));
+        debug!("Consumed {} reserved for order {} from user {}'s balance", amount, order_id, user_id);
+        Ok(balance)
+    }
+    
+    /// Pays a market out of its market maker's pool for a fill against the market maker
+    ///
+    /// The pool counts towards the house account's reserved balance.
+    pub async fn debit_market_maker_pool(&self, house_account_id: Uuid, market_id: &str, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
         }
         
-        // Save the updated balance
-        self.repository.save_user_balance(&balance).await?;
+        // The ledger refuses to pay out more than the pool holds
+        let pool = LedgerAccount::amm_subsidy(market_id, house_account_id);
+        let balance = self.update_balance(house_account_id, |balance| {
+            // Try to consume the reserved funds
+            balance.consume_reserved_funds(amount, market_id, None).map_err(|e| anyhow!(e))?;
+            
+            // Record the transaction and move the money into the market
+            let transaction = BalanceTransaction::new(
+                house_account_id,
+                amount,
+                TransactionType::TradeDebit,
+                Some(order_id.to_string()),
+                format!("Paid for fill of order {}", order_id),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, pool.clone(), LedgerAccount::market_escrow(market_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
         
-        // Record the transaction
-        let transaction = BalanceTransaction::new(
-            user_id, 
-            amount,
-            TransactionType::OrderReserve, 
-            Some(order_id.to_string()),
-            format!("Reserved for order {}", order_id),
-        );
+        debug!("Debited {} for order {} from the market maker pool of market {}", amount, order_id, market_id);
+        Ok(balance)
+    }
+    
+    /// Credits the proceeds of a fill against the market maker to its pool
+    pub async fn credit_market_maker_pool(&self, house_account_id: Uuid, market_id: &str, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
         
-        self.repository.save_balance_transaction(&transaction).await?;
+        let balance = self.update_balance(house_account_id, |balance| {
+            // The proceeds stay reserved in the pool until the market settles
+            balance.add_reserved_funds(amount);
+            
+            // Record the transaction and move the money out of the market into the pool
+            let transaction = BalanceTransaction::new(
+                house_account_id,
+                amount,
+                TransactionType::TradeCredit,
+                Some(order_id.to_string()),
+                format!("Proceeds from fill of order {}", order_id),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::market_escrow(market_id), LedgerAccount::amm_subsidy(market_id, house_account_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
         
-        debug!("Reserved {} for order {} from user {}'s balance", amount, order_id, user_id);
+        debug!("Credited {} for order {} to the market maker pool of market {}", amount, order_id, market_id);
         Ok(balance)
     }
     
-    /// Releases reserved funds back to available (e.g., for cancelled orders)
-    pub async fn release_funds(&self, user_id: Uuid, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
+    /// Credits the proceeds of a filled sell order out of the market
+    ///
+    /// Proceeds go back to the promotional credit bucket up to what promotional
+    /// credit paid for the user's shares in the market; the rest are winnings.
+    pub async fn credit_trade_proceeds(&self, user_id: Uuid, market_id: &str, amount: Decimal, order_id: Uuid) -> Result<UserBalance> {
         if amount <= Decimal::ZERO {
             return Err(anyhow!("Amount must be positive"));
         }
         
-        // Get the user's current balance
-        let mut balance = self.get_user_balance(user_id).await?;
+        let balance = self.update_balance(user_id, |balance| {
+            // Add the proceeds to available funds
+            let added = balance.add_winnings(market_id, amount);
+            
+            // Record the transaction and move the money out of the market
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::TradeCredit,
+                Some(order_id.to_string()),
+                format!("Proceeds from fill of order {}", order_id),
+            );
+            let entries = entries_to_buckets(&transaction, LedgerAccount::market_escrow(market_id), added);
+            Ok((vec![transaction], entries))
+        }).await?;
         
-        // Try to release the funds
-        if let Err(e) = balance.release_funds(amount) {
-            return Err(anyhow!(e));
+        debug!("Credited {} for order {} to user {}'s balance", amount, order_id, user_id);
+        Ok(balance)
+    }
+    
+    /// Reserves the subsidy for an automated market maker from the house account
+    ///
+    /// The subsidy counts towards the house account's reserved balance but is held
+    /// in the market maker's pool until the market settles.
+    pub async fn reserve_amm_subsidy(&self, house_account_id: Uuid, amount: Decimal, market_id: &str) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
         }
         
-        // Save the updated balance
-        self.repository.save_user_balance(&balance).await?;
+        let balance = self.update_balance(house_account_id, |balance| {
+            // Try to reserve the subsidy
+            let taken = balance.reserve_funds(amount, market_id, None).map_err(|e| anyhow!(e))?;
+            
+            // Record the transaction and set the subsidy aside
+            let transaction = BalanceTransaction::new(
+                house_account_id,
+                amount,
+                TransactionType::AmmSubsidy,
+                Some(market_id.to_string()),
+                format!("Market maker subsidy for market {}", market_id),
+            );
+            let entries = entries_from_buckets(&transaction, taken, LedgerAccount::amm_subsidy(market_id, house_account_id));
+            Ok((vec![transaction], entries))
+        }).await?;
         
-        // Record the transaction
-        let transaction = BalanceTransaction::new(
-            user_id, 
+        info!("Reserved market maker subsidy of {} from house account {} for market {}", amount, house_account_id, market_id);
+        Ok(balance)
+    }
+    
+    /// Turns whatever a market maker's pool holds into the house account's collateral so it settles with the market
+    pub async fn release_market_maker_pool(&self, house_account_id: Uuid, market_id: &str) -> Result<Decimal> {
+        let pool = LedgerAccount::amm_subsidy(market_id, house_account_id);
+        let amount = self.repository.get_ledger_balance(&pool).await?;
+        if amount <= Decimal::ZERO {
+            return Ok(Decimal::ZERO);
+        }
+        
+        let entry = LedgerEntry::new(
+            pool,
+            LedgerAccount::market_collateral(market_id, house_account_id),
             amount,
-            TransactionType::OrderRelease, 
-            Some(order_id.to_string()),
-            format!("Released from order {}", order_id),
+            TransactionType::AmmSubsidy,
+            Some(market_id.to_string()),
+            format!("Market maker pool of market {} released for settlement", market_id),
         );
         
-        self.repository.save_balance_transaction(&transaction).await?;
+        self.repository.post_ledger_entries(&[], &[], &[entry]).await?;
         
-        debug!("Released {} from order {} back to user {}'s balance", amount, order_id, user_id);
-        Ok(balance)
+        debug!("Released market maker pool of {} for market {} to house account {}", amount, market_id, house_account_id);
+        Ok(amount)
     }
     
-    /// Processes a settlement payout
+    /// Pays a user out of the funds a market holds
+    ///
+    /// A market can only pay out what has been paid into it.
     pub async fn process_payout(&self, user_id: Uuid, amount: Decimal, market_id: &str) -> Result<UserBalance> {
-        // Get the user's current balance
-        let mut balance = self.get_user_balance(user_id).await?;
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
         
-        // Add the payout to available funds
-        balance.add_funds(amount);
+        // The ledger refuses to pay out more than the market's escrow holds
+        let escrow = LedgerAccount::market_escrow(market_id);
+        let balance = self.update_balance(user_id, |balance| {
+            // Add the payout to available funds
+            let added = balance.add_winnings(market_id, amount);
+            
+            // Record the transaction and move the money out of the market
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::SettlementPayout,
+                Some(market_id.to_string()),
+                format!("Settlement payout from market {}", market_id),
+            );
+            let entries = entries_to_buckets(&transaction, escrow.clone(), added);
+            Ok((vec![transaction], entries))
+        }).await?;
         
-        // Save the updated balance
-        self.repository.save_user_balance(&balance).await?;
+        info!("Processed payout of {} to user {} from market {}", amount, user_id, market_id);
+        Ok(balance)
+    }
+    
+    /// Locks funds in a market to mint complete sets of its outcome shares
+    ///
+    /// The funds leave the user's balance and back the minted shares until they
+    /// are redeemed or the market settles. Promotional credit cannot pay for them,
+    /// since redeeming the sets straight away would turn it into cash.
+    pub async fn lock_for_complete_sets(&self, user_id: Uuid, market_id: &str, amount: Decimal) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
         
-        // Record the transaction
-        let transaction = BalanceTransaction::new(
-            user_id, 
-            amount,
-            TransactionType::SettlementPayout, 
-            Some(market_id.to_string()),
-            format!("Settlement payout from market {}", market_id),
-        );
+        let balance = self.update_balance(user_id, |balance| {
+            // Try to take the funds from the withdrawable buckets
+            let taken = balance.withdraw_withdrawable_funds(amount).map_err(|e| anyhow!(e))?;
+            
+            // Record the transaction and move the money into the market
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::CompleteSetMint,
+                Some(market_id.to_string()),
+                format!("Minted complete sets in market {}", market_id),
+            );
+            let entries = entries_from_buckets(&transaction, taken, LedgerAccount::market_escrow(market_id));
+            Ok((vec![transaction], entries))
+        }).await?;
+        
+        info!("Locked {} from user {} to mint complete sets in market {}", amount, user_id, market_id);
+        Ok(balance)
+    }
+    
+    /// Returns funds locked in a market for complete sets that have been redeemed
+    pub async fn release_from_complete_sets(&self, user_id: Uuid, market_id: &str, amount: Decimal) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
         
-        self.repository.save_balance_transaction(&transaction).await?;
+        let balance = self.update_balance(user_id, |balance| {
+            // Add the funds back to available
+            balance.add_funds(amount);
+            
+            // Record the transaction and move the money out of the market
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::CompleteSetRedeem,
+                Some(market_id.to_string()),
+                format!("Redeemed complete sets in market {}", market_id),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::market_escrow(market_id), LedgerAccount::user_available(user_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
         
-        info!("Processed payout of {} to user {} from market {}", amount, user_id, market_id);
+        info!("Returned {} to user {} for complete sets redeemed in market {}", amount, user_id, market_id);
         Ok(balance)
     }
     
+    /// Settles a user's position in a resolved market and saves the settlement with it
+    ///
+    /// `collateral` is what the market holds as the user's collateral. A positive
+    /// payout is paid to the user out of the market's escrow; a negative one is
+    /// owed and paid into escrow from that collateral first, then from available
+    /// funds. Any collateral left over is released. Tax withheld is moved from the
+    /// payout into the tax liability account.
+    pub async fn settle_position(&self, settlement: &PositionSettlement, collateral: Decimal) -> Result<UserBalance> {
+        let user_id = settlement.entry.user_id;
+        let market_id = settlement.market_id.as_str();
+        let payout = settlement.entry.payout;
+        let tax = settlement.tax();
+        
+        if tax < Decimal::ZERO || tax > payout.max(Decimal::ZERO) {
+            return Err(anyhow!("Cannot withhold {} from a payout of {}", tax, payout));
+        }
+        
+        // The ledger refuses to pay out more than the market's escrow holds
+        let escrow = LedgerAccount::market_escrow(market_id);
+        
+        let owed = (-payout).max(Decimal::ZERO);
+        let from_collateral = owed.min(collateral);
+        let released = collateral - from_collateral;
+        
+        let balance = self.update_balance_for(user_id, Some(settlement), |balance| {
+            // Cover what is owed from the collateral, then from available funds
+            balance.consume_reserved_funds(from_collateral, market_id, None).map_err(|e| anyhow!(e))?;
+            let taken = balance.withdraw_funds(owed - from_collateral).map_err(|e| anyhow!(e))?;
+            
+            // Return the rest of the collateral and pay out winning shares, returning
+            // promotional credit that paid for them as promotional credit
+            let returned = balance.release_funds(released, market_id, None).map_err(|e| anyhow!(e))?;
+            let added = if payout > Decimal::ZERO {
+                balance.add_winnings(market_id, payout)
+            } else {
+                BucketAmounts::default()
+            };
+            balance.settle_promo(market_id);
+            balance.withhold_from_winnings(tax).map_err(|e| anyhow!(e))?;
+            
+            // Record the transactions and their movements in the ledger
+            let mut transactions = Vec::new();
+            let mut entries = Vec::new();
+            if released > Decimal::ZERO {
+                let transaction = BalanceTransaction::new(
+                    user_id,
+                    released,
+                    TransactionType::OrderRelease,
+                    Some(market_id.to_string()),
+                    format!("Collateral released on settlement of market {}", market_id),
+                );
+                entries.extend(entries_to_buckets(&transaction, LedgerAccount::market_collateral(market_id, user_id), returned));
+                transactions.push(transaction);
+            }
+            if owed > Decimal::ZERO {
+                let transaction = BalanceTransaction::new(
+                    user_id,
+                    owed,
+                    TransactionType::SettlementDebit,
+                    Some(market_id.to_string()),
+                    format!("Settlement of short position in market {}", market_id),
+                );
+                if from_collateral > Decimal::ZERO {
+                    let mut entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::market_collateral(market_id, user_id), escrow.clone());
+                    entry.amount = from_collateral;
+                    entries.push(entry);
+                }
+                entries.extend(entries_from_buckets(&transaction, taken, escrow.clone()));
+                transactions.push(transaction);
+            }
+            if payout > Decimal::ZERO {
+                let transaction = BalanceTransaction::new(
+                    user_id,
+                    payout,
+                    TransactionType::SettlementPayout,
+                    Some(market_id.to_string()),
+                    format!("Settlement payout from market {}", market_id),
+                );
+                entries.extend(entries_to_buckets(&transaction, escrow.clone(), added));
+                transactions.push(transaction);
+            }
+            if tax > Decimal::ZERO {
+                let transaction = BalanceTransaction::new(
+                    user_id,
+                    tax,
+                    TransactionType::TaxWithholding,
+                    Some(market_id.to_string()),
+                    format!("TDS withheld from settlement payout of market {}", market_id),
+                );
+                entries.push(LedgerEntry::for_transaction(&transaction, LedgerAccount::user_winnings(user_id), LedgerAccount::tax_liability()));
+                transactions.push(transaction);
+            }
+            Ok((transactions, entries))
+        }).await?;
+        
+        info!("Settled user {} in market {}: payout {}, tax withheld {}, collateral released {}", user_id, market_id, payout, tax, released);
+        Ok(balance)
+    }
+    
+    /// Credits a grant of promotional credit to a user's balance until it expires
+    pub async fn add_promo_credit(
+        &self,
+        user_id: Uuid,
+        amount: Decimal,
+        credit_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<UserBalance> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
+        
+        let balance = self.update_balance(user_id, |balance| {
+            balance.add_promo(credit_id, amount, expires_at);
+            
+            // The house funds promotional credit from outside the platform
+            let transaction = BalanceTransaction::new(
+                user_id,
+                amount,
+                TransactionType::PromoCredit,
+                Some(credit_id.to_string()),
+                format!("Promotional credit {}", credit_id),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::external(), LedgerAccount::user_promo(user_id));
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
+        
+        info!("Credited promotional credit {} of {} to user {}", credit_id, amount, user_id);
+        Ok(balance)
+    }
+    
+    /// Removes whatever is left of a user's expired grants of promotional credit, returning how much was removed
+    pub async fn expire_promo_credit(&self, user_id: Uuid) -> Result<Decimal> {
+        let now = Utc::now();
+        let mut expired = Decimal::ZERO;
+        self.update_balance(user_id, |balance| {
+            expired = balance.expire_promo(now);
+            if expired.is_zero() {
+                return Ok((vec![], vec![]));
+            }
+            
+            let transaction = BalanceTransaction::new(
+                user_id,
+                expired,
+                TransactionType::PromoExpiry,
+                None,
+                "Promotional credit expired".to_string(),
+            );
+            let entry = LedgerEntry::for_transaction(&transaction, LedgerAccount::user_promo(user_id), LedgerAccount::external());
+            Ok((vec![transaction], vec![entry]))
+        }).await?;
+        
+        if expired > Decimal::ZERO {
+            info!("Expired {} of promotional credit for user {}", expired, user_id);
+        }
+        Ok(expired)
+    }
+    
     /// Gets transaction history for a user
     pub async fn get_transaction_history(&self, user_id: Uuid) -> Result<Vec<BalanceTransaction>> {
         self.repository.get_balance_transactions_for_user(user_id).await
             .map_err(|e| anyhow!("Failed to get transaction history: {}", e))
     }
-} 
\ No newline at end of file
+}
+
+/// Creates the ledger entries moving a transaction's amount out of the buckets it was taken from
+fn entries_from_buckets(transaction: &BalanceTransaction, taken: BucketAmounts, credit_account: LedgerAccount) -> Vec<LedgerEntry> {
+    taken.non_zero()
+        .into_iter()
+        .map(|(bucket, amount)| {
+            let mut entry = LedgerEntry::for_transaction(transaction, LedgerAccount::user_bucket(transaction.user_id, bucket), credit_account.clone());
+            entry.amount = amount;
+            entry
+        })
+        .collect()
+}
+
+/// Creates the ledger entries moving a transaction's amount into the buckets it was added to
+fn entries_to_buckets(transaction: &BalanceTransaction, debit_account: LedgerAccount, added: BucketAmounts) -> Vec<LedgerEntry> {
+    added.non_zero()
+        .into_iter()
+        .map(|(bucket, amount)| {
+            let mut entry = LedgerEntry::for_transaction(transaction, debit_account.clone(), LedgerAccount::user_bucket(transaction.user_id, bucket));
+            entry.amount = amount;
+            entry
+        })
+        .collect()
+}
+
+/// Sums what a transaction's ledger entries moved into and out of the user's available accounts
+///
+/// The entries for a transaction share its type, reference and description, which
+/// is unique among the transactions of a single balance update.
+fn available_change(transaction: &BalanceTransaction, entries: &[LedgerEntry]) -> Decimal {
+    let user_id = Some(transaction.user_id.to_string());
+    let is_available = |account: &LedgerAccount| account.is_user_available_account() && account.owner_id == user_id;
+    entries
+        .iter()
+        .filter(|entry| {
+            entry.transaction_type == transaction.transaction_type
+                && entry.reference_id == transaction.reference_id
+                && entry.description == transaction.description
+        })
+        .map(|entry| {
+            if is_available(&entry.credit_account) {
+                entry.amount
+            } else if is_available(&entry.debit_account) {
+                -entry.amount
+            } else {
+                Decimal::ZERO
+            }
+        })
+        .sum()
+}
@@ -197,58 +197,66 @@ impl<R: Repository + Send + Sync + 'static> BotService<R> {
         order_service: &OrderService<R>,
         config: &BotConfig,
     ) -> Result<(), String> {
-        // Get current midpoint price for Yes outcome
-        let mid_price = market.get_implied_probability()
-            .unwrap_or(Decimal::new(5, 1)); // Default to 0.5
-            
         // Calculate bid and ask prices with spread
         let half_spread = config.max_spread / Decimal::new(2, 0);
         
-        // Add small random jitter to prices - create a local RNG that doesn't cross await points
-        let jitter = {
-            let mut rng = rand::thread_rng();
-            Decimal::new(rng.gen_range(0..100) as i64, 4) // Random 0.0000 to 0.0100
-        };
+        // Without any orders, each outcome defaults to an even share of probability
+        let default_price = Decimal::ONE / Decimal::from(market.num_outcomes() as u32);
         
-        // Make sure prices stay in valid range (0.01 to 0.99)
-        let bid_price = (mid_price - half_spread + jitter)
-            .max(Decimal::new(1, 2))
-            .min(Decimal::new(99, 2));
+        // Quote a bid and an ask on every outcome
+        for index in 0..market.num_outcomes() {
+            let outcome = OutcomeSide::new(index as u32);
             
-        let ask_price = (mid_price + half_spread + jitter)
-            .max(Decimal::new(1, 2))
-            .min(Decimal::new(99, 2));
+            // Get current midpoint price for this outcome
+            let mid_price = market.order_book.get_mid_price(outcome)
+                .unwrap_or(default_price);
             
-        // Place bid order for Yes
-        let bid_order = Order::new(
-            config.bot_user_id,
-            market.market_id.clone(),
-            OrderSide::Buy,
-            OutcomeSide::Yes,
-            bid_price,
-            config.order_size,
-        );
-        
-        // Place ask order for Yes
-        let ask_order = Order::new(
-            config.bot_user_id,
-            market.market_id.clone(),
-            OrderSide::Sell,
-            OutcomeSide::Yes,
-            ask_price,
-            config.order_size,
-        );
-        
-        // Submit orders
-        let bid_result = order_service.submit_order(bid_order).await;
-        let ask_result = order_service.submit_order(ask_order).await;
-        
-        if let Err(e) = bid_result {
-            return Err(format!("Failed to place bid order: {}", e));
-        }
-        
-        if let Err(e) = ask_result {
-            return Err(format!("Failed to place ask order: {}", e));
+            // Add small random jitter to prices - create a local RNG that doesn't cross await points
+            let jitter = {
+                let mut rng = rand::thread_rng();
+                Decimal::new(rng.gen_range(0..100) as i64, 4) // Random 0.0000 to 0.0100
+            };
+            
+            // Make sure prices stay in valid range (0.01 to 0.99)
+            let bid_price = (mid_price - half_spread + jitter)
+                .max(Decimal::new(1, 2))
+                .min(Decimal::new(99, 2));
+                
+            let ask_price = (mid_price + half_spread + jitter)
+                .max(Decimal::new(1, 2))
+                .min(Decimal::new(99, 2));
+                
+            // Place bid order
+            let bid_order = Order::new(
+                config.bot_user_id,
+                market.market_id.clone(),
+                OrderSide::Buy,
+                outcome,
+                bid_price,
+                config.order_size,
+            );
+            
+            // Place ask order
+            let ask_order = Order::new(
+                config.bot_user_id,
+                market.market_id.clone(),
+                OrderSide::Sell,
+                outcome,
+                ask_price,
+                config.order_size,
+            );
+            
+            // Submit orders
+            let bid_result = order_service.submit_order(bid_order).await;
+            let ask_result = order_service.submit_order(ask_order).await;
+            
+            if let Err(e) = bid_result {
+                return Err(format!("Failed to place bid order: {}", e));
+            }
+            
+            if let Err(e) = ask_result {
+                return Err(format!("Failed to place ask order: {}", e));
+            }
         }
         
         Ok(())
@@ -264,9 +272,12 @@ impl<R: Repository + Send + Sync + 'static> BotService<R> {
         let random_ops = {
             let mut rng = rand::thread_rng();
             
-            // Get current midpoint price for Yes outcome
-            let mid_price = market.get_implied_probability()
-                .unwrap_or(Decimal::new(5, 1)); // Default to 0.5
+            // Random outcome
+            let outcome = OutcomeSide::new(rng.gen_range(0..market.num_outcomes()) as u32);
+            
+            // Get current midpoint price for the chosen outcome
+            let mid_price = market.order_book.get_mid_price(outcome)
+                .unwrap_or(Decimal::ONE / Decimal::from(market.num_outcomes() as u32));
                 
             // Random decision to buy or sell
             let side = if rng.gen_bool(0.5) {
@@ -285,13 +296,6 @@ impl<R: Repository + Send + Sync + 'static> BotService<R> {
             let size_factor = rng.gen_range(50..150) as u32;
             let size = (config.order_size * size_factor) / 100;
             
-            // Random outcome
-            let outcome = if rng.gen_bool(0.5) {
-                OutcomeSide::Yes
-            } else {
-                OutcomeSide::No
-            };
-            
             (side, outcome, price, size)
         };
         
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+use log::{info, warn};
+use rust_decimal::Decimal;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::deposit::{Deposit, DepositStatus};
+use crate::services::balance_service::BalanceService;
+use crate::services::payment_provider::PaymentProvider;
+use crate::db::connection::Repository;
+
+/// Service for taking deposits through a payment provider
+///
+/// A deposit starts as a payment with the provider and is credited to the user
+/// only when the provider's signed webhook confirms it. Providers deliver
+/// webhooks at least once, so each deposit is credited exactly once however
+/// often its webhook arrives.
+pub struct DepositService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Service crediting paid deposits
+    balance_service: Arc<BalanceService<R>>,
+    
+    /// Provider collecting the payments
+    payment_provider: Arc<dyn PaymentProvider>,
+    
+    /// Held while a webhook is handled, so repeats of it wait and see its outcome
+    lock: Mutex<()>,
+}
+
+impl<R: Repository> DepositService<R> {
+    /// Creates a new deposit service
+    pub fn new(
+        repository: Arc<R>,
+        balance_service: Arc<BalanceService<R>>,
+        payment_provider: Arc<dyn PaymentProvider>,
+    ) -> Self {
+        Self {
+            repository,
+            balance_service,
+            payment_provider,
+            lock: Mutex::new(()),
+        }
+    }
+    
+    /// Starts a deposit, setting up its payment with the provider
+    pub async fn create_deposit(&self, user_id: Uuid, amount: Decimal) -> Result<Deposit> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
+        
+        let mut deposit = Deposit::new(user_id, amount);
+        let intent = self.payment_provider.create_payment(&deposit).await
+            .map_err(|e| anyhow!("Failed to set up payment for deposit {}: {}", deposit.deposit_id, e))?;
+        deposit.provider_reference = Some(intent.provider_reference);
+        deposit.checkout_url = Some(intent.checkout_url);
+        
+        self.repository.save_deposit(&deposit).await?;
+        
+        info!("User {} started deposit {} of {}", user_id, deposit.deposit_id, amount);
+        Ok(deposit)
+    }
+    
+    /// Handles a webhook from the payment provider, crediting the deposit if it was paid
+    ///
+    /// A repeat of a webhook that was already handled returns the deposit as it is
+    /// without crediting it again.
+    pub async fn handle_callback(&self, body: &[u8], signature: &str) -> Result<Deposit> {
+        let callback = self.payment_provider.parse_callback(body, signature)?;
+        
+        let _lock = self.lock.lock().await;
+        let mut deposit = self.repository.get_deposit(callback.deposit_id).await?;
+        if deposit.provider_reference.as_deref() != Some(callback.provider_reference.as_str()) {
+            return Err(anyhow!(
+                "Payment {} does not belong to deposit {}",
+                callback.provider_reference, deposit.deposit_id
+            ));
+        }
+        
+        if !deposit.is_pending() {
+            if deposit.status == callback.status {
+                info!("Deposit {} was already {:?}", deposit.deposit_id, deposit.status);
+                return Ok(deposit);
+            }
+            return Err(anyhow!("Deposit {} is already {:?}", deposit.deposit_id, deposit.status));
+        }
+        
+        match callback.status {
+            DepositStatus::Pending => return Ok(deposit),
+            DepositStatus::Completed => {
+                if callback.amount != deposit.amount {
+                    return Err(anyhow!(
+                        "Deposit {} was paid {}, not {}",
+                        deposit.deposit_id, callback.amount, deposit.amount
+                    ));
+                }
+                
+                // The deposit ID is the credit's reference, so it is only ever credited once
+                let reference = deposit.deposit_id.to_string();
+                self.balance_service.add_funds(deposit.user_id, deposit.amount, Some(&reference)).await?;
+                deposit.complete();
+            }
+            DepositStatus::Failed => {
+                warn!("Payment for deposit {} failed: {:?}", deposit.deposit_id, callback.failure_reason);
+                deposit.fail(callback.failure_reason);
+            }
+        }
+        
+        if !self.repository.update_deposit(&deposit, DepositStatus::Pending).await? {
+            // Another instance handled a repeat of the webhook first
+            return self.repository.get_deposit(deposit.deposit_id).await;
+        }
+        
+        info!("Deposit {} of {} for user {} is {:?}", deposit.deposit_id, deposit.amount, deposit.user_id, deposit.status);
+        Ok(deposit)
+    }
+    
+    /// Gets a deposit by ID
+    pub async fn get_deposit(&self, deposit_id: Uuid) -> Result<Deposit> {
+        self.repository.get_deposit(deposit_id).await
+    }
+    
+    /// Gets a user's deposits, newest first
+    pub async fn get_deposits_for_user(&self, user_id: Uuid) -> Result<Vec<Deposit>> {
+        self.repository.get_deposits_for_user(user_id).await
+    }
+}
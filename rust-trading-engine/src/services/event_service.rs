@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use log::{info, error};
+use tokio::sync::{mpsc, Mutex};
+use anyhow::{Result, anyhow};
+
+use crate::models::{Event, EventStatus, EventStatusSummary, MarketStatus, MarketStatusSummary};
+use crate::services::order_service::OrderService;
+use crate::services::settlement_service::SettlementService;
+use crate::db::connection::Repository;
+
+/// Service for managing events and fanning their status out to their markets
+pub struct EventService<R: Repository> {
+    /// Database repository
+    repository: Arc<R>,
+    
+    /// Order service that owns the markets
+    order_service: Arc<OrderService<R>>,
+    
+    /// Settlement service for refunding cancelled markets
+    settlement_service: Arc<SettlementService<R>>,
+    
+    /// Sender for event status notifications
+    update_sender: mpsc::Sender<EventStatusSummary>,
+    
+    /// Serializes status changes so transitions are checked against the latest status
+    status_lock: Mutex<()>,
+}
+
+impl<R: Repository> EventService<R> {
+    /// Creates a new event service
+    pub fn new(
+        repository: Arc<R>,
+        order_service: Arc<OrderService<R>>,
+        settlement_service: Arc<SettlementService<R>>,
+        update_sender: mpsc::Sender<EventStatusSummary>,
+    ) -> Self {
+        Self {
+            repository,
+            order_service,
+            settlement_service,
+            update_sender,
+            status_lock: Mutex::new(()),
+        }
+    }
+    
+    /// Creates a new event
+    pub async fn create_event(&self, event: Event) -> Result<Event> {
+        if self.repository.get_event(&event.event_id).await.is_ok() {
+            return Err(anyhow!("Event with ID {} already exists", event.event_id));
+        }
+        
+        self.repository.save_event_with_markets(&event, &[]).await?;
+        
+        info!("Created event: {}", event.event_id);
+        Ok(event)
+    }
+    
+    /// Gets an event by ID
+    pub async fn get_event(&self, event_id: &str) -> Result<Event> {
+        self.repository.get_event(event_id).await
+            .map_err(|e| anyhow!("Failed to get event: {}", e))
+    }
+    
+    /// Gets all events
+    pub async fn get_all_events(&self) -> Result<Vec<Event>> {
+        self.repository.get_all_events().await
+            .map_err(|e| anyhow!("Failed to get events: {}", e))
+    }
+    
+    /// Gets the status of an event and each of its markets
+    pub async fn get_event_status(&self, event_id: &str) -> Result<EventStatusSummary> {
+        let event = self.get_event(event_id).await?;
+        
+        let mut markets = Vec::with_capacity(event.market_ids.len());
+        for market_id in &event.market_ids {
+            let market = self.order_service.get_market(market_id).await?;
+            markets.push(MarketStatusSummary {
+                market_id: market.market_id,
+                question: market.question,
+                status: market.status,
+            });
+        }
+        
+        Ok(EventStatusSummary {
+            event_id: event.event_id,
+            name: event.name,
+            status: event.status,
+            markets,
+        })
+    }
+    
+    /// Adds existing markets to an event
+    pub async fn add_markets(&self, event_id: &str, market_ids: &[String]) -> Result<EventStatusSummary> {
+        let _guard = self.status_lock.lock().await;
+        
+        let event = self.get_event(event_id).await?;
+        self.order_service.add_markets_to_event(&event, market_ids).await?;
+        
+        info!("Added {} markets to event {}", market_ids.len(), event_id);
+        self.publish_status(event_id).await
+    }
+    
+    /// Pauses trading on all of an event's markets
+    pub async fn pause_event(&self, event_id: &str) -> Result<EventStatusSummary> {
+        self.set_status(event_id, EventStatus::Paused).await
+    }
+    
+    /// Resumes trading on all of an event's paused markets
+    pub async fn resume_event(&self, event_id: &str) -> Result<EventStatusSummary> {
+        self.set_status(event_id, EventStatus::Open).await
+    }
+    
+    /// Closes trading on all of an event's markets
+    pub async fn close_event(&self, event_id: &str) -> Result<EventStatusSummary> {
+        self.set_status(event_id, EventStatus::Closed).await
+    }
+    
+    /// Cancels an event and all of its unresolved markets, refunding participants
+    ///
+    /// Cancelling an event that is already cancelled refunds its cancelled markets
+    /// again, which finishes any refunds that failed part way; participants already
+    /// refunded are skipped.
+    pub async fn cancel_event(&self, event_id: &str) -> Result<EventStatusSummary> {
+        self.set_status(event_id, EventStatus::Cancelled).await
+    }
+    
+    /// Moves an event to a new status and applies it to every market it owns
+    async fn set_status(&self, event_id: &str, status: EventStatus) -> Result<EventStatusSummary> {
+        let _guard = self.status_lock.lock().await;
+        
+        let mut event = self.get_event(event_id).await?;
+        
+        let changed = if status == EventStatus::Cancelled && event.status == EventStatus::Cancelled {
+            // Retrying a cancellation refunds the markets it cancelled again
+            let mut cancelled = Vec::new();
+            for market_id in &event.market_ids {
+                let market = self.order_service.get_market(market_id).await?;
+                if market.status == MarketStatus::Cancelled {
+                    cancelled.push(market);
+                }
+            }
+            cancelled
+        } else {
+            event.transition(status).map_err(|e| anyhow!(e))?;
+            
+            // The event and all of its markets change status in one transaction
+            self.order_service.apply_event_status(&event).await?
+        };
+        
+        if status == EventStatus::Cancelled {
+            for market in &changed {
+                self.settlement_service.process_market_cancellation_refunds(&market.market_id).await
+                    .map_err(|e| anyhow!("Failed to refund market {}: {}", market.market_id, e))?;
+            }
+        }
+        
+        info!("Event {} is now {:?}", event_id, status);
+        self.publish_status(event_id).await
+    }
+    
+    /// Gets an event's status and sends it to event subscribers
+    async fn publish_status(&self, event_id: &str) -> Result<EventStatusSummary> {
+        let summary = self.get_event_status(event_id).await?;
+        
+        if let Err(e) = self.update_sender.send(summary.clone()).await {
+            error!("Failed to send event status update: {}", e);
+        }
+        
+        Ok(summary)
+    }
+}
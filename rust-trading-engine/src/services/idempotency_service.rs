@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use log::debug;
+use anyhow::Result;
+
+use crate::models::IdempotencyRecord;
+use crate::db::connection::Repository;
+
+/// What to do with a request made with an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// First use of the key; run the request
+    New,
+    
+    /// The same request already succeeded with this key; send back its response
+    Replay {
+        status_code: u16,
+        response_body: String,
+    },
+    
+    /// A request with this key is still running
+    InProgress,
+    
+    /// The key was already used for a different request
+    Mismatch,
+}
+
+/// Service for making retried requests run only once
+pub struct IdempotencyService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+}
+
+impl<R: Repository> IdempotencyService<R> {
+    /// Creates a new idempotency service
+    pub fn new(repository: Arc<R>) -> Self {
+        Self { repository }
+    }
+    
+    /// Claims an idempotency key for a request on an endpoint
+    pub async fn claim(&self, endpoint: &str, idempotency_key: &str, request_body: &str) -> Result<IdempotencyClaim> {
+        let record = IdempotencyRecord::new(endpoint.to_string(), idempotency_key.to_string(), request_body.to_string());
+        if self.repository.claim_idempotency_key(&record).await? {
+            return Ok(IdempotencyClaim::New);
+        }
+        
+        // The key was released between the claim and the read, so its request is being retried
+        let Some(existing) = self.repository.get_idempotency_key(endpoint, idempotency_key).await? else {
+            return Ok(IdempotencyClaim::InProgress);
+        };
+        
+        if existing.request_body != request_body {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+        if !existing.is_completed() {
+            return Ok(IdempotencyClaim::InProgress);
+        }
+        
+        match (existing.status_code, existing.response_body) {
+            (Some(status_code), Some(response_body)) => {
+                debug!("Replaying response for idempotency key {} on {}", idempotency_key, endpoint);
+                Ok(IdempotencyClaim::Replay { status_code, response_body })
+            }
+            _ => Ok(IdempotencyClaim::InProgress),
+        }
+    }
+    
+    /// Stores the response to a request so repeats of it get the same response
+    pub async fn complete(&self, endpoint: &str, idempotency_key: &str, status_code: u16, response_body: &str) -> Result<()> {
+        self.repository.complete_idempotency_key(endpoint, idempotency_key, status_code, response_body).await
+    }
+    
+    /// Frees an idempotency key after its request failed, so the request can be retried
+    pub async fn release(&self, endpoint: &str, idempotency_key: &str) -> Result<()> {
+        debug!("Releasing idempotency key {} on {}", idempotency_key, endpoint);
+        self.repository.delete_idempotency_key(endpoint, idempotency_key).await
+    }
+}
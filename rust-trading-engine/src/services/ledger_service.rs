@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use chrono::Utc;
+use log::{info, warn};
+use rust_decimal::Decimal;
+use anyhow::Result;
+
+use crate::models::{BalanceBucket, LedgerAccount, LedgerAccountType, LedgerBalance, LedgerCheck, MarketEscrow, MarketStatus};
+use crate::db::connection::Repository;
+
+/// Service for inspecting the double-entry ledger
+pub struct LedgerService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+}
+
+impl<R: Repository> LedgerService<R> {
+    /// Creates a new ledger service
+    pub fn new(repository: Arc<R>) -> Self {
+        Self { repository }
+    }
+    
+    /// Gets the balance of every ledger account
+    pub async fn get_account_balances(&self) -> Result<Vec<LedgerBalance>> {
+        self.repository.get_ledger_balances().await
+    }
+    
+    /// Checks that the ledger conserves money and agrees with every user balance
+    ///
+    /// The invariants are:
+    /// - account balances sum to zero, so money only leaves one account by entering another
+    /// - only the external account may be negative
+    /// - each bucket of a user's available balance (cash, winnings and promotional
+    ///   credit) equals its account, and their reserved balance equals their
+    ///   reserved account plus the collateral markets hold for them and the market
+    ///   maker pools they fund
+    /// - resolved and cancelled markets hold nothing
+    pub async fn check_invariants(&self) -> Result<LedgerCheck> {
+        let balances = self.repository.get_ledger_balances().await?;
+        let mut violations = Vec::new();
+        
+        let mut net_balance = Decimal::ZERO;
+        let mut external = Decimal::ZERO;
+        let mut held_by_users = Decimal::ZERO;
+        let mut held_in_escrow = Decimal::ZERO;
+        let mut held_as_fees = Decimal::ZERO;
+        let mut held_as_subsidies = Decimal::ZERO;
+        let mut held_as_collateral = Decimal::ZERO;
+        let mut held_for_withdrawals = Decimal::ZERO;
+        let mut held_for_tax = Decimal::ZERO;
+        let mut held_as_promo = Decimal::ZERO;
+        let mut held_by_markets: BTreeMap<String, Decimal> = BTreeMap::new();
+        let mut reserved_by_users: BTreeMap<String, Decimal> = BTreeMap::new();
+        let mut ledger_accounts: BTreeMap<LedgerAccount, Decimal> = BTreeMap::new();
+        
+        for LedgerBalance { account, balance } in balances {
+            net_balance += balance;
+            match account.account_type {
+                LedgerAccountType::External => external += balance,
+                LedgerAccountType::UserAvailable | LedgerAccountType::UserWinnings | LedgerAccountType::UserReserved => held_by_users += balance,
+                LedgerAccountType::UserPromo => {
+                    held_by_users += balance;
+                    held_as_promo += balance;
+                }
+                LedgerAccountType::MarketEscrow => held_in_escrow += balance,
+                LedgerAccountType::HouseFees => held_as_fees += balance,
+                LedgerAccountType::AmmSubsidy => held_as_subsidies += balance,
+                LedgerAccountType::MarketCollateral => held_as_collateral += balance,
+                LedgerAccountType::PendingWithdrawal => held_for_withdrawals += balance,
+                LedgerAccountType::TaxLiability => held_for_tax += balance,
+            }
+            
+            if account.is_market_account() {
+                *held_by_markets.entry(account.owner_id.clone().unwrap_or_default()).or_insert(Decimal::ZERO) += balance;
+            }
+            if matches!(account.account_type, LedgerAccountType::MarketCollateral | LedgerAccountType::AmmSubsidy) {
+                *reserved_by_users.entry(account.holder_id.clone().unwrap_or_default()).or_insert(Decimal::ZERO) += balance;
+            }
+            
+            if account.account_type != LedgerAccountType::External && balance < Decimal::ZERO {
+                violations.push(format!("Account {} is overdrawn: {}", account, balance));
+            }
+            
+            ledger_accounts.insert(account, balance);
+        }
+        
+        if net_balance != Decimal::ZERO {
+            violations.push(format!("Ledger does not balance: accounts sum to {}", net_balance));
+        }
+        
+        for (market_id, held) in held_by_markets {
+            if held.is_zero() {
+                continue;
+            }
+            
+            let market = self.repository.get_market(&market_id).await?;
+            if market.is_resolved() || market.status == MarketStatus::Cancelled {
+                violations.push(format!("Market {} is {:?} but still holds {}", market_id, market.status, held));
+            }
+        }
+        
+        let user_balances = self.repository.get_all_user_balances().await?;
+        for balance in &user_balances {
+            let buckets = balance.buckets();
+            for bucket in BalanceBucket::ALL {
+                let held = ledger_accounts.remove(&LedgerAccount::user_bucket(balance.user_id, bucket)).unwrap_or_default();
+                if buckets.get(bucket) != held {
+                    violations.push(format!(
+                        "User {} has {:?} balance {} but the ledger holds {}",
+                        balance.user_id, bucket, buckets.get(bucket), held
+                    ));
+                }
+            }
+            
+            let reserved = ledger_accounts.remove(&LedgerAccount::user_reserved(balance.user_id)).unwrap_or_default()
+                + reserved_by_users.remove(&balance.user_id.to_string()).unwrap_or_default();
+            
+            if balance.reserved_balance != reserved {
+                violations.push(format!(
+                    "User {} has reserved balance {} but the ledger holds {}",
+                    balance.user_id, balance.reserved_balance, reserved
+                ));
+            }
+        }
+        
+        // User accounts left over have money in the ledger but no balance
+        for (account, balance) in ledger_accounts {
+            let is_user_account = account.is_user_available_account() || account.account_type == LedgerAccountType::UserReserved;
+            if is_user_account && !balance.is_zero() {
+                violations.push(format!("Account {} holds {} but the user has no balance", account, balance));
+            }
+        }
+        for (user_id, reserved) in reserved_by_users {
+            if !reserved.is_zero() {
+                violations.push(format!("Markets hold {} for user {} but the user has no balance", reserved, user_id));
+            }
+        }
+        
+        let check = LedgerCheck {
+            net_balance,
+            money_on_platform: -external,
+            held_by_users,
+            held_in_escrow,
+            held_as_fees,
+            held_as_subsidies,
+            held_as_collateral,
+            held_for_withdrawals,
+            held_for_tax,
+            held_as_promo,
+            violations,
+            checked_at: Utc::now(),
+        };
+        
+        if check.is_consistent() {
+            info!("Ledger check passed: {} held on the platform", check.money_on_platform);
+        } else {
+            warn!("Ledger check found {} violations", check.violations.len());
+        }
+        
+        Ok(check)
+    }
+    
+    /// Gets what a market holds against the most it could have to pay out
+    ///
+    /// Whichever outcome wins, the market pays each long share of it at most one
+    /// unit, so its maximum liability is the largest number of long shares held in
+    /// any one outcome.
+    pub async fn get_market_escrow(&self, market_id: &str) -> Result<MarketEscrow> {
+        let market = self.repository.get_market(market_id).await?;
+        
+        let mut escrow = Decimal::ZERO;
+        let mut collateral = Decimal::ZERO;
+        let mut market_maker_pool = Decimal::ZERO;
+        for LedgerBalance { account, balance } in self.repository.get_market_ledger_balances(market_id).await? {
+            match account.account_type {
+                LedgerAccountType::MarketEscrow => escrow += balance,
+                LedgerAccountType::MarketCollateral => collateral += balance,
+                LedgerAccountType::AmmSubsidy => market_maker_pool += balance,
+                _ => {}
+            }
+        }
+        
+        let mut long_shares = vec![0i64; market.num_outcomes()];
+        if !market.is_resolved() && market.status != MarketStatus::Cancelled {
+            for position in self.repository.get_positions_for_market(market_id).await? {
+                if let Some(shares) = long_shares.get_mut(position.market_option_id.index()) {
+                    *shares += position.quantity.max(0);
+                }
+            }
+        }
+        let max_liability = Decimal::from(long_shares.into_iter().max().unwrap_or(0));
+        
+        let total_held = escrow + collateral + market_maker_pool;
+        Ok(MarketEscrow {
+            market_id: market.market_id,
+            escrow,
+            collateral,
+            market_maker_pool,
+            total_held,
+            max_liability,
+            is_fully_collateralized: total_held >= max_liability,
+        })
+    }
+}
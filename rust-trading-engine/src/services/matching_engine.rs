@@ -1,11 +1,10 @@
-use std::sync::Arc;
 use log::{debug, info};
 use rust_decimal::Decimal;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::models::{
-    Market, Order, OrderSide, OrderStatus, OutcomeSide, Trade
+    Market, Order, OrderSide, OrderStatus, Trade
 };
 
 /// Represents the result of an order matching operation
@@ -16,6 +15,9 @@ pub struct MatchingResult {
     
     /// The trades that were executed as part of the matching
     pub trades: Vec<Trade>,
+    
+    /// Resting orders that were filled (fully or partially) by the matching
+    pub updated_orders: Vec<Order>,
 }
 
 /// Service for matching orders in prediction markets
@@ -38,6 +40,7 @@ impl MatchingEngine {
             return MatchingResult {
                 remaining_order: Some(order),
                 trades: Vec::new(),
+                updated_orders: Vec::new(),
             };
         }
 
@@ -46,6 +49,7 @@ impl MatchingEngine {
         // Match the order against the order book
         let matched_result = self.match_order(&mut order, market).await;
         trades.extend(matched_result.trades);
+        let updated_orders = matched_result.updated_orders;
         
         // If there's still quantity remaining, add it to the order book
         let remaining_order = if order.remaining_quantity > 0 && order.is_active() {
@@ -58,19 +62,20 @@ impl MatchingEngine {
         MatchingResult {
             remaining_order,
             trades,
+            updated_orders,
         }
     }
 
-    /// Matches an order against the market order book
-    async fn match_order(&self, order: &mut Order, market: &mut Market) -> MatchingResult {
+    /// Matches an order against the market order book without resting the remainder
+    pub async fn match_order(&self, order: &mut Order, market: &mut Market) -> MatchingResult {
         let mut trades = Vec::new();
+        let mut updated_orders = Vec::new();
 
         // Determine which book to match against based on order type
-        let book = match (order.side, order.outcome) {
-            (OrderSide::Buy, OutcomeSide::Yes) => &mut market.order_book.yes_asks,
-            (OrderSide::Sell, OutcomeSide::Yes) => &mut market.order_book.yes_bids,
-            (OrderSide::Buy, OutcomeSide::No) => &mut market.order_book.no_asks,
-            (OrderSide::Sell, OutcomeSide::No) => &mut market.order_book.no_bids,
+        let outcome_book = market.order_book.outcome_book_mut(order.outcome);
+        let book = match order.side {
+            OrderSide::Buy => &mut outcome_book.asks,
+            OrderSide::Sell => &mut outcome_book.bids,
         };
 
         // Get a list of matching price levels
@@ -140,9 +145,7 @@ impl MatchingEngine {
                         matching_order.apply_fill(match_quantity);
                         
                         // Send trade notification
-                        if let Err(e) = self.trade_sender.try_send(trade.clone()) {
-                            debug!("Failed to send trade notification: {}", e);
-                        }
+                        self.notify_trade(&trade);
                         
                         // Add to result
                         trades.push(trade);
@@ -155,8 +158,13 @@ impl MatchingEngine {
                     
                     // If the matching order is fully filled, remove it
                     if matching_order.remaining_quantity == 0 {
-                        orders_at_price.remove(i);
+                        if let Some(filled_order) = orders_at_price.remove(i) {
+                            updated_orders.push(filled_order);
+                        }
                     } else {
+                        if match_quantity > 0 {
+                            updated_orders.push(matching_order.clone());
+                        }
                         i += 1;
                     }
                 }
@@ -171,6 +179,14 @@ impl MatchingEngine {
         MatchingResult {
             remaining_order: Some(order.clone()),
             trades,
+            updated_orders,
+        }
+    }
+
+    /// Publishes an executed trade to subscribers
+    pub fn notify_trade(&self, trade: &Trade) {
+        if let Err(e) = self.trade_sender.try_send(trade.clone()) {
+            debug!("Failed to send trade notification: {}", e);
         }
     }
 
@@ -3,10 +3,40 @@ pub mod order_service;
 pub mod bot_service;
 pub mod settlement_service;
 pub mod balance_service;
+pub mod amm_service;
+pub mod event_service;
+pub mod position_service;
+pub mod portfolio_service;
+pub mod ledger_service;
+pub mod idempotency_service;
+pub mod reconciliation_service;
+pub mod payout_provider;
+pub mod withdrawal_service;
+pub mod payment_provider;
+pub mod deposit_service;
+pub mod promo_service;
+pub mod tax_service;
+pub mod risk_service;
+pub mod auth_service;
 
 // Re-export common types
 pub use matching_engine::MatchingEngine;
 pub use order_service::OrderService;
 pub use bot_service::{BotService, BotStrategy, BotConfig};
 pub use settlement_service::SettlementService;
-pub use balance_service::BalanceService; 
\ No newline at end of file
+pub use balance_service::BalanceService;
+pub use amm_service::AmmService;
+pub use event_service::EventService;
+pub use position_service::PositionService;
+pub use portfolio_service::PortfolioService;
+pub use ledger_service::LedgerService;
+pub use idempotency_service::IdempotencyService;
+pub use reconciliation_service::ReconciliationService;
+pub use payout_provider::{LocalPayoutProvider, PayoutProvider};
+pub use withdrawal_service::WithdrawalService;
+pub use payment_provider::{MockPaymentProvider, PaymentProvider};
+pub use deposit_service::DepositService;
+pub use promo_service::PromoService;
+pub use tax_service::TaxService;
+pub use risk_service::RiskService;
+pub use auth_service::AuthService; 
\ No newline at end of file
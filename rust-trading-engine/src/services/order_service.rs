@@ -1,15 +1,18 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use log::{debug, info, error};
-use tokio::sync::{mpsc, Mutex, RwLock};
+use log::{error, info, warn};
+use tokio::sync::{mpsc, Mutex, MutexGuard, RwLock};
 use uuid::Uuid;
 use rust_decimal::Decimal;
 use anyhow::{Result, anyhow};
 use serde::{Serialize, Deserialize};
 
-use crate::models::{Market, Order, OrderStatus, OrderSide};
+use crate::models::{Event, EventStatus, LiquidityMode, Market, MarketGroup, MarketStatus, Order, OrderSide, OutcomeSide, RiskRejection, TimeInForce, Trade};
 use crate::services::matching_engine::MatchingEngine;
 use crate::services::balance_service::BalanceService;
+use crate::services::amm_service::AmmService;
+use crate::services::position_service::PositionService;
+use crate::services::risk_service::RiskService;
 use crate::db::connection::Repository;
 
 /// Result of matching an order
@@ -21,8 +24,150 @@ pub struct OrderMatchResult {
     /// Whether the order was matched
     pub was_matched: bool,
     
+    /// Trades executed for the order
+    pub trades: Vec<Trade>,
+    
     /// Any error that occurred
     pub error: Option<String>,
+    
+    /// The risk limit the order was rejected for, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_rejection: Option<RiskRejection>,
+}
+
+/// What to do with a market's resting orders when it is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PauseOrderPolicy {
+    /// Leave resting orders on the book to match again once the market resumes
+    KeepOrders,
+    
+    /// Cancel all resting orders and release their reserved funds
+    CancelOrders,
+}
+
+/// Result of pausing a market
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketPauseResult {
+    /// The paused market
+    pub market: Market,
+    
+    /// Orders cancelled because of the pause
+    pub cancelled_orders: Vec<Order>,
+}
+
+/// Result of converting No shares in one market of a group into Yes shares in the others
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupConversion {
+    /// The group the markets belong to
+    pub group_id: String,
+    
+    /// The user who converted shares
+    pub user_id: Uuid,
+    
+    /// The market whose No shares were converted
+    pub market_id: String,
+    
+    /// ID of the conversion, referenced by the collateral the house account reserved for it
+    pub conversion_id: Uuid,
+    
+    /// Number of shares converted
+    pub quantity: u32,
+    
+    /// Zero-price trades moving the shares between the user and the house account
+    pub trades: Vec<Trade>,
+}
+
+/// Result of minting or redeeming complete sets of a market's outcome shares
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompleteSetOperation {
+    /// The market the sets belong to
+    pub market_id: String,
+    
+    /// The user who minted or redeemed the sets
+    pub user_id: Uuid,
+    
+    /// Number of complete sets
+    pub quantity: u32,
+    
+    /// Funds locked for minting or returned on redemption
+    pub amount: Decimal,
+    
+    /// Transfers of each outcome's shares between the user and the complete set issuer
+    pub trades: Vec<Trade>,
+}
+
+/// Quote for closing a position at the prices currently on the book
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CashOutQuote {
+    /// The user holding the position
+    pub user_id: Uuid,
+    
+    /// The market the position is in
+    pub market_id: String,
+    
+    /// The outcome held
+    pub outcome: OutcomeSide,
+    
+    /// Net shares held (negative means short)
+    pub position_quantity: i64,
+    
+    /// Side of the order that closes the position
+    pub side: OrderSide,
+    
+    /// Shares the book can take now
+    pub quantity: u32,
+    
+    /// Shares the book cannot take now
+    pub unfilled_quantity: u32,
+    
+    /// Cash received for selling a long position, or paid to buy back a short one
+    pub amount: Decimal,
+    
+    /// Average price per share
+    pub average_price: Option<Decimal>,
+    
+    /// Price of the best level on the book
+    pub best_price: Option<Decimal>,
+    
+    /// Price of the last level needed, used as the limit of the exit order
+    pub worst_price: Option<Decimal>,
+    
+    /// Cash lost against closing the whole quantity at the best price
+    pub slippage: Decimal,
+}
+
+/// Result of closing a position at the prices currently on the book
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CashOutResult {
+    /// The quote the exit was executed against
+    pub quote: CashOutQuote,
+    
+    /// The immediate-or-cancel order that closed the position
+    pub result: OrderMatchResult,
+}
+
+/// A filled transfer of shares from one account to another at a price
+struct ShareTransfer<'a> {
+    /// The market the shares are in
+    market_id: &'a str,
+    
+    /// The outcome transferred
+    outcome: OutcomeSide,
+    
+    /// Account giving up the shares
+    from_user_id: Uuid,
+    
+    /// Account receiving the shares
+    to_user_id: Uuid,
+    
+    /// Number of shares
+    quantity: u32,
+    
+    /// Price per share
+    price: Decimal,
+    
+    /// Whether the sender holds the shares rather than selling them short
+    covered: bool,
 }
 
 /// Service for managing orders and markets
@@ -39,8 +184,20 @@ pub struct OrderService<R: Repository> {
     /// Balance service for handling user funds
     balance_service: Arc<BalanceService<R>>,
     
+    /// Automated market makers for markets that use them
+    amm_service: Arc<AmmService<R>>,
+    
+    /// Position service for tracking holdings
+    position_service: Arc<PositionService<R>>,
+    
+    /// Risk service checking orders against pre-trade limits
+    risk_service: Arc<RiskService<R>>,
+    
     /// Cache of markets
     markets_cache: Arc<RwLock<Vec<Market>>>,
+    
+    /// Sender for market status notifications
+    status_sender: mpsc::Sender<(String, MarketStatus)>,
 }
 
 impl<R: Repository> OrderService<R> {
@@ -48,14 +205,22 @@ impl<R: Repository> OrderService<R> {
     pub fn new(
         repository: Arc<R>,
         matching_engine: Arc<Mutex<MatchingEngine>>,
-        balance_service: Arc<BalanceService<R>>
+        balance_service: Arc<BalanceService<R>>,
+        amm_service: Arc<AmmService<R>>,
+        position_service: Arc<PositionService<R>>,
+        risk_service: Arc<RiskService<R>>,
+        status_sender: mpsc::Sender<(String, MarketStatus)>
     ) -> Self {
         Self {
             markets: Arc::new(RwLock::new(HashMap::new())),
             matching_engine,
             repository,
             balance_service,
+            amm_service,
+            position_service,
+            risk_service,
             markets_cache: Arc::new(RwLock::new(Vec::new())),
+            status_sender,
         }
     }
     
@@ -63,6 +228,12 @@ impl<R: Repository> OrderService<R> {
     pub async fn create_market(&self, market: Market) -> Result<Market> {
         let market_id = market.market_id.clone();
         
+        // Validate the market's outcomes
+        Market::validate_outcomes(&market.outcomes).map_err(|e| anyhow!(e))?;
+        
+        // Validate the range of scalar markets
+        market.validate_bounds().map_err(|e| anyhow!(e))?;
+        
         // Save market to database
         self.repository.save_market(&market).await?;
         
@@ -113,83 +284,300 @@ impl<R: Repository> OrderService<R> {
         }
     }
     
-    /// Calculates amount to reserve for an order
+    /// Enables an automated market maker on a market
+    pub async fn enable_market_maker(
+        &self,
+        market_id: &str,
+        liquidity: Decimal,
+        house_account_id: Uuid,
+        liquidity_mode: LiquidityMode,
+    ) -> Result<Market> {
+        if liquidity_mode == LiquidityMode::OrderBook {
+            return Err(anyhow!("Liquidity mode must be Amm or Hybrid to enable a market maker"));
+        }
+        
+        // Hold the matching engine so no order is matched while the market changes
+        let _engine = self.matching_engine.lock().await;
+        
+        let mut market = self.get_market(market_id).await?;
+        if market.is_resolved() || market.status == MarketStatus::Cancelled {
+            return Err(anyhow!("Market {} is no longer trading", market_id));
+        }
+        
+        self.amm_service.create_market_maker(
+            market_id,
+            market.num_outcomes(),
+            liquidity,
+            house_account_id
+        ).await?;
+        
+        market.liquidity_mode = liquidity_mode;
+        market.updated_at = chrono::Utc::now();
+        self.repository.save_market(&market).await?;
+        self.update_cached_market(&market).await;
+        
+        info!("Enabled market maker on market {} in {:?} mode", market_id, liquidity_mode);
+        Ok(market)
+    }
+    
+    /// Calculates amount to reserve for the unfilled part of an order
     fn calculate_reserve_amount(&self, order: &Order) -> Decimal {
-        match order.side {
-            // For buy orders, reserve price * quantity
-            OrderSide::Buy => order.price * Decimal::from(order.quantity),
+        order.reserve_amount()
+    }
+    
+    /// Builds the result for an order rejected before it reached the book
+    fn rejected(order: Order, error: String) -> OrderMatchResult {
+        OrderMatchResult {
+            order,
+            was_matched: false,
+            trades: Vec::new(),
+            error: Some(error),
+            risk_rejection: None,
+        }
+    }
+    
+    /// Replaces a market in the cache
+    async fn update_cached_market(&self, market: &Market) {
+        let mut markets = self.markets_cache.write().await;
+        if let Some(idx) = markets.iter().position(|m| m.market_id == market.market_id) {
+            markets[idx] = market.clone();
+        }
+    }
+    
+    /// Drops a market from the cache so it is next read from the database
+    pub async fn invalidate_cached_market(&self, market_id: &str) {
+        let mut markets = self.markets_cache.write().await;
+        markets.retain(|m| m.market_id != market_id);
+    }
+    
+    /// Takes the matching engine so no order is matched while the caller changes markets
+    ///
+    /// The engine is not reentrant: callers must not call back into methods that take it.
+    pub async fn lock_matching_engine(&self) -> MutexGuard<'_, MatchingEngine> {
+        self.matching_engine.lock().await
+    }
+    
+    /// Notifies subscribers that a market's status changed
+    async fn notify_status(&self, market: &Market) {
+        if let Err(e) = self.status_sender.send((market.market_id.clone(), market.status)).await {
+            error!("Failed to send status update for market {}: {}", market.market_id, e);
+        }
+    }
+    
+    /// Pauses trading on a market
+    ///
+    /// With `PauseOrderPolicy::CancelOrders` every resting order is cancelled and
+    /// its reserve released; otherwise orders stay on the book until the market resumes.
+    pub async fn pause_market(&self, market_id: &str, policy: PauseOrderPolicy) -> Result<MarketPauseResult> {
+        // Hold the matching engine so no order is matched while the market changes
+        let _engine = self.matching_engine.lock().await;
+        
+        let mut market = self.get_market(market_id).await?;
+        if !market.is_open() {
+            return Err(anyhow!("Market {} is not open", market_id));
+        }
+        
+        market.pause();
+        
+        let mut cancelled_orders = Vec::new();
+        if policy == PauseOrderPolicy::CancelOrders {
+            let resting: Vec<Order> = market.order_book.all_orders().cloned().collect();
             
-            // For sell orders, reserve quantity
-            OrderSide::Sell => Decimal::from(order.quantity),
+            for mut order in resting {
+                market.order_book.remove_order(order.order_id);
+                
+                let reserved_amount = self.calculate_reserve_amount(&order);
+                order.cancel();
+                self.repository.save_order(&order).await?;
+                
+                if reserved_amount > Decimal::ZERO {
+                    self.balance_service.release_funds(
+                        order.user_id,
+                        &order.market_id,
+                        reserved_amount,
+                        order.order_id
+                    ).await?;
+                }
+                
+                cancelled_orders.push(order);
+            }
+        }
+        
+        self.repository.save_market(&market).await?;
+        self.update_cached_market(&market).await;
+        self.notify_status(&market).await;
+        
+        info!("Paused market {} ({} orders cancelled)", market_id, cancelled_orders.len());
+        Ok(MarketPauseResult {
+            market,
+            cancelled_orders,
+        })
+    }
+    
+    /// Resumes trading on a paused market
+    pub async fn resume_market(&self, market_id: &str) -> Result<Market> {
+        // Hold the matching engine so no order is matched while the market changes
+        let _engine = self.matching_engine.lock().await;
+        
+        let mut market = self.get_market(market_id).await?;
+        if market.status != MarketStatus::Paused {
+            return Err(anyhow!("Market {} is not paused", market_id));
         }
+        
+        // A market paused through its event resumes with the event
+        if let Some(event_id) = &market.event_id {
+            let event = self.repository.get_event(event_id).await?;
+            if event.status != EventStatus::Open {
+                return Err(anyhow!("Market {} belongs to event {} which is {:?}", market_id, event_id, event.status));
+            }
+        }
+        
+        market.resume();
+        
+        self.repository.save_market(&market).await?;
+        self.update_cached_market(&market).await;
+        self.notify_status(&market).await;
+        
+        info!("Resumed market {}", market_id);
+        Ok(market)
     }
     
     /// Submits an order to a market
     pub async fn submit_order(&self, order: Order) -> Result<OrderMatchResult> {
+        // Hold the matching engine for the whole submission so share locks and fills are serialized
+        let engine = self.matching_engine.lock().await;
+        self.execute_order(&engine, order).await
+    }
+    
+    /// Checks, reserves for, matches and rests an order while the caller holds the matching engine
+    async fn execute_order(&self, engine: &MatchingEngine, order: Order) -> Result<OrderMatchResult> {
         let market_id = order.market_id.clone();
         let user_id = order.user_id;
         let order_id = order.order_id;
+        let mut order = order;
         
-        // Calculate amount to reserve
-        let reserve_amount = self.calculate_reserve_amount(&order);
+        // A client order ID can only be used once per user
+        if let Some(client_order_id) = &order.client_order_id {
+            if client_order_id.is_empty() || client_order_id.len() > Order::MAX_CLIENT_ORDER_ID_LEN {
+                let error = format!("Client order ID must be 1 to {} characters", Order::MAX_CLIENT_ORDER_ID_LEN);
+                return Ok(Self::rejected(order, error));
+            }
+            if let Some(existing) = self.repository.get_order_by_client_order_id(user_id, client_order_id).await? {
+                let error = format!("Client order ID {} is already used by order {}", client_order_id, existing.order_id);
+                return Ok(Self::rejected(order, error));
+            }
+        }
         
-        // Reserve funds for the order
-        self.balance_service.reserve_funds(
-            user_id,
-            reserve_amount,
-            order_id
-        ).await?;
+        // Get the market
+        let mut market = match self.get_market(&market_id).await {
+            Ok(market) => market,
+            Err(e) => return Ok(Self::rejected(order, format!("Market not found: {}", e))),
+        };
         
-        // Save the initial order to database
-        if let Err(e) = self.repository.save_order(&order).await {
-            // If saving fails, release the reserved funds
-            let _ = self.balance_service.release_funds(
+        // Check if market is open
+        if !market.is_open() {
+            return Ok(Self::rejected(order, "Market is not open for trading".to_string()));
+        }
+        
+        // Check that the order is for one of the market's outcomes
+        if !market.has_outcome(order.outcome) {
+            let error = format!("Market {} has no outcome {}", market_id, order.outcome.index());
+            return Ok(Self::rejected(order, error));
+        }
+        
+        // Check the order against the user's pre-trade risk limits
+        if let Some(rejection) = self.risk_service.check_order(&order).await? {
+            let mut result = Self::rejected(order, rejection.to_string());
+            result.risk_rejection = Some(rejection);
+            return Ok(result);
+        }
+        
+        // Sell shares the user holds before selling any short
+        if order.side == OrderSide::Sell {
+            let unlocked = self.get_unlocked_shares(&market, user_id, order.outcome).await?;
+            order.covered_quantity = order.quantity.min(unlocked);
+            
+            if !market.allow_shorting && order.covered_quantity < order.quantity {
+                let error = format!(
+                    "Market {} does not allow short selling: user {} holds {} unlocked shares, order is for {}",
+                    market_id, user_id, unlocked, order.quantity
+                );
+                return Ok(Self::rejected(order, error));
+            }
+        }
+        
+        // Reserve funds for the part of the order not backed by shares
+        let reserve_amount = self.calculate_reserve_amount(&order);
+        if reserve_amount > Decimal::ZERO {
+            self.balance_service.reserve_funds(
                 user_id,
+                &market_id,
                 reserve_amount,
                 order_id
-            ).await;
-            return Err(anyhow!("Failed to save order: {}", e));
+            ).await?;
         }
         
-        // Get the market from database
-        let mut market = match self.get_market(&market_id).await {
-            Ok(market) => market,
-            Err(e) => {
-                // If market not found, release the reserved funds
+        // Save the initial order to database
+        if let Err(e) = self.repository.save_order(&order).await {
+            // If saving fails, release the reserved funds
+            if reserve_amount > Decimal::ZERO {
                 let _ = self.balance_service.release_funds(
                     user_id,
+                    &market_id,
                     reserve_amount,
                     order_id
                 ).await;
-                return Ok(OrderMatchResult {
-                    order,
-                    was_matched: false,
-                    error: Some(format!("Market not found: {}", e)),
-                });
             }
-        };
+            return Err(anyhow!("Failed to save order: {}", e));
+        }
         
-        // Check if market is open
-        if !market.is_open() {
-            // Release funds if market is not open
-            let _ = self.balance_service.release_funds(
-                user_id,
-                reserve_amount,
-                order_id
-            ).await;
-            return Ok(OrderMatchResult {
-                order,
-                was_matched: false,
-                error: Some("Market is not open for trading".to_string()),
-            });
+        let mut trades = Vec::new();
+        
+        // Match against resting orders first
+        if market.uses_order_book() {
+            let result = engine.match_order(&mut order, &mut market).await;
+            
+            for resting_order in &result.updated_orders {
+                self.repository.save_order(resting_order).await?;
+            }
+            
+            for trade in &result.trades {
+                self.repository.save_trade(trade).await?;
+                self.position_service.apply_trade(trade).await?;
+                self.settle_trade_funds(trade, &order).await?;
+            }
+            
+            trades.extend(result.trades);
         }
         
-        // Get matching engine but don't use it yet (matching logic to be implemented later)
-        // This is a placeholder for future matching logic implementation
-        let _engine = self.matching_engine.lock().await;
+        // Then fill what remains against the automated market maker
+        if market.uses_amm() && order.remaining_quantity > 0 {
+            if let Some(trade) = self.fill_against_market_maker(&mut order).await? {
+                engine.notify_trade(&trade);
+                trades.push(trade);
+            }
+        }
+        
+        // Rest any remainder on the book, or cancel it if the market has no book or the order is immediate-or-cancel
+        if order.remaining_quantity > 0 {
+            if market.uses_order_book() && order.time_in_force == TimeInForce::GoodTilCancelled {
+                market.order_book.add_order(order.clone());
+            } else {
+                let unfilled_amount = self.calculate_reserve_amount(&order);
+                order.cancel();
+                if unfilled_amount > Decimal::ZERO {
+                    self.balance_service.release_funds(
+                        user_id,
+                        &market_id,
+                        unfilled_amount,
+                        order_id
+                    ).await?;
+                }
+            }
+        }
         
-        // For now, just add to order book
-        market.order_book.add_order(order.clone());
+        // Save the final order state
+        self.repository.save_order(&order).await?;
         
         // Save the updated market
         if let Err(e) = self.repository.save_market(&market).await {
@@ -198,25 +586,189 @@ impl<R: Repository> OrderService<R> {
         }
         
         // Update the cache
-        {
-            let mut markets = self.markets_cache.write().await;
-            if let Some(idx) = markets.iter().position(|m| m.market_id == market.market_id) {
-                markets[idx] = market.clone();
-            }
-        }
+        self.update_cached_market(&market).await;
         
         Ok(OrderMatchResult {
             order,
-            was_matched: false,
+            was_matched: !trades.is_empty(),
+            trades,
             error: None,
+            risk_rejection: None,
         })
     }
     
+    /// Moves funds between buyer and seller for a trade against the order book
+    async fn settle_trade_funds(&self, trade: &Trade, incoming_order: &Order) -> Result<()> {
+        let quantity = Decimal::from(trade.quantity);
+        let cost = trade.price * quantity;
+        
+        // The buyer pays out of the funds reserved for their order
+        if cost > Decimal::ZERO {
+            self.balance_service.consume_reserved_funds(
+                trade.buyer_id,
+                &trade.market_id,
+                cost,
+                trade.buy_order_id
+            ).await?;
+        }
+        
+        // An incoming buy that filled below its limit gets the price improvement back
+        if incoming_order.side == OrderSide::Buy {
+            let improvement = (incoming_order.price - trade.price) * quantity;
+            if improvement > Decimal::ZERO {
+                self.balance_service.release_funds(
+                    trade.buyer_id,
+                    &trade.market_id,
+                    improvement,
+                    trade.buy_order_id
+                ).await?;
+            }
+        }
+        
+        // The seller receives the proceeds; their collateral stays reserved until settlement
+        if cost > Decimal::ZERO {
+            self.balance_service.credit_trade_proceeds(
+                trade.seller_id,
+                &trade.market_id,
+                cost,
+                trade.sell_order_id
+            ).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Fills as much of an order as possible against the market's automated market maker
+    async fn fill_against_market_maker(&self, order: &mut Order) -> Result<Option<Trade>> {
+        let market_maker = match self.amm_service.get_market_maker(&order.market_id).await? {
+            Some(market_maker) => market_maker,
+            None => return Ok(None),
+        };
+        
+        // The house account cannot trade against its own market maker
+        let house_account_id = market_maker.house_account_id;
+        if order.user_id == house_account_id {
+            return Ok(None);
+        }
+        
+        let quote = match self.amm_service.quote_order(order).await? {
+            Some(quote) => quote,
+            None => return Ok(None),
+        };
+        
+        // Record the market maker's side of the fill as a filled order on the house account
+        let amm_side = match order.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let mut amm_order = Order::new(
+            house_account_id,
+            order.market_id.clone(),
+            amm_side,
+            order.outcome,
+            quote.average_price,
+            quote.quantity,
+        );
+        amm_order.apply_fill(quote.quantity);
+        
+        match order.side {
+            OrderSide::Buy => {
+                // The buyer pays the market maker out of their reserve
+                self.balance_service.consume_reserved_funds(
+                    order.user_id,
+                    &order.market_id,
+                    quote.amount,
+                    order.order_id
+                ).await?;
+                
+                let improvement = order.price * Decimal::from(quote.quantity) - quote.amount;
+                if improvement > Decimal::ZERO {
+                    self.balance_service.release_funds(
+                        order.user_id,
+                        &order.market_id,
+                        improvement,
+                        order.order_id
+                    ).await?;
+                }
+                
+                self.balance_service.credit_market_maker_pool(
+                    house_account_id,
+                    &order.market_id,
+                    quote.amount,
+                    amm_order.order_id
+                ).await?;
+            }
+            OrderSide::Sell => {
+                // The market maker pays the seller out of its pool
+                if let Err(e) = self.balance_service.debit_market_maker_pool(
+                    house_account_id,
+                    &order.market_id,
+                    quote.amount,
+                    amm_order.order_id
+                ).await {
+                    warn!("Market maker for market {} cannot pay for fill: {}", order.market_id, e);
+                    return Ok(None);
+                }
+                
+                self.balance_service.credit_trade_proceeds(
+                    order.user_id,
+                    &order.market_id,
+                    quote.amount,
+                    order.order_id
+                ).await?;
+            }
+        }
+        
+        self.amm_service.apply_fill(&order.market_id, order.outcome, order.side, &quote).await?;
+        order.apply_fill(quote.quantity);
+        
+        self.repository.save_order(&amm_order).await?;
+        
+        let trade = match order.side {
+            OrderSide::Buy => Trade::new(
+                order.market_id.clone(),
+                order.order_id,
+                order.user_id,
+                amm_order.order_id,
+                house_account_id,
+                order.outcome,
+                quote.average_price,
+                quote.quantity,
+            ),
+            OrderSide::Sell => Trade::new(
+                order.market_id.clone(),
+                amm_order.order_id,
+                house_account_id,
+                order.order_id,
+                order.user_id,
+                order.outcome,
+                quote.average_price,
+                quote.quantity,
+            ),
+        };
+        self.repository.save_trade(&trade).await?;
+        self.position_service.apply_trade(&trade).await?;
+        
+        info!(
+            "Filled order {} against market maker at average price {} for quantity {}",
+            order.order_id, quote.average_price, quote.quantity
+        );
+        Ok(Some(trade))
+    }
+    
     /// Cancels an order
     pub async fn cancel_order(&self, order_id: Uuid) -> Result<Order> {
+        // Hold the matching engine so the order cannot fill or be cancelled by settlement meanwhile
+        let _engine = self.matching_engine.lock().await;
+        
         // Get the order
         let mut order = self.repository.get_order(order_id).await?;
         
+        // Only an order still on the book has a reserve left to release
+        if !order.is_active() {
+            return Err(anyhow!("Order {} is {:?} and cannot be cancelled", order_id, order.status));
+        }
+        
         // Get the market
         let market_id = order.market_id.clone();
         let mut market = self.get_market(&market_id).await?;
@@ -237,19 +789,17 @@ impl<R: Repository> OrderService<R> {
             let reserved_amount = self.calculate_reserve_amount(&removed_order);
             
             // Release the reserved funds
-            self.balance_service.release_funds(
-                order.user_id,
-                reserved_amount,
-                order_id
-            ).await?;
+            if reserved_amount > Decimal::ZERO {
+                self.balance_service.release_funds(
+                    order.user_id,
+                    &order.market_id,
+                    reserved_amount,
+                    order_id
+                ).await?;
+            }
             
             // Update the cache
-            {
-                let mut markets = self.markets_cache.write().await;
-                if let Some(idx) = markets.iter().position(|m| m.market_id == market_id) {
-                    markets[idx] = market;
-                }
-            }
+            self.update_cached_market(&market).await;
             
             Ok(order)
         } else {
@@ -257,9 +807,453 @@ impl<R: Repository> OrderService<R> {
         }
     }
     
+    /// Creates a group of mutually exclusive and exhaustive binary markets
+    pub async fn create_market_group(&self, group: MarketGroup) -> Result<MarketGroup> {
+        if group.market_ids.len() < 2 {
+            return Err(anyhow!("A market group needs at least two markets"));
+        }
+        
+        // Hold the matching engine so the markets cannot change, or join another group, until the group is saved
+        let _engine = self.matching_engine.lock().await;
+        
+        let mut markets = Vec::with_capacity(group.market_ids.len());
+        for market_id in &group.market_ids {
+            if markets.iter().any(|m: &Market| &m.market_id == market_id) {
+                return Err(anyhow!("Market {} is listed more than once", market_id));
+            }
+            
+            let market = self.get_market(market_id).await?;
+            if !market.is_binary() {
+                return Err(anyhow!("Market {} is not a binary market", market_id));
+            }
+            if market.is_resolved() {
+                return Err(anyhow!("Market {} is already resolved", market_id));
+            }
+            if let Some(group_id) = &market.group_id {
+                return Err(anyhow!("Market {} already belongs to group {}", market_id, group_id));
+            }
+            markets.push(market);
+        }
+        
+        self.repository.save_market_group(&group).await?;
+        
+        for mut market in markets {
+            market.group_id = Some(group.group_id.clone());
+            self.update_cached_market(&market).await;
+        }
+        
+        info!("Created market group {} with {} markets", group.group_id, group.market_ids.len());
+        Ok(group)
+    }
+    
+    /// Gets a market group by ID
+    pub async fn get_market_group(&self, group_id: &str) -> Result<MarketGroup> {
+        self.repository.get_market_group(group_id).await
+            .map_err(|e| anyhow!("Failed to get market group: {}", e))
+    }
+    
+    /// Adds markets to an event, bringing them in line with the event's status
+    pub async fn add_markets_to_event(&self, event: &Event, market_ids: &[String]) -> Result<Vec<Market>> {
+        if !event.accepts_markets() {
+            return Err(anyhow!("Event {} is {:?} and cannot take new markets", event.event_id, event.status));
+        }
+        
+        // Hold the matching engine so no order sees a half-applied change
+        let _engine = self.matching_engine.lock().await;
+        
+        let mut markets = Vec::with_capacity(market_ids.len());
+        for market_id in market_ids {
+            let mut market = self.get_market(market_id).await?;
+            if let Some(event_id) = &market.event_id {
+                return Err(anyhow!("Market {} already belongs to event {}", market_id, event_id));
+            }
+            
+            market.event_id = Some(event.event_id.clone());
+            market.apply_event_status(event.status);
+            markets.push(market);
+        }
+        
+        self.repository.save_event_with_markets(event, &markets).await?;
+        
+        for market in &markets {
+            self.update_cached_market(market).await;
+        }
+        
+        Ok(markets)
+    }
+    
+    /// Saves an event's new status and fans it out to all of its markets in one transaction
+    ///
+    /// Returns the markets whose status changed.
+    pub async fn apply_event_status(&self, event: &Event) -> Result<Vec<Market>> {
+        // Hold the matching engine so no order is matched against a market mid-change
+        let _engine = self.matching_engine.lock().await;
+        
+        let mut changed = Vec::new();
+        for market_id in &event.market_ids {
+            let mut market = self.get_market(market_id).await?;
+            if market.apply_event_status(event.status) {
+                changed.push(market);
+            }
+        }
+        
+        self.repository.save_event_with_markets(event, &changed).await?;
+        
+        for market in &changed {
+            self.update_cached_market(market).await;
+            self.notify_status(market).await;
+        }
+        
+        info!("Applied status {:?} of event {} to {} markets", event.status, event.event_id, changed.len());
+        Ok(changed)
+    }
+    
+    /// Gets the shares of an outcome a user holds that are not locked by their resting sell orders
+    async fn get_unlocked_shares(&self, market: &Market, user_id: Uuid, outcome: OutcomeSide) -> Result<u32> {
+        let position = self.position_service.get_position(user_id, &market.market_id, outcome).await?;
+        
+        let locked: i64 = market.order_book.all_orders()
+            .filter(|o| o.user_id == user_id && o.side == OrderSide::Sell && o.outcome == outcome)
+            .map(|o| o.locked_shares() as i64)
+            .sum();
+        
+        Ok((position.quantity - locked).clamp(0, u32::MAX as i64) as u32)
+    }
+    
+    /// Records a filled transfer of shares from one account to another at a price
+    ///
+    /// Any cash or collateral for the transfer is moved by the caller.
+    async fn transfer_shares(&self, transfer: ShareTransfer<'_>) -> Result<Trade> {
+        let ShareTransfer { market_id, outcome, from_user_id, to_user_id, quantity, price, covered } = transfer;
+        let mut sell_order = Order::new(
+            from_user_id,
+            market_id.to_string(),
+            OrderSide::Sell,
+            outcome,
+            price,
+            quantity,
+        );
+        if covered {
+            sell_order.covered_quantity = quantity;
+        }
+        sell_order.apply_fill(quantity);
+        
+        let mut buy_order = Order::new(
+            to_user_id,
+            market_id.to_string(),
+            OrderSide::Buy,
+            outcome,
+            price,
+            quantity,
+        );
+        buy_order.apply_fill(quantity);
+        
+        self.repository.save_order(&sell_order).await?;
+        self.repository.save_order(&buy_order).await?;
+        
+        let trade = Trade::new(
+            market_id.to_string(),
+            buy_order.order_id,
+            to_user_id,
+            sell_order.order_id,
+            from_user_id,
+            outcome,
+            price,
+            quantity,
+        );
+        self.repository.save_trade(&trade).await?;
+        self.position_service.apply_trade(&trade).await?;
+        
+        Ok(trade)
+    }
+    
+    /// Converts No shares in one market of a group into Yes shares in every other market
+    ///
+    /// Since exactly one market in the group resolves Yes, a No share in one market
+    /// pays out in exactly the same cases as holding one Yes share in each of the
+    /// others, so the user pays nothing. The house account takes the No shares and
+    /// is short the Yes shares it hands out, so it reserves one unit per share as
+    /// collateral in each of the other markets, as a short seller would; the markets
+    /// then settle on their own like any other.
+    pub async fn convert_no_to_yes(
+        &self,
+        group_id: &str,
+        user_id: Uuid,
+        market_id: &str,
+        quantity: u32,
+    ) -> Result<GroupConversion> {
+        if quantity == 0 {
+            return Err(anyhow!("Quantity must be positive"));
+        }
+        
+        let group = self.get_market_group(group_id).await?;
+        if !group.contains(market_id) {
+            return Err(anyhow!("Market {} is not in group {}", market_id, group_id));
+        }
+        
+        // Hold the matching engine so holdings cannot change during the conversion
+        let _engine = self.matching_engine.lock().await;
+        
+        for id in &group.market_ids {
+            let market = self.get_market(id).await?;
+            if !market.is_open() {
+                return Err(anyhow!("Market {} is not open for trading", id));
+            }
+        }
+        
+        // Shares locked by resting sell orders cannot be converted
+        let market = self.get_market(market_id).await?;
+        let held = self.get_unlocked_shares(&market, user_id, OutcomeSide::NO).await?;
+        if held < quantity {
+            return Err(anyhow!(
+                "User {} holds {} unlocked No shares in market {}, cannot convert {}",
+                user_id, held, market_id, quantity
+            ));
+        }
+        
+        // Fund the house account's short Yes shares before any shares move
+        let conversion_id = Uuid::new_v4();
+        let sibling_ids: Vec<&str> = group.siblings_of(market_id).map(String::as_str).collect();
+        self.balance_service.reserve_conversion_collateral(
+            group.house_account_id,
+            &sibling_ids,
+            quantity,
+            conversion_id
+        ).await.map_err(|e| anyhow!("House account {} cannot collateralize the conversion: {}", group.house_account_id, e))?;
+        
+        let mut trades = Vec::with_capacity(group.market_ids.len());
+        
+        // Hand the No shares to the house account...
+        trades.push(self.transfer_shares(ShareTransfer {
+            market_id,
+            outcome: OutcomeSide::NO,
+            from_user_id: user_id,
+            to_user_id: group.house_account_id,
+            quantity,
+            price: Decimal::ZERO,
+            covered: true,
+        }).await?);
+        
+        // ...in exchange for Yes shares in every other market
+        for sibling_id in sibling_ids {
+            trades.push(self.transfer_shares(ShareTransfer {
+                market_id: sibling_id,
+                outcome: OutcomeSide::YES,
+                from_user_id: group.house_account_id,
+                to_user_id: user_id,
+                quantity,
+                price: Decimal::ZERO,
+                covered: false,
+            }).await?);
+        }
+        
+        info!("User {} converted {} No shares in market {} within group {}", user_id, quantity, market_id, group_id);
+        Ok(GroupConversion {
+            group_id: group_id.to_string(),
+            user_id,
+            market_id: market_id.to_string(),
+            conversion_id,
+            quantity,
+            trades,
+        })
+    }
+    
+    /// Mints complete sets: locks one unit per set and gives the user one share of every outcome
+    pub async fn mint_complete_sets(&self, market_id: &str, user_id: Uuid, quantity: u32) -> Result<CompleteSetOperation> {
+        if quantity == 0 {
+            return Err(anyhow!("Quantity must be positive"));
+        }
+        
+        // Hold the matching engine so the market cannot change during the mint
+        let _engine = self.matching_engine.lock().await;
+        
+        let market = self.get_market(market_id).await?;
+        if !market.is_open() {
+            return Err(anyhow!("Market {} is not open for trading", market_id));
+        }
+        
+        self.balance_service.lock_for_complete_sets(user_id, market_id, Decimal::from(quantity)).await?;
+        
+        let mut trades = Vec::with_capacity(market.num_outcomes());
+        for (index, price) in market.complete_set_prices().into_iter().enumerate() {
+            trades.push(self.transfer_shares(ShareTransfer {
+                market_id,
+                outcome: OutcomeSide::new(index as u32),
+                from_user_id: Market::COMPLETE_SET_ISSUER_ID,
+                to_user_id: user_id,
+                quantity,
+                price,
+                covered: false,
+            }).await?);
+        }
+        
+        info!("User {} minted {} complete sets in market {}", user_id, quantity, market_id);
+        Ok(CompleteSetOperation {
+            market_id: market_id.to_string(),
+            user_id,
+            quantity,
+            amount: Decimal::from(quantity),
+            trades,
+        })
+    }
+    
+    /// Redeems complete sets: takes one share of every outcome per set and returns one unit for each
+    ///
+    /// Sets can be redeemed at any time until the market is resolved or cancelled.
+    pub async fn redeem_complete_sets(&self, market_id: &str, user_id: Uuid, quantity: u32) -> Result<CompleteSetOperation> {
+        if quantity == 0 {
+            return Err(anyhow!("Quantity must be positive"));
+        }
+        
+        // Hold the matching engine so holdings cannot change during the redemption
+        let _engine = self.matching_engine.lock().await;
+        
+        let market = self.get_market(market_id).await?;
+        if market.is_resolved() || market.status == MarketStatus::Cancelled {
+            return Err(anyhow!("Market {} is already settled", market_id));
+        }
+        
+        // Shares locked by resting sell orders cannot be redeemed
+        for index in 0..market.num_outcomes() {
+            let outcome = OutcomeSide::new(index as u32);
+            let held = self.get_unlocked_shares(&market, user_id, outcome).await?;
+            if held < quantity {
+                return Err(anyhow!(
+                    "User {} holds {} unlocked shares of outcome {} in market {}, cannot redeem {} sets",
+                    user_id, held, index, market_id, quantity
+                ));
+            }
+        }
+        
+        let mut trades = Vec::with_capacity(market.num_outcomes());
+        for (index, price) in market.complete_set_prices().into_iter().enumerate() {
+            trades.push(self.transfer_shares(ShareTransfer {
+                market_id,
+                outcome: OutcomeSide::new(index as u32),
+                from_user_id: user_id,
+                to_user_id: Market::COMPLETE_SET_ISSUER_ID,
+                quantity,
+                price,
+                covered: true,
+            }).await?);
+        }
+        
+        self.balance_service.release_from_complete_sets(user_id, market_id, Decimal::from(quantity)).await?;
+        
+        info!("User {} redeemed {} complete sets in market {}", user_id, quantity, market_id);
+        Ok(CompleteSetOperation {
+            market_id: market_id.to_string(),
+            user_id,
+            quantity,
+            amount: Decimal::from(quantity),
+            trades,
+        })
+    }
+    
+    /// Quotes what a user would receive for closing their position in an outcome now
+    pub async fn quote_cash_out(&self, user_id: Uuid, market_id: &str, outcome: OutcomeSide) -> Result<CashOutQuote> {
+        let _engine = self.matching_engine.lock().await;
+        
+        let market = self.get_market(market_id).await?;
+        self.build_cash_out_quote(&market, user_id, outcome).await
+    }
+    
+    /// Closes a user's position in an outcome against the book in one step
+    ///
+    /// The quote and the immediate-or-cancel order that executes it are made under
+    /// the same hold on the matching engine, so the fill matches the quote.
+    pub async fn cash_out(&self, user_id: Uuid, market_id: &str, outcome: OutcomeSide) -> Result<CashOutResult> {
+        let engine = self.matching_engine.lock().await;
+        
+        let market = self.get_market(market_id).await?;
+        if !market.is_open() {
+            return Err(anyhow!("Market {} is not open for trading", market_id));
+        }
+        
+        let quote = self.build_cash_out_quote(&market, user_id, outcome).await?;
+        let limit_price = match quote.worst_price {
+            Some(price) => price,
+            None => return Err(anyhow!("No liquidity on the book to close the position in market {}", market_id)),
+        };
+        
+        let mut order = Order::new(
+            user_id,
+            market_id.to_string(),
+            quote.side,
+            outcome,
+            limit_price,
+            quote.quantity,
+        );
+        order.time_in_force = TimeInForce::ImmediateOrCancel;
+        
+        let result = self.execute_order(&engine, order).await?;
+        if let Some(error) = &result.error {
+            return Err(anyhow!("Failed to close position: {}", error));
+        }
+        
+        info!("User {} cashed out {} shares of outcome {} in market {} for {}",
+            user_id, quote.quantity, outcome.index(), market_id, quote.amount);
+        Ok(CashOutResult { quote, result })
+    }
+    
+    /// Walks the book opposite a user's position to price closing it
+    async fn build_cash_out_quote(&self, market: &Market, user_id: Uuid, outcome: OutcomeSide) -> Result<CashOutQuote> {
+        if !market.has_outcome(outcome) {
+            return Err(anyhow!("Market {} has no outcome {}", market.market_id, outcome.index()));
+        }
+        
+        let position = self.position_service.get_position(user_id, &market.market_id, outcome).await?;
+        
+        // A long position sells the shares not already locked by resting sell orders; a short one buys back
+        let (side, quantity) = if position.quantity > 0 {
+            (OrderSide::Sell, self.get_unlocked_shares(market, user_id, outcome).await?)
+        } else {
+            (OrderSide::Buy, position.quantity.unsigned_abs().min(u32::MAX as u64) as u32)
+        };
+        if quantity == 0 {
+            return Err(anyhow!(
+                "User {} has no position to close in outcome {} of market {}",
+                user_id, outcome.index(), market.market_id
+            ));
+        }
+        
+        let walk = market.order_book.walk(outcome, side, quantity, user_id);
+        let at_best_price = walk.best_price.unwrap_or_default() * Decimal::from(walk.quantity);
+        let slippage = match side {
+            OrderSide::Sell => at_best_price - walk.amount,
+            OrderSide::Buy => walk.amount - at_best_price,
+        };
+        
+        Ok(CashOutQuote {
+            user_id,
+            market_id: market.market_id.clone(),
+            outcome,
+            position_quantity: position.quantity,
+            side,
+            quantity: walk.quantity,
+            unfilled_quantity: quantity - walk.quantity,
+            amount: walk.amount,
+            average_price: (walk.quantity > 0).then(|| walk.amount / Decimal::from(walk.quantity)),
+            best_price: walk.best_price,
+            worst_price: walk.worst_price,
+            slippage,
+        })
+    }
+    
     /// Gets all orders for a user in a market
     pub async fn get_orders_for_user(&self, market_id: &str, user_id: Uuid) -> Result<Vec<Order>> {
         self.repository.get_orders_for_user(market_id, user_id).await
             .map_err(|e| anyhow!("Failed to get orders: {}", e))
     }
+    
+    /// Gets an order by ID
+    pub async fn get_order(&self, order_id: Uuid) -> Result<Order> {
+        self.repository.get_order(order_id).await
+    }
+    
+    /// Gets a user's order by the ID the client chose for it
+    pub async fn get_order_by_client_order_id(&self, user_id: Uuid, client_order_id: &str) -> Result<Order> {
+        self.repository.get_order_by_client_order_id(user_id, client_order_id).await?
+            .ok_or_else(|| anyhow!("User {} has no order with client order ID {}", user_id, client_order_id))
+    }
 } 
\ No newline at end of file
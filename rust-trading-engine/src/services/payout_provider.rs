@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use log::info;
+use tokio::sync::Mutex;
+use anyhow::Result;
+
+use crate::models::withdrawal::Withdrawal;
+
+/// Sends the funds of approved withdrawals to their destination
+#[async_trait::async_trait]
+pub trait PayoutProvider: Send + Sync {
+    /// Pays a withdrawal out to its destination, returning the provider's reference for the payout
+    ///
+    /// The withdrawal ID identifies the payout, so sending the same withdrawal
+    /// again must not pay it twice and should return the same reference.
+    async fn send_payout(&self, withdrawal: &Withdrawal) -> Result<String>;
+}
+
+/// Payout provider that only logs payouts, for local development and testing
+#[derive(Default)]
+pub struct LocalPayoutProvider {
+    /// Reference of each withdrawal paid out so far
+    payouts: Mutex<HashMap<uuid::Uuid, String>>,
+}
+
+impl LocalPayoutProvider {
+    /// Creates a new local payout provider
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl PayoutProvider for LocalPayoutProvider {
+    async fn send_payout(&self, withdrawal: &Withdrawal) -> Result<String> {
+        let mut payouts = self.payouts.lock().await;
+        if let Some(reference) = payouts.get(&withdrawal.withdrawal_id) {
+            return Ok(reference.clone());
+        }
+        
+        let reference = format!("local-{}", withdrawal.withdrawal_id);
+        info!(
+            "Local payout {} of {} to {} for user {}",
+            reference, withdrawal.amount, withdrawal.destination, withdrawal.user_id
+        );
+        payouts.insert(withdrawal.withdrawal_id, reference.clone());
+        Ok(reference)
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::{
+    MarkPriceSource, Market, MarketPortfolio, OutcomeSide, Portfolio, PortfolioTotals, Position,
+    PositionValuation, Trade,
+};
+use crate::services::balance_service::BalanceService;
+use crate::services::order_service::OrderService;
+use crate::services::position_service::PositionService;
+use crate::db::connection::Repository;
+
+/// Service for valuing user portfolios at current market prices
+pub struct PortfolioService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Order service for markets with their live order books
+    order_service: Arc<OrderService<R>>,
+    
+    /// Balance service for user funds
+    balance_service: Arc<BalanceService<R>>,
+    
+    /// Position service for user holdings
+    position_service: Arc<PositionService<R>>,
+    
+    /// Mark price source used when a request does not name one
+    default_mark_source: MarkPriceSource,
+}
+
+impl<R: Repository> PortfolioService<R> {
+    /// Creates a new portfolio service
+    pub fn new(
+        repository: Arc<R>,
+        order_service: Arc<OrderService<R>>,
+        balance_service: Arc<BalanceService<R>>,
+        position_service: Arc<PositionService<R>>,
+        default_mark_source: MarkPriceSource,
+    ) -> Self {
+        Self {
+            repository,
+            order_service,
+            balance_service,
+            position_service,
+            default_mark_source,
+        }
+    }
+    
+    /// Values a user's positions and reserved funds in every market they are involved in
+    pub async fn get_portfolio(&self, user_id: Uuid, mark_source: Option<MarkPriceSource>) -> Result<Portfolio> {
+        let mark_source = mark_source.unwrap_or(self.default_mark_source);
+        let balance = self.balance_service.get_user_balance(user_id).await?;
+        
+        // Markets hold the user's collateral and any market maker pools they fund
+        let mut holdings: BTreeMap<String, (Vec<Position>, Decimal)> = BTreeMap::new();
+        for position in self.position_service.get_positions_for_user(user_id).await? {
+            holdings.entry(position.market_id.clone()).or_default().0.push(position);
+        }
+        for reserve in self.repository.get_market_reserves_for_user(user_id).await? {
+            if !reserve.balance.is_zero() {
+                holdings.entry(reserve.account.owner_id.unwrap_or_default()).or_default().1 += reserve.balance;
+            }
+        }
+        
+        let mut markets = Vec::with_capacity(holdings.len());
+        for (market_id, (positions, reserved_collateral)) in holdings {
+            let market = self.order_service.get_market(&market_id).await?;
+            markets.push(self.value_market(&market, &positions, reserved_collateral, mark_source).await?);
+        }
+        
+        let reserved_in_markets: Decimal = markets.iter().map(|m| m.reserved_collateral).sum();
+        let market_value: Decimal = markets.iter().map(|m| m.market_value).sum();
+        let totals = PortfolioTotals {
+            available_balance: balance.available_balance,
+            cash_balance: balance.cash_balance(),
+            winnings_balance: balance.winnings_balance,
+            promo_balance: balance.promo_balance(),
+            withdrawable_balance: balance.withdrawable_balance(),
+            reserved_balance: balance.reserved_balance,
+            total_balance: balance.total_balance(),
+            reserved_in_markets,
+            reserved_unallocated: balance.reserved_balance - reserved_in_markets,
+            market_value,
+            equity: balance.total_balance() + market_value,
+            unrealized_pnl: markets.iter().map(|m| m.unrealized_pnl).sum(),
+            realized_pnl: markets.iter().map(|m| m.realized_pnl).sum(),
+        };
+        
+        Ok(Portfolio {
+            user_id,
+            mark_source,
+            markets,
+            totals,
+            valued_at: Utc::now(),
+        })
+    }
+    
+    /// Values a user's positions in one market
+    async fn value_market(
+        &self,
+        market: &Market,
+        positions: &[Position],
+        reserved_collateral: Decimal,
+        mark_source: MarkPriceSource,
+    ) -> Result<MarketPortfolio> {
+        let trades = if market.is_resolved() || positions.iter().all(|p| p.is_flat()) {
+            Vec::new()
+        } else {
+            self.repository.get_trades_for_market(&market.market_id).await?
+        };
+        let last_prices = Self::last_trade_prices(&trades, market.num_outcomes());
+        
+        let valuations: Vec<PositionValuation> = positions.iter()
+            .map(|position| {
+                let outcome = position.market_option_id;
+                let last_price = last_prices.get(outcome.index()).copied().flatten();
+                PositionValuation::new(
+                    outcome,
+                    position.quantity,
+                    position.average_entry_price,
+                    position.realized_pnl,
+                    Self::mark_price(market, outcome, mark_source, last_price),
+                )
+            })
+            .collect();
+        
+        Ok(MarketPortfolio {
+            market_id: market.market_id.clone(),
+            status: market.status,
+            reserved_collateral,
+            market_value: valuations.iter().map(|v| v.market_value).sum(),
+            unrealized_pnl: valuations.iter().map(|v| v.unrealized_pnl).sum(),
+            realized_pnl: valuations.iter().map(|v| v.realized_pnl).sum(),
+            positions: valuations,
+        })
+    }
+    
+    /// Gets the price to mark an outcome at
+    ///
+    /// Resolved markets are marked at what each share pays out.
+    fn mark_price(market: &Market, outcome: OutcomeSide, source: MarkPriceSource, last_price: Option<Decimal>) -> Option<Decimal> {
+        if market.is_resolved() {
+            return market.payout_per_share(outcome);
+        }
+        
+        let mid_price = market.order_book.get_mid_price(outcome);
+        match source {
+            MarkPriceSource::Mid => mid_price.or(last_price),
+            MarkPriceSource::LastTrade => last_price.or(mid_price),
+        }
+    }
+    
+    /// Gets the price of the last trade in each outcome
+    ///
+    /// Share conversions and complete set transfers are not trades at a market price
+    /// and are skipped.
+    fn last_trade_prices(trades: &[Trade], num_outcomes: usize) -> Vec<Option<Decimal>> {
+        let mut last: Vec<Option<&Trade>> = vec![None; num_outcomes];
+        
+        for trade in trades {
+            if trade.price <= Decimal::ZERO
+                || trade.seller_id == Market::COMPLETE_SET_ISSUER_ID
+                || trade.buyer_id == Market::COMPLETE_SET_ISSUER_ID
+            {
+                continue;
+            }
+            
+            if let Some(slot) = last.get_mut(trade.outcome.index()) {
+                if slot.is_none_or(|previous| trade.executed_at >= previous.executed_at) {
+                    *slot = Some(trade);
+                }
+            }
+        }
+        
+        last.into_iter().map(|trade| trade.map(|t| t.price)).collect()
+    }
+}
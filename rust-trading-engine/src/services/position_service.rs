@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use log::debug;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::{Market, OrderSide, OutcomeSide, Position, Trade};
+use crate::db::connection::Repository;
+
+/// Service for tracking what each user holds in each market
+pub struct PositionService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+}
+
+impl<R: Repository> PositionService<R> {
+    /// Creates a new position service
+    pub fn new(repository: Arc<R>) -> Self {
+        Self { repository }
+    }
+    
+    /// Gets a user's position in an outcome of a market, empty if they have never traded it
+    pub async fn get_position(&self, user_id: Uuid, market_id: &str, outcome: OutcomeSide) -> Result<Position> {
+        match self.repository.get_position(user_id, market_id, outcome).await? {
+            Some(position) => Ok(position),
+            None => Ok(Position::new(user_id, market_id.to_string(), outcome)),
+        }
+    }
+    
+    /// Gets all positions for a user
+    pub async fn get_positions_for_user(&self, user_id: Uuid) -> Result<Vec<Position>> {
+        self.repository.get_positions_for_user(user_id).await
+    }
+    
+    /// Updates the buyer's and seller's positions for a trade
+    pub async fn apply_trade(&self, trade: &Trade) -> Result<()> {
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let user_id = trade.user_id_for_side(side);
+            let mut position = self.get_position(user_id, &trade.market_id, trade.outcome).await?;
+            position.apply_trade(side, trade.price, trade.quantity);
+            self.repository.save_position(&position).await?;
+        }
+        
+        debug!("Updated positions for trade {}", trade.trade_id);
+        Ok(())
+    }
+    
+    /// Closes every open position in a resolved market at its payout per share
+    pub async fn settle_market(&self, market: &Market) -> Result<()> {
+        for mut position in self.repository.get_positions_for_market(&market.market_id).await? {
+            if position.is_flat() {
+                continue;
+            }
+            
+            let payout_per_share = market.payout_per_share(position.market_option_id).unwrap_or_default();
+            position.settle(payout_per_share);
+            self.repository.save_position(&position).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Unwinds every position in a cancelled market, whose trades are refunded
+    pub async fn void_market(&self, market_id: &str) -> Result<()> {
+        for mut position in self.repository.get_positions_for_market(market_id).await? {
+            position.void();
+            self.repository.save_position(&position).await?;
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use rust_decimal::Decimal;
+use tokio::time;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::promo::PromoCredit;
+use crate::services::balance_service::BalanceService;
+use crate::db::connection::Repository;
+
+/// Service for granting promotional credit and removing it when it expires
+///
+/// Promotional credit is spent before a user's cash and winnings, soonest-expiring
+/// grant first. Whatever is left of a grant when it expires is removed.
+pub struct PromoService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Service crediting and removing the credit
+    balance_service: Arc<BalanceService<R>>,
+}
+
+impl<R: Repository + Send + Sync + 'static> PromoService<R> {
+    /// Creates a new promotional credit service
+    pub fn new(repository: Arc<R>, balance_service: Arc<BalanceService<R>>) -> Self {
+        Self {
+            repository,
+            balance_service,
+        }
+    }
+    
+    /// Removes expired promotional credit every `interval` in the background
+    pub fn start_schedule(self: &Arc<Self>, interval: Duration) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.expire_promo_credits().await {
+                    error!("Failed to expire promotional credit: {}", e);
+                }
+            }
+        });
+        
+        info!("Expiring promotional credit every {} seconds", interval.as_secs());
+    }
+    
+    /// Grants promotional credit to a user until it expires
+    pub async fn grant_promo_credit(
+        &self,
+        user_id: Uuid,
+        amount: Decimal,
+        reason: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<PromoCredit> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
+        if expires_at <= Utc::now() {
+            return Err(anyhow!("Promotional credit must expire in the future"));
+        }
+        
+        let credit = PromoCredit::new(user_id, amount, reason, expires_at);
+        self.repository.save_promo_credit(&credit).await?;
+        self.balance_service.add_promo_credit(user_id, amount, credit.credit_id, expires_at).await?;
+        
+        info!("Granted {} of promotional credit to user {} until {}", amount, user_id, expires_at);
+        Ok(credit)
+    }
+    
+    /// Removes every user's expired promotional credit, returning how much was removed
+    pub async fn expire_promo_credits(&self) -> Result<Decimal> {
+        let now = Utc::now();
+        let mut expired = Decimal::ZERO;
+        
+        for balance in self.repository.get_all_user_balances().await? {
+            if balance.promo_credits.iter().any(|holding| holding.expires_at <= now) {
+                expired += self.balance_service.expire_promo_credit(balance.user_id).await?;
+            }
+        }
+        
+        Ok(expired)
+    }
+    
+    /// Gets the promotional credit granted to a user, newest first
+    pub async fn get_promo_credits_for_user(&self, user_id: Uuid) -> Result<Vec<PromoCredit>> {
+        self.repository.get_promo_credits_for_user(user_id).await
+    }
+}
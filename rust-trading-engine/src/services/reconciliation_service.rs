@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use log::{error, info, warn};
+use rust_decimal::Decimal;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::{
+    Discrepancy, DiscrepancyKind, LedgerAccountType, Market, MarketStatus, OrderSide, OutcomeSide,
+    ReconciliationMetrics, ReconciliationReport, UserBalance,
+};
+use crate::db::connection::Repository;
+
+/// How long to wait before checking a user with discrepancies again
+///
+/// Orders and balances are saved separately, so a user caught in the middle of
+/// an update can look out of balance for a moment.
+const RECHECK_DELAY: Duration = Duration::from_millis(500);
+
+/// Service that rebuilds balances and positions from their history and reports where they differ
+///
+/// For each user it checks that:
+/// - the reserved balance equals what their active orders reserve, plus the
+///   collateral held for shares they sold short in unsettled markets and the
+///   market maker pools they fund
+/// - the available balance equals the sum of their balance transactions
+/// - each position equals the sum of their trades in it, or zero once the market
+///   has settled
+pub struct ReconciliationService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Held while a run is in progress so runs never overlap
+    running: Mutex<()>,
+    
+    /// Report of the last finished run
+    last_report: RwLock<Option<ReconciliationReport>>,
+    
+    /// Counters across all runs
+    metrics: RwLock<ReconciliationMetrics>,
+}
+
+/// Markets a run has looked up, with the house account of each one's market maker
+type MarketCache = HashMap<String, (Market, Option<Uuid>)>;
+
+impl<R: Repository + Send + Sync + 'static> ReconciliationService<R> {
+    /// Creates a new reconciliation service
+    pub fn new(repository: Arc<R>) -> Self {
+        Self {
+            repository,
+            running: Mutex::new(()),
+            last_report: RwLock::new(None),
+            metrics: RwLock::new(ReconciliationMetrics::default()),
+        }
+    }
+    
+    /// Runs reconciliation every `interval` in the background
+    pub fn start_schedule(self: &Arc<Self>, interval: Duration) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            
+            // The first tick completes immediately; wait a full interval before the first run
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.run(false).await {
+                    error!("Scheduled reconciliation failed: {}", e);
+                }
+            }
+        });
+        
+        info!("Scheduled reconciliation every {} seconds", interval.as_secs());
+    }
+    
+    /// Reconciles every user and records the report
+    pub async fn run(&self, on_demand: bool) -> Result<ReconciliationReport> {
+        let _running = self.running.try_lock()
+            .map_err(|_| anyhow!("A reconciliation run is already in progress"))?;
+        
+        let started_at = Utc::now();
+        let result = self.reconcile_all().await;
+        let finished_at = Utc::now();
+        
+        let mut metrics = self.metrics.write().await;
+        let (users_checked, discrepancies) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                metrics.runs_failed += 1;
+                metrics.last_error = Some(e.to_string());
+                return Err(e);
+            }
+        };
+        
+        let report = ReconciliationReport {
+            run_id: Uuid::new_v4(),
+            on_demand,
+            users_checked,
+            discrepancies,
+            started_at,
+            finished_at,
+        };
+        
+        metrics.runs_completed += 1;
+        metrics.discrepancies_found += report.discrepancies.len() as u64;
+        metrics.last_users_checked = report.users_checked;
+        metrics.last_discrepancies = report.counts_by_kind();
+        metrics.last_duration_ms = (finished_at - started_at).num_milliseconds();
+        metrics.last_run_at = Some(finished_at);
+        drop(metrics);
+        
+        if report.is_reconciled() {
+            info!("Reconciliation {} passed for {} users", report.run_id, report.users_checked);
+        } else {
+            for discrepancy in &report.discrepancies {
+                warn!(
+                    "Reconciliation {} found {:?} discrepancy for user {}: expected {}, found {}",
+                    report.run_id, discrepancy.kind, discrepancy.user_id, discrepancy.expected, discrepancy.actual
+                );
+            }
+        }
+        
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+    
+    /// Gets the report of the last finished run
+    pub async fn get_last_report(&self) -> Result<ReconciliationReport> {
+        self.last_report.read().await.clone()
+            .ok_or_else(|| anyhow!("Reconciliation has not run yet"))
+    }
+    
+    /// Gets counters across all runs
+    pub async fn get_metrics(&self) -> ReconciliationMetrics {
+        self.metrics.read().await.clone()
+    }
+    
+    /// Reconciles every user, returning how many were checked and what differs
+    async fn reconcile_all(&self) -> Result<(usize, Vec<Discrepancy>)> {
+        let balances = self.repository.get_all_user_balances().await?;
+        let mut markets = MarketCache::new();
+        let mut discrepancies = Vec::new();
+        
+        for balance in &balances {
+            let mut found = self.reconcile_user(balance, &mut markets).await?;
+            
+            // Only report what is still there on a second look
+            if !found.is_empty() {
+                time::sleep(RECHECK_DELAY).await;
+                let balance = self.repository.get_user_balance(balance.user_id).await?;
+                found = self.reconcile_user(&balance, &mut markets).await?;
+            }
+            discrepancies.extend(found);
+        }
+        
+        Ok((balances.len(), discrepancies))
+    }
+    
+    /// Rebuilds one user's balances and positions and compares them with the records
+    async fn reconcile_user(&self, balance: &UserBalance, markets: &mut MarketCache) -> Result<Vec<Discrepancy>> {
+        let user_id = balance.user_id;
+        let mut discrepancies = Vec::new();
+        
+        // Reserved funds back active orders, short sales until their market settles, and market maker pools
+        let mut reserved = Decimal::ZERO;
+        for order in self.repository.get_all_orders_for_user(user_id).await? {
+            if order.is_active() {
+                reserved += order.reserve_amount();
+            }
+            
+            // The house account's fills against its own market maker are paid from the pool
+            let (market, house_account_id) = self.get_market(&order.market_id, markets).await?;
+            if order.side == OrderSide::Sell && !is_settled(market) && *house_account_id != Some(user_id) {
+                reserved += Decimal::from(order.short_filled_quantity());
+            }
+        }
+        for held in self.repository.get_market_reserves_for_user(user_id).await? {
+            if held.account.account_type == LedgerAccountType::AmmSubsidy {
+                reserved += held.balance;
+            }
+        }
+        if reserved != balance.reserved_balance {
+            discrepancies.push(Discrepancy::balance(DiscrepancyKind::ReservedBalance, user_id, reserved, balance.reserved_balance));
+        }
+        
+        // Available funds are the sum of every change transactions made to them
+        let available: Decimal = self.repository.get_balance_transactions_for_user(user_id).await?
+            .iter()
+            .map(|transaction| transaction.available_change)
+            .sum();
+        if available != balance.available_balance {
+            discrepancies.push(Discrepancy::balance(DiscrepancyKind::AvailableBalance, user_id, available, balance.available_balance));
+        }
+        
+        // Positions are the shares bought less the shares sold, until the market settles
+        let mut positions: BTreeMap<(String, OutcomeSide), (i64, i64)> = BTreeMap::new();
+        for trade in self.repository.get_trades_for_user(user_id).await? {
+            let (market, _) = self.get_market(&trade.market_id, markets).await?;
+            if is_settled(market) {
+                continue;
+            }
+            
+            let quantity = trade.quantity as i64;
+            let held = &mut positions.entry((trade.market_id.clone(), trade.outcome)).or_default().0;
+            if trade.buyer_id == user_id {
+                *held += quantity;
+            }
+            if trade.seller_id == user_id {
+                *held -= quantity;
+            }
+        }
+        for position in self.repository.get_positions_for_user(user_id).await? {
+            positions.entry((position.market_id, position.market_option_id)).or_default().1 = position.quantity;
+        }
+        for ((market_id, outcome), (expected, actual)) in positions {
+            if expected != actual {
+                discrepancies.push(Discrepancy::position(user_id, market_id, outcome, expected, actual));
+            }
+        }
+        
+        Ok(discrepancies)
+    }
+    
+    /// Gets a market and its market maker's house account, looking each market up once per run
+    async fn get_market<'a>(&self, market_id: &str, markets: &'a mut MarketCache) -> Result<&'a (Market, Option<Uuid>)> {
+        if !markets.contains_key(market_id) {
+            let market = self.repository.get_market(market_id).await?;
+            let house_account_id = self.repository.get_market_maker(market_id).await?
+                .map(|market_maker| market_maker.house_account_id);
+            markets.insert(market_id.to_string(), (market, house_account_id));
+        }
+        Ok(&markets[market_id])
+    }
+}
+
+/// Checks if a market has settled, so it no longer holds collateral or positions
+fn is_settled(market: &Market) -> bool {
+    market.is_resolved() || market.status == MarketStatus::Cancelled
+}
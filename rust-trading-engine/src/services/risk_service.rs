@@ -0,0 +1,249 @@
+use std::sync::Arc;
+use log::{info, warn};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::order::{Order, OrderSide};
+use crate::models::position::Position;
+use crate::models::risk::{RiskLimits, RiskRejection, RiskTier};
+use crate::db::connection::Repository;
+
+/// Service checking orders against pre-trade risk limits
+///
+/// Each user is held to the limits of their tier, tightened by any limits set
+/// for the market they are trading. Positions count at their entry price and
+/// open orders at their limit price. An order that can only shrink the user's
+/// position in its outcome, even once their other open orders on the same side
+/// of it fill, is held to the open order limit alone, so users can always trade
+/// out of risk.
+pub struct RiskService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+}
+
+impl<R: Repository> RiskService<R> {
+    /// Creates a new risk service
+    pub fn new(repository: Arc<R>) -> Self {
+        Self { repository }
+    }
+    
+    /// Checks an order against the limits for its user and market, returning why it is turned away if it is
+    pub async fn check_order(&self, order: &Order) -> Result<Option<RiskRejection>> {
+        let limits = self.get_limits(order.user_id, &order.market_id).await?;
+        let open_orders: Vec<Order> = self.repository.get_all_orders_for_user(order.user_id).await?
+            .into_iter()
+            .filter(|open| open.is_active() && open.order_id != order.order_id)
+            .collect();
+        let positions = self.repository.get_positions_for_user(order.user_id).await?;
+        let rejection = find_breach(order, &limits, &positions, &open_orders);
+        
+        if let Some(rejection) = &rejection {
+            warn!("Rejected order {} from user {}: {}", order.order_id, order.user_id, rejection);
+        }
+        Ok(rejection)
+    }
+    
+    /// Gets the limits a user is held to in a market: their tier's, tightened by the market's
+    pub async fn get_limits(&self, user_id: Uuid, market_id: &str) -> Result<RiskLimits> {
+        let tier = self.get_user_tier(user_id).await?;
+        let limits = match self.repository.get_market_risk_limits(market_id).await? {
+            Some(market_limits) => tier.limits.tightest(&market_limits),
+            None => tier.limits,
+        };
+        Ok(limits)
+    }
+    
+    /// Gets the tier a user is assigned to, or the default tier
+    pub async fn get_user_tier(&self, user_id: Uuid) -> Result<RiskTier> {
+        let name = self.repository.get_user_risk_tier(user_id).await?
+            .unwrap_or_else(|| RiskTier::DEFAULT.to_string());
+        
+        // Without the tier in the database, nothing is limited
+        Ok(self.repository.get_risk_tier(&name).await?
+            .unwrap_or(RiskTier { name, limits: RiskLimits::default() }))
+    }
+    
+    /// Gets every risk tier
+    pub async fn get_tiers(&self) -> Result<Vec<RiskTier>> {
+        self.repository.get_risk_tiers().await
+    }
+    
+    /// Creates a risk tier or replaces its limits
+    pub async fn save_tier(&self, tier: RiskTier) -> Result<RiskTier> {
+        if tier.name.trim().is_empty() {
+            return Err(anyhow!("Tier name must not be empty"));
+        }
+        
+        self.repository.save_risk_tier(&tier).await?;
+        info!("Set limits for risk tier {}: {:?}", tier.name, tier.limits);
+        Ok(tier)
+    }
+    
+    /// Assigns a user to a risk tier
+    pub async fn assign_user_tier(&self, user_id: Uuid, tier: &str) -> Result<RiskTier> {
+        let tier = self.repository.get_risk_tier(tier).await?
+            .ok_or_else(|| anyhow!("Risk tier {} not found", tier))?;
+        
+        self.repository.save_user_risk_tier(user_id, &tier.name).await?;
+        info!("Assigned user {} to risk tier {}", user_id, tier.name);
+        Ok(tier)
+    }
+    
+    /// Sets the limits for every user trading a market, on top of their tier's
+    pub async fn set_market_limits(&self, market_id: &str, limits: RiskLimits) -> Result<RiskLimits> {
+        self.repository.get_market(market_id).await
+            .map_err(|e| anyhow!("Market {} not found: {}", market_id, e))?;
+        
+        self.repository.save_market_risk_limits(market_id, &limits).await?;
+        info!("Set risk limits for market {}: {:?}", market_id, limits);
+        Ok(limits)
+    }
+}
+
+/// Finds the first limit an order would break, given the user's positions and their other open orders
+fn find_breach(order: &Order, limits: &RiskLimits, positions: &[Position], open_orders: &[Order]) -> Option<RiskRejection> {
+    if let Some(limit) = limits.max_open_orders {
+        let count = open_orders.len() as u32;
+        if count >= limit {
+            return Some(RiskRejection::MaxOpenOrders { user_id: order.user_id, open_orders: count, limit });
+        }
+    }
+    
+    let position = positions.iter()
+        .find(|p| p.market_id == order.market_id && p.market_option_id == order.outcome)
+        .map(|p| p.quantity)
+        .unwrap_or(0);
+    
+    // Assume the order fills in full, after the user's other open orders on the same side of the outcome
+    let pending: i64 = open_orders.iter()
+        .filter(|open| open.market_id == order.market_id && open.outcome == order.outcome && open.side == order.side)
+        .map(|open| i64::from(open.remaining_quantity))
+        .sum();
+    let change = i64::from(order.quantity) + pending;
+    let resulting = match order.side {
+        OrderSide::Buy => position + change,
+        OrderSide::Sell => position - change,
+    };
+    
+    // Only an order that leaves the position smaller on the same side trades out of risk
+    if resulting.signum() * position.signum() >= 0 && resulting.abs() <= position.abs() {
+        return None;
+    }
+    
+    let value = order.price * Decimal::from(order.quantity);
+    if let Some(limit) = limits.max_order_value {
+        if value > limit {
+            return Some(RiskRejection::MaxOrderValue { value, limit });
+        }
+    }
+    
+    if let Some(limit) = limits.max_position {
+        if resulting.abs() > limit {
+            return Some(RiskRejection::MaxPosition { market_id: order.market_id.clone(), position: resulting, limit });
+        }
+    }
+    
+    let mut market_notional = value;
+    let mut total_exposure = value;
+    for p in positions {
+        let committed = Decimal::from(p.quantity.abs()) * p.average_entry_price;
+        total_exposure += committed;
+        if p.market_id == order.market_id {
+            market_notional += committed;
+        }
+    }
+    for open in open_orders {
+        let committed = open.price * Decimal::from(open.remaining_quantity);
+        total_exposure += committed;
+        if open.market_id == order.market_id {
+            market_notional += committed;
+        }
+    }
+    
+    if let Some(limit) = limits.max_market_notional {
+        if market_notional > limit {
+            return Some(RiskRejection::MaxMarketNotional {
+                market_id: order.market_id.clone(),
+                notional: market_notional,
+                limit,
+            });
+        }
+    }
+    if let Some(limit) = limits.max_total_exposure {
+        if total_exposure > limit {
+            return Some(RiskRejection::MaxTotalExposure { exposure: total_exposure, limit });
+        }
+    }
+    
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OutcomeSide;
+    
+    fn order(side: OrderSide, price: &str, quantity: u32) -> Order {
+        Order::new(Uuid::nil(), "market".to_string(), side, OutcomeSide::YES, price.parse().unwrap(), quantity)
+    }
+    
+    fn position(quantity: i64, average_entry_price: &str) -> Position {
+        let mut position = Position::new(Uuid::nil(), "market".to_string(), OutcomeSide::YES);
+        position.quantity = quantity;
+        position.average_entry_price = average_entry_price.parse().unwrap();
+        position
+    }
+    
+    fn position_limit(limit: i64) -> RiskLimits {
+        RiskLimits { max_position: Some(limit), ..RiskLimits::default() }
+    }
+    
+    #[test]
+    fn selling_down_a_long_position_skips_the_position_limit() {
+        let rejection = find_breach(&order(OrderSide::Sell, "0.5", 4), &position_limit(5), &[position(10, "0.5")], &[]);
+        assert_eq!(rejection, None);
+    }
+    
+    #[test]
+    fn selling_through_a_long_position_is_held_to_the_position_limit() {
+        let rejection = find_breach(&order(OrderSide::Sell, "0.5", 15), &position_limit(4), &[position(10, "0.5")], &[]);
+        assert_eq!(rejection, Some(RiskRejection::MaxPosition { market_id: "market".to_string(), position: -5, limit: 4 }));
+    }
+    
+    #[test]
+    fn open_orders_on_the_same_side_count_towards_the_resulting_position() {
+        let open = order(OrderSide::Sell, "0.5", 8);
+        let rejection = find_breach(&order(OrderSide::Sell, "0.5", 4), &position_limit(1), &[position(10, "0.5")], &[open]);
+        assert_eq!(rejection, Some(RiskRejection::MaxPosition { market_id: "market".to_string(), position: -2, limit: 1 }));
+    }
+    
+    #[test]
+    fn open_orders_on_the_other_side_do_not_count_towards_the_resulting_position() {
+        let open = order(OrderSide::Buy, "0.5", 8);
+        let rejection = find_breach(&order(OrderSide::Sell, "0.5", 4), &position_limit(1), &[position(10, "0.5")], &[open]);
+        assert_eq!(rejection, None);
+    }
+    
+    #[test]
+    fn the_open_order_limit_applies_to_orders_that_reduce_risk() {
+        let limits = RiskLimits { max_open_orders: Some(1), ..RiskLimits::default() };
+        let open = order(OrderSide::Buy, "0.5", 8);
+        let rejection = find_breach(&order(OrderSide::Sell, "0.5", 4), &limits, &[position(10, "0.5")], &[open]);
+        assert_eq!(rejection, Some(RiskRejection::MaxOpenOrders { user_id: Uuid::nil(), open_orders: 1, limit: 1 }));
+    }
+    
+    #[test]
+    fn exposure_counts_positions_at_entry_price_and_open_orders_at_limit_price() {
+        let limits = RiskLimits { max_total_exposure: Some(Decimal::from(10)), ..RiskLimits::default() };
+        let open = order(OrderSide::Buy, "0.5", 4);
+        let positions = [position(10, "0.6")];
+        
+        // 6 held, 2 resting and 2 ordered
+        let rejection = find_breach(&order(OrderSide::Buy, "0.5", 4), &limits, &positions, std::slice::from_ref(&open));
+        assert_eq!(rejection, None);
+        
+        let rejection = find_breach(&order(OrderSide::Buy, "0.5", 6), &limits, &positions, &[open]);
+        assert_eq!(rejection, Some(RiskRejection::MaxTotalExposure { exposure: Decimal::from(11), limit: Decimal::from(10) }));
+    }
+}
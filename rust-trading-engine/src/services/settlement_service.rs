@@ -1,13 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use uuid::Uuid;
 use tokio::sync::mpsc;
 use log::{info, debug, error};
 use std::sync::Arc;
 
-use crate::models::{Market, MarketStatus, OutcomeSide, Trade, Order, OrderStatus};
+use crate::models::{LedgerAccountType, Market, MarketStatus, OutcomeSide, PositionSettlement, Trade, Order, SettlementEntry, SettlementReport};
 use crate::db::connection::Repository;
 use crate::services::balance_service::BalanceService;
+use crate::services::order_service::OrderService;
+use crate::services::position_service::PositionService;
+use crate::services::tax_service::TaxService;
 
 /// Service that handles market resolution and payouts
 pub struct SettlementService<R: Repository> {
@@ -17,18 +21,37 @@ pub struct SettlementService<R: Repository> {
     /// Database repository
     repository: Arc<R>,
     balance_service: Arc<BalanceService<R>>,
+    
+    /// Order service whose matching engine settlement holds while it changes markets
+    order_service: Arc<OrderService<R>>,
+    
+    /// Position service for closing positions on resolution
+    position_service: Arc<PositionService<R>>,
+    
+    /// Tax service withholding tax from payouts
+    tax_service: Arc<TaxService<R>>,
 }
 
 impl<R: Repository> SettlementService<R> {
     /// Creates a new settlement service
-    pub fn new(payout_sender: mpsc::Sender<(Uuid, Decimal)>, repository: Arc<R>, balance_service: Arc<BalanceService<R>>) -> Self {
-        Self { payout_sender, repository, balance_service }
+    pub fn new(
+        payout_sender: mpsc::Sender<(Uuid, Decimal)>,
+        repository: Arc<R>,
+        balance_service: Arc<BalanceService<R>>,
+        order_service: Arc<OrderService<R>>,
+        position_service: Arc<PositionService<R>>,
+        tax_service: Arc<TaxService<R>>
+    ) -> Self {
+        Self { payout_sender, repository, balance_service, order_service, position_service, tax_service }
     }
     
-    /// Resolves a market to a specific outcome
-    pub async fn resolve_market(&self, market_id: &str, outcome: OutcomeSide) -> Result<Market, String> {
+    /// Resolves a market to a specific outcome and settles every position in it
+    pub async fn resolve_market(&self, market_id: &str, outcome: OutcomeSide) -> Result<SettlementReport, String> {
+        // Hold the matching engine so no order fills or rests while the market settles
+        let _engine = self.order_service.lock_matching_engine().await;
+        
         // Get the market
-        let mut market = self.repository.get_market(market_id).await
+        let market = self.repository.get_market(market_id).await
             .map_err(|e| format!("Failed to get market: {}", e))?;
         
         // Check if the market is already resolved
@@ -36,78 +59,313 @@ impl<R: Repository> SettlementService<R> {
             return Err(format!("Market {} is already resolved", market_id));
         }
         
+        // Scalar markets resolve to a value rather than an outcome
+        if market.is_scalar() {
+            return Err(format!("Market {} is a scalar market and must be resolved to a value", market_id));
+        }
+        
+        // Check that the winning outcome belongs to the market
+        if !market.has_outcome(outcome) {
+            return Err(format!("Market {} has no outcome {}", market_id, outcome.index()));
+        }
+        
+        // A market whose settlement failed part way can only be finished with the same outcome
+        if let Some(resolution) = market.resolution.filter(|resolution| *resolution != outcome) {
+            return Err(format!("Market {} is being settled to outcome {}", market_id, resolution.index()));
+        }
+        
         // Check if the market is closed for trading
         if market.status != MarketStatus::Closed {
             return Err(format!("Market {} must be closed before resolving", market_id));
         }
         
-        // Set market status to resolved
+        // In a mutually exclusive group, a Yes resolution settles every sibling as No
+        let siblings = match (&market.group_id, outcome) {
+            (Some(group_id), OutcomeSide::YES) => {
+                let group = self.repository.get_market_group(group_id).await
+                    .map_err(|e| format!("Failed to get market group: {}", e))?;
+                
+                let mut siblings = Vec::new();
+                for sibling_id in group.siblings_of(market_id) {
+                    let sibling = self.repository.get_market(sibling_id).await
+                        .map_err(|e| format!("Failed to get market: {}", e))?;
+                    
+                    if sibling.resolution == Some(OutcomeSide::YES) {
+                        return Err(format!("Market {} in group {} already resolved Yes", sibling_id, group_id));
+                    }
+                    if !sibling.is_resolved() {
+                        siblings.push(sibling);
+                    }
+                }
+                siblings
+            }
+            _ => Vec::new(),
+        };
+        
+        let mut market = market;
         market.resolve(outcome);
+        let report = self.settle_market(&mut market).await?;
         
-        // Save the updated market
-        self.repository.save_market(&market).await
-            .map_err(|e| format!("Failed to save resolved market: {}", e))?;
+        let mut siblings = siblings;
+        for sibling in siblings.iter_mut() {
+            if sibling.is_open() {
+                sibling.close();
+            }
+            sibling.resolve(OutcomeSide::NO);
+            self.settle_market(sibling).await?;
+        }
         
-        // Process payouts to users with winning positions
-        self.process_payouts(market_id, outcome).await?;
+        // The market is marked resolved last, so resolving it again finishes any sibling left unsettled
+        for sibling in &siblings {
+            self.mark_resolved(sibling).await?;
+            info!("Auto-resolved market {} to No as sibling of {}", sibling.market_id, market_id);
+        }
+        self.mark_resolved(&market).await?;
         
         info!("Resolved market {} to outcome {:?}", market_id, outcome);
-        Ok(market)
+        Ok(report)
     }
     
-    /// Process all payouts for a resolved market
-    async fn process_payouts(&self, market_id: &str, winning_outcome: OutcomeSide) -> Result<(), String> {
-        // Get all trades for this market
-        let trades = self.repository.get_trades_for_market(market_id).await
+    /// Resolves a scalar market to the final value of the underlying quantity and settles every position in it
+    pub async fn resolve_scalar_market(&self, market_id: &str, value: Decimal) -> Result<SettlementReport, String> {
+        // Hold the matching engine so no order fills or rests while the market settles
+        let _engine = self.order_service.lock_matching_engine().await;
+        
+        // Get the market
+        let mut market = self.repository.get_market(market_id).await
+            .map_err(|e| format!("Failed to get market: {}", e))?;
+        
+        // Check that this is a scalar market
+        if !market.is_scalar() {
+            return Err(format!("Market {} is not a scalar market", market_id));
+        }
+        
+        // Check if the market is already resolved
+        if market.is_resolved() {
+            return Err(format!("Market {} is already resolved", market_id));
+        }
+        
+        // Check if the market is closed for trading
+        if market.status != MarketStatus::Closed {
+            return Err(format!("Market {} must be closed before resolving", market_id));
+        }
+        
+        // A market whose settlement failed part way can only be finished with the same value
+        if let Some(resolved_value) = market.resolved_value.filter(|resolved_value| *resolved_value != value) {
+            return Err(format!("Market {} is being settled to value {}", market_id, resolved_value));
+        }
+        
+        market.resolve_scalar(value);
+        let report = self.settle_market(&mut market).await?;
+        self.mark_resolved(&market).await?;
+        
+        info!("Resolved scalar market {} to value {}", market_id, value);
+        Ok(report)
+    }
+    
+    /// Gets the settlement report of a resolved market
+    pub async fn get_settlement_report(&self, market_id: &str) -> Result<SettlementReport, String> {
+        let market = self.repository.get_market(market_id).await
+            .map_err(|e| format!("Failed to get market: {}", e))?;
+        
+        if !market.is_resolved() {
+            return Err(format!("Market {} has not been resolved", market_id));
+        }
+        
+        let entries = self.repository.get_settlement_entries(market_id).await
+            .map_err(|e| format!("Failed to get settlement entries: {}", e))?;
+        
+        Ok(SettlementReport {
+            market_id: market.market_id,
+            resolution: market.resolution,
+            resolved_value: market.resolved_value,
+            entries,
+            settled_at: market.resolved_at.unwrap_or(market.updated_at),
+        })
+    }
+    
+    /// Settles every position in a resolved market and records the settlement report
+    ///
+    /// The market is saved as still closed with its resolution, so that if settlement
+    /// fails part way it can be resolved again to the same outcome to settle the
+    /// rest; the caller marks it resolved once it is settled. The caller holds the
+    /// matching engine.
+    async fn settle_market(&self, market: &mut Market) -> Result<SettlementReport, String> {
+        let market_id = market.market_id.clone();
+        
+        // Orders still resting on the book will never fill
+        self.cancel_resting_orders(market).await?;
+        
+        // Record the resolution with the emptied book before paying anything
+        let mut settling = market.clone();
+        settling.status = MarketStatus::Closed;
+        self.repository.save_market(&settling).await
+            .map_err(|e| format!("Failed to save resolution of market: {}", e))?;
+        self.order_service.invalidate_cached_market(&market_id).await;
+        
+        let trades = self.repository.get_trades_for_market(&market_id).await
             .map_err(|e| format!("Failed to get trades for market: {}", e))?;
         
-        // Process payouts for each trade
-        let mut processed_users = std::collections::HashSet::new();
+        let settled_at = market.resolved_at.unwrap_or_else(Utc::now);
+        let mut entries = Self::calculate_payouts(market, &trades)?;
+        self.settle_entries(market, &mut entries, settled_at).await?;
         
-        for trade in trades {
-            // Check if trade outcome matches winning outcome
-            if trade.outcome != winning_outcome {
+        let report = SettlementReport {
+            market_id: market_id.clone(),
+            resolution: market.resolution,
+            resolved_value: market.resolved_value,
+            entries,
+            settled_at,
+        };
+        
+        // Saves the entries not already saved as each user was settled
+        self.repository.save_settlement_report(&report).await
+            .map_err(|e| format!("Failed to save settlement report: {}", e))?;
+        
+        self.position_service.settle_market(market).await
+            .map_err(|e| format!("Failed to close positions: {}", e))?;
+        
+        self.check_escrow_settled(&market_id).await?;
+        
+        info!("Settled market {}: paid out {} to {} accounts", market_id, report.total_payout(), report.entries.len());
+        Ok(report)
+    }
+    
+    /// Saves a settled market as resolved
+    async fn mark_resolved(&self, market: &Market) -> Result<(), String> {
+        self.repository.save_market(market).await
+            .map_err(|e| format!("Failed to save resolved market: {}", e))?;
+        self.order_service.invalidate_cached_market(&market.market_id).await;
+        Ok(())
+    }
+    
+    /// Pays out or collects each entry's payout and releases the collateral the market holds
+    ///
+    /// Everything owed to the market is collected before anything is paid out, so
+    /// payouts never draw on escrow the market does not yet hold. Each entry is
+    /// saved with the funds it moves, and users whose entries are already saved
+    /// are skipped, so settling a market again carries on where a failure stopped it.
+    async fn settle_entries(&self, market: &Market, entries: &mut Vec<SettlementEntry>, settled_at: DateTime<Utc>) -> Result<(), String> {
+        let market_id = &market.market_id;
+        
+        let settled: HashSet<Uuid> = self.repository.get_settlement_entries(market_id).await
+            .map_err(|e| format!("Failed to get settlement entries: {}", e))?
+            .into_iter()
+            .map(|entry| entry.user_id)
+            .collect();
+        
+        // The market maker's pool settles as the house account's collateral
+        let market_maker = self.repository.get_market_maker(market_id).await
+            .map_err(|e| format!("Failed to get market maker: {}", e))?;
+        if let Some(market_maker) = &market_maker {
+            self.balance_service.release_market_maker_pool(market_maker.house_account_id, market_id).await
+                .map_err(|e| format!("Failed to release market maker pool: {}", e))?;
+        }
+        let house_account_id = market_maker.map(|market_maker| market_maker.house_account_id);
+        
+        let collateral = self.get_collateral(market_id).await?;
+        
+        // Accounts holding collateral but no trades still need it released
+        for user_id in collateral.keys() {
+            if !entries.iter().any(|entry| entry.user_id == *user_id) {
+                entries.push(SettlementEntry::new(*user_id, market.num_outcomes()));
+            }
+        }
+        
+        let mut ordered: Vec<&SettlementEntry> = entries.iter().collect();
+        ordered.sort_by_key(|entry| entry.payout > Decimal::ZERO);
+        
+        for entry in ordered {
+            // The issuer's short side of complete sets is paid from the units locked to mint them
+            if entry.user_id == Market::COMPLETE_SET_ISSUER_ID || settled.contains(&entry.user_id) {
                 continue;
             }
             
-            // Process buyer payout if they haven't been processed yet
-            if !processed_users.contains(&trade.buyer_id) {
-                // For winning buyers, they get their invested amount plus profit
-                let payout_amount = Decimal::from(trade.quantity) * 
-                    (Decimal::ONE + (Decimal::ONE - trade.price));
-                
-                self.balance_service.process_payout(
-                    trade.buyer_id, 
-                    payout_amount, 
-                    market_id
-                ).await.map_err(|e| format!("Failed to process buyer payout: {}", e))?;
-                
-                processed_users.insert(trade.buyer_id);
-                info!("Processed payout of {} to buyer {} for market {}", payout_amount, trade.buyer_id, market_id);
+            let user_collateral = collateral.get(&entry.user_id).copied().unwrap_or(Decimal::ZERO);
+            
+            // The house's own winnings are not taxed as a user's
+            let net_winnings = if Some(entry.user_id) == house_account_id { Decimal::ZERO } else { entry.realized_pnl };
+            
+            let settlement = PositionSettlement::new(market_id.clone(), entry.clone(), settled_at);
+            self.tax_service.settle_position(settlement, user_collateral, net_winnings).await
+                .map_err(|e| format!("Failed to settle user {}: {}", entry.user_id, e))?;
+            
+            if entry.payout > Decimal::ZERO {
+                if let Err(e) = self.payout_sender.send((entry.user_id, entry.payout)).await {
+                    error!("Failed to send payout notification: {}", e);
+                }
+            }
+        }
+        
+        entries.sort_by_key(|entry| entry.user_id);
+        Ok(())
+    }
+    
+    /// Gets the collateral a market holds for each user
+    async fn get_collateral(&self, market_id: &str) -> Result<HashMap<Uuid, Decimal>, String> {
+        let balances = self.repository.get_market_ledger_balances(market_id).await
+            .map_err(|e| format!("Failed to get ledger balances for market: {}", e))?;
+        
+        let mut collateral = HashMap::new();
+        for balance in balances {
+            if balance.account.account_type != LedgerAccountType::MarketCollateral || balance.balance <= Decimal::ZERO {
+                continue;
             }
             
-            // Process seller payout if they haven't been processed yet
-            if !processed_users.contains(&trade.seller_id) {
-                // For winning sellers, they get back the quantity they sold
-                let payout_amount = Decimal::from(trade.quantity);
-                
-                self.balance_service.process_payout(
-                    trade.seller_id, 
-                    payout_amount, 
-                    market_id
-                ).await.map_err(|e| format!("Failed to process seller payout: {}", e))?;
-                
-                processed_users.insert(trade.seller_id);
-                info!("Processed payout of {} to seller {} for market {}", payout_amount, trade.seller_id, market_id);
+            let holder_id = balance.account.holder_id.as_deref().unwrap_or_default();
+            let user_id = Uuid::parse_str(holder_id)
+                .map_err(|e| format!("Invalid collateral holder {}: {}", holder_id, e))?;
+            collateral.insert(user_id, balance.balance);
+        }
+        
+        Ok(collateral)
+    }
+    
+    /// Checks that a settled market holds nothing: every unit paid in has been paid out
+    async fn check_escrow_settled(&self, market_id: &str) -> Result<(), String> {
+        let balances = self.repository.get_market_ledger_balances(market_id).await
+            .map_err(|e| format!("Failed to get ledger balances for market: {}", e))?;
+        
+        let held: Decimal = balances.iter().map(|balance| balance.balance).sum();
+        if !held.is_zero() {
+            error!("Market {} still holds {} after settlement", market_id, held);
+            return Err(format!("Market {} still holds {} after settlement", market_id, held));
+        }
+        
+        debug!("Escrow for market {} is settled", market_id);
+        Ok(())
+    }
+    
+    /// Cancels the orders still resting on a market's book, removes them from it and releases their reserves
+    ///
+    /// The caller saves the market afterwards.
+    async fn cancel_resting_orders(&self, market: &mut Market) -> Result<(), String> {
+        let orders: Vec<Order> = market.order_book.all_orders().cloned().collect();
+        
+        for mut order in orders {
+            market.order_book.remove_order(order.order_id);
+            let reserved_amount = order.reserve_amount();
+            
+            order.cancel();
+            self.repository.save_order(&order).await
+                .map_err(|e| format!("Failed to update order status: {}", e))?;
+            
+            if reserved_amount > Decimal::ZERO {
+                self.balance_service.release_funds(order.user_id, &market.market_id, reserved_amount, order.order_id).await
+                    .map_err(|e| format!("Failed to release funds for order {}: {}", order.order_id, e))?;
             }
+            
+            debug!("Cancelled resting order {} on settlement of market {}", order.order_id, market.market_id);
         }
         
-        info!("Completed all payouts for market {}", market_id);
         Ok(())
     }
     
     /// Closes trading for a market
     pub async fn close_market(&self, market_id: &str) -> Result<Market, String> {
+        // Hold the matching engine so no order fills against the market while it closes
+        let _engine = self.order_service.lock_matching_engine().await;
+        
         // Get the market
         let mut market = self.repository.get_market(market_id).await
             .map_err(|e| format!("Failed to get market: {}", e))?;
@@ -123,6 +381,7 @@ impl<R: Repository> SettlementService<R> {
         // Save the updated market
         self.repository.save_market(&market).await
             .map_err(|e| format!("Failed to save closed market: {}", e))?;
+        self.order_service.invalidate_cached_market(market_id).await;
         
         info!("Closed market {}", market_id);
         Ok(market)
@@ -130,12 +389,15 @@ impl<R: Repository> SettlementService<R> {
     
     /// Cancels a market and refunds all participants
     pub async fn cancel_market(&self, market_id: &str) -> Result<Market, String> {
+        // Hold the matching engine so no order fills against the market while it is refunded
+        let _engine = self.order_service.lock_matching_engine().await;
+        
         // Get the market
         let mut market = self.repository.get_market(market_id).await
             .map_err(|e| format!("Failed to get market: {}", e))?;
         
         // Check if the market is already resolved
-        if market.is_resolved() {
+        if market.is_resolved() || market.is_settling() {
             return Err(format!("Market {} is already resolved and cannot be cancelled", market_id));
         }
         
@@ -147,106 +409,102 @@ impl<R: Repository> SettlementService<R> {
             .map_err(|e| format!("Database error saving cancelled market: {}", e))?;
         
         // Process refunds to all participants
-        self.process_market_cancellation_refunds(market_id).await?;
+        self.refund_cancelled_market(market_id).await?;
         
         info!("Market {} cancelled and refunds processed", market_id);
         Ok(market)
     }
     
     /// Process refunds for a cancelled market
-    async fn process_market_cancellation_refunds(&self, market_id: &str) -> Result<(), String> {
-        // Get all active orders for this market
-        let market = self.repository.get_market(market_id).await
+    ///
+    /// Resting orders are cancelled and every trade is unwound: each account gets
+    /// back the net cash it paid into the market, or pays back the net cash it
+    /// received, so the market's escrow ends up empty.
+    pub async fn process_market_cancellation_refunds(&self, market_id: &str) -> Result<(), String> {
+        // Hold the matching engine so no order fills against the market while it is refunded
+        let _engine = self.order_service.lock_matching_engine().await;
+        self.refund_cancelled_market(market_id).await
+    }
+    
+    /// Refunds a cancelled market while the caller holds the matching engine
+    async fn refund_cancelled_market(&self, market_id: &str) -> Result<(), String> {
+        let mut market = self.repository.get_market(market_id).await
             .map_err(|e| format!("Failed to get market: {}", e))?;
         
-        // Get orders from the order book (only active ones)
-        let orders: Vec<Order> = market.order_book.yes_bids.values()
-            .flat_map(|orders| orders.iter().cloned())
-            .chain(market.order_book.yes_asks.values().flat_map(|orders| orders.iter().cloned()))
-            .chain(market.order_book.no_bids.values().flat_map(|orders| orders.iter().cloned()))
-            .chain(market.order_book.no_asks.values().flat_map(|orders| orders.iter().cloned()))
-            .collect();
+        info!("Processing refunds for {} orders in cancelled market {}", market.order_book.all_orders().count(), market_id);
+        self.cancel_resting_orders(&mut market).await?;
         
-        info!("Processing refunds for {} orders in cancelled market {}", orders.len(), market_id);
+        self.repository.save_market(&market).await
+            .map_err(|e| format!("Failed to save cancelled market: {}", e))?;
+        self.order_service.invalidate_cached_market(market_id).await;
         
-        for order in orders {
-            // Only process orders that still have remaining quantities
-            if order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled {
-                let _remaining_ratio = Decimal::from(order.remaining_quantity) / Decimal::from(order.quantity);
-                
-                // Different refund logic based on order side
-                match order.side {
-                    // For buy orders, refund price * remaining quantity
-                    crate::models::OrderSide::Buy => {
-                        let refund_amount = order.price * Decimal::from(order.remaining_quantity);
-                        
-                        if refund_amount > Decimal::ZERO {
-                            self.balance_service.process_payout(
-                                order.user_id,
-                                refund_amount,
-                                market_id
-                            ).await.map_err(|e| format!("Failed to process buy refund: {}", e))?;
-                            
-                            info!("Refunded {} to buyer {} for cancelled order {} in market {}", 
-                                refund_amount, order.user_id, order.order_id, market_id);
-                        }
-                    },
-                    
-                    // For sell orders, refund the remaining quantity
-                    crate::models::OrderSide::Sell => {
-                        let refund_amount = Decimal::from(order.remaining_quantity);
-                        
-                        if refund_amount > Decimal::ZERO {
-                            self.balance_service.process_payout(
-                                order.user_id,
-                                refund_amount,
-                                market_id
-                            ).await.map_err(|e| format!("Failed to process sell refund: {}", e))?;
-                            
-                            info!("Refunded {} to seller {} for cancelled order {} in market {}", 
-                                refund_amount, order.user_id, order.order_id, market_id);
-                        }
-                    }
-                }
-                
-                // Update order status to Cancelled in database
-                let mut cancelled_order = order.clone();
-                cancelled_order.status = OrderStatus::Cancelled;
-                self.repository.save_order(&cancelled_order).await
-                    .map_err(|e| format!("Failed to update order status: {}", e))?;
-            }
+        let trades = self.repository.get_trades_for_market(market_id).await
+            .map_err(|e| format!("Failed to get trades for market: {}", e))?;
+        
+        let mut entries = Self::calculate_net_positions(&market, &trades)?;
+        for entry in entries.iter_mut() {
+            entry.payout = entry.cost_basis;
         }
+        self.settle_entries(&market, &mut entries, Utc::now()).await?;
+        
+        self.position_service.void_market(market_id).await
+            .map_err(|e| format!("Failed to close positions: {}", e))?;
+        
+        self.check_escrow_settled(market_id).await?;
         
         info!("Completed all refunds for cancelled market {}", market_id);
         Ok(())
     }
     
-    /// Calculates payouts for all users in a market
-    async fn calculate_payouts(&self, market: &Market, outcome: OutcomeSide) -> Result<HashMap<Uuid, Decimal>, String> {
-        let mut payouts: HashMap<Uuid, Decimal> = HashMap::new();
-        
-        // Get all trades for this market from the database
-        let trades = match self.repository.get_trades_for_market(&market.market_id).await {
-            Ok(trades) => trades,
-            Err(e) => {
-                error!("Database error getting trades: {}", e);
-                return Err(format!("Database error: {}", e));
+    /// Calculates each user's net positions, cost basis and payout in a resolved market
+    ///
+    /// Every share traded is held long by its buyer and short by its seller, so the
+    /// payouts across all users sum to zero: each winning share is paid out exactly
+    /// once, funded by the accounts short that share.
+    fn calculate_payouts(market: &Market, trades: &[Trade]) -> Result<Vec<SettlementEntry>, String> {
+        let mut entries = Self::calculate_net_positions(market, trades)?;
+        for entry in entries.iter_mut() {
+            let mut payout = Decimal::ZERO;
+            for (index, position) in entry.positions.iter().enumerate() {
+                let per_share = market.payout_per_share(OutcomeSide::new(index as u32))
+                    .ok_or_else(|| format!("Market {} has no payout for outcome {}", market.market_id, index))?;
+                payout += Decimal::from(*position) * per_share;
             }
-        };
+            
+            entry.payout = payout;
+            entry.realized_pnl = payout - entry.cost_basis;
+        }
+        
+        Ok(entries)
+    }
+    
+    /// Calculates each user's net positions and cost basis in a market from its trades
+    fn calculate_net_positions(market: &Market, trades: &[Trade]) -> Result<Vec<SettlementEntry>, String> {
+        let num_outcomes = market.num_outcomes();
+        let mut entries: HashMap<Uuid, SettlementEntry> = HashMap::new();
         
         for trade in trades {
-            let (yes_user_id, yes_payout, no_user_id, no_payout) = trade.calculate_payout(outcome);
-            
-            // Add payouts to the respective users
-            if yes_payout > Decimal::ZERO {
-                *payouts.entry(yes_user_id).or_insert(Decimal::ZERO) += yes_payout;
+            if !market.has_outcome(trade.outcome) {
+                return Err(format!("Trade {} is for unknown outcome {}", trade.trade_id, trade.outcome.index()));
             }
             
-            if no_payout > Decimal::ZERO {
-                *payouts.entry(no_user_id).or_insert(Decimal::ZERO) += no_payout;
-            }
+            let index = trade.outcome.index();
+            let quantity = trade.quantity as i64;
+            let cost = trade.price * Decimal::from(trade.quantity);
+            
+            let buyer = entries.entry(trade.buyer_id)
+                .or_insert_with(|| SettlementEntry::new(trade.buyer_id, num_outcomes));
+            buyer.positions[index] += quantity;
+            buyer.cost_basis += cost;
+            
+            let seller = entries.entry(trade.seller_id)
+                .or_insert_with(|| SettlementEntry::new(trade.seller_id, num_outcomes));
+            seller.positions[index] -= quantity;
+            seller.cost_basis -= cost;
         }
         
-        Ok(payouts)
+        let mut entries: Vec<SettlementEntry> = entries.into_values().collect();
+        entries.sort_by_key(|entry| entry.user_id);
+        Ok(entries)
     }
-} 
+}
\ No newline at end of file
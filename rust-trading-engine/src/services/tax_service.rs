@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use log::info;
+use rust_decimal::Decimal;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::balance::UserBalance;
+use crate::models::settlement::PositionSettlement;
+use crate::models::tax::{TaxStatement, TaxWithholding};
+use crate::services::balance_service::BalanceService;
+use crate::db::connection::Repository;
+
+/// Service withholding tax (TDS) on users' net winnings when markets pay out
+///
+/// Net winnings are counted per user and financial year: each settlement adds
+/// what it won or lost, and its payout is withheld whatever brings the tax on the
+/// year's net winnings so far up to the rate. Losses lower what later winnings are
+/// taxed on, but tax already withheld is not paid back.
+pub struct TaxService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Service paying out settlements
+    balance_service: Arc<BalanceService<R>>,
+    
+    /// Rate of tax withheld on net winnings (e.g. 0.30 for 30%)
+    rate: Decimal,
+    
+    /// Held while a settlement is taxed, so each sees the withholdings before it
+    lock: Mutex<()>,
+}
+
+impl<R: Repository> TaxService<R> {
+    /// Creates a new tax service withholding at a rate
+    pub fn new(repository: Arc<R>, balance_service: Arc<BalanceService<R>>, rate: Decimal) -> Self {
+        Self {
+            repository,
+            balance_service,
+            rate,
+            lock: Mutex::new(()),
+        }
+    }
+    
+    /// Settles a user's position in a resolved market, withholding tax from the payout
+    ///
+    /// `net_winnings` is what the settlement won the user: the payout less what
+    /// they paid into the market, negative when they lost. The withholding is saved
+    /// with the settlement.
+    pub async fn settle_position(
+        &self,
+        mut settlement: PositionSettlement,
+        collateral: Decimal,
+        net_winnings: Decimal,
+    ) -> Result<UserBalance> {
+        if net_winnings.is_zero() {
+            return self.balance_service.settle_position(&settlement, collateral).await;
+        }
+        
+        let user_id = settlement.entry.user_id;
+        let payout = settlement.entry.payout;
+        
+        let _lock = self.lock.lock().await;
+        let mut withholding = TaxWithholding::new(
+            user_id,
+            settlement.market_id.clone(),
+            net_winnings,
+            Decimal::ZERO,
+            self.rate,
+            Decimal::ZERO,
+        );
+        let earlier = self.repository.get_tax_withholdings_for_user(user_id, Some(withholding.financial_year)).await?;
+        
+        let (taxable_winnings, amount) = tax_due(self.rate, &earlier, net_winnings, payout);
+        withholding.taxable_winnings = taxable_winnings;
+        withholding.amount = amount;
+        settlement.withholding = Some(withholding);
+        
+        let balance = self.balance_service.settle_position(&settlement, collateral).await?;
+        
+        if amount > Decimal::ZERO {
+            info!(
+                "Withheld {} of tax from user {} in market {} (net winnings {} this year)",
+                amount, user_id, settlement.market_id, taxable_winnings
+            );
+        }
+        Ok(balance)
+    }
+    
+    /// Gets a user's tax statement for each financial year they have settlements in, latest first
+    pub async fn get_tax_statements(&self, user_id: Uuid) -> Result<Vec<TaxStatement>> {
+        let mut years: BTreeMap<i32, Vec<TaxWithholding>> = BTreeMap::new();
+        for withholding in self.repository.get_tax_withholdings_for_user(user_id, None).await? {
+            years.entry(withholding.financial_year).or_default().push(withholding);
+        }
+        
+        Ok(years.into_iter()
+            .rev()
+            .map(|(year, withholdings)| TaxStatement::new(user_id, year, withholdings))
+            .collect())
+    }
+    
+    /// Gets a user's tax statement for the financial year starting in `year`
+    pub async fn get_tax_statement(&self, user_id: Uuid, year: i32) -> Result<TaxStatement> {
+        let withholdings = self.repository.get_tax_withholdings_for_user(user_id, Some(year)).await?;
+        Ok(TaxStatement::new(user_id, year, withholdings))
+    }
+}
+
+/// Works out the tax to withhold from a settlement's payout, given the year's earlier withholdings
+///
+/// Returns the year's net winnings including the settlement, and what is withheld:
+/// whatever the tax on those net winnings so far is short of, as far as the payout covers it.
+fn tax_due(rate: Decimal, earlier: &[TaxWithholding], net_winnings: Decimal, payout: Decimal) -> (Decimal, Decimal) {
+    let taxable_winnings = earlier.iter().map(|w| w.net_winnings).sum::<Decimal>() + net_winnings;
+    let withheld: Decimal = earlier.iter().map(|w| w.amount).sum();
+    let due = (taxable_winnings.max(Decimal::ZERO) * rate).round_dp(2);
+    let amount = (due - withheld).max(Decimal::ZERO).min(payout.max(Decimal::ZERO));
+    (taxable_winnings, amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+    
+    /// An earlier settlement this year that won `net_winnings` and had `amount` withheld
+    fn earlier(net_winnings: &str, amount: &str) -> TaxWithholding {
+        TaxWithholding::new(Uuid::nil(), "market".to_string(), dec(net_winnings), Decimal::ZERO, dec("0.3"), dec(amount))
+    }
+    
+    #[test]
+    fn the_first_win_of_the_year_is_taxed_at_the_rate() {
+        assert_eq!(tax_due(dec("0.3"), &[], dec("100"), dec("150")), (dec("100"), dec("30")));
+    }
+    
+    #[test]
+    fn earlier_losses_lower_what_a_win_is_taxed_on() {
+        let earlier = [earlier("-40", "0")];
+        assert_eq!(tax_due(dec("0.3"), &earlier, dec("100"), dec("150")), (dec("60"), dec("18")));
+    }
+    
+    #[test]
+    fn tax_already_withheld_is_not_withheld_again() {
+        let earlier = [earlier("100", "30")];
+        assert_eq!(tax_due(dec("0.3"), &earlier, dec("50"), dec("80")), (dec("150"), dec("15")));
+    }
+    
+    #[test]
+    fn a_loss_after_tax_was_withheld_pays_nothing_back() {
+        let earlier = [earlier("100", "30")];
+        assert_eq!(tax_due(dec("0.3"), &earlier, dec("-60"), dec("0")), (dec("40"), dec("0")));
+    }
+    
+    #[test]
+    fn tax_a_payout_could_not_cover_is_caught_up_as_far_as_the_next_payout_covers() {
+        let earlier = [earlier("100", "10")];
+        assert_eq!(tax_due(dec("0.3"), &earlier, dec("10"), dec("15")), (dec("110"), dec("15")));
+    }
+    
+    #[test]
+    fn tax_is_rounded_to_the_paisa() {
+        assert_eq!(tax_due(dec("0.3"), &[], dec("0.05"), dec("1")), (dec("0.05"), dec("0.02")));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use log::{error, info};
+use rust_decimal::Decimal;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::withdrawal::{Withdrawal, WithdrawalStatus};
+use crate::services::balance_service::BalanceService;
+use crate::services::payout_provider::PayoutProvider;
+use crate::db::connection::Repository;
+
+/// Service for requesting, reviewing and paying out withdrawals
+///
+/// A request holds the funds out of the user's available balance until an admin
+/// reviews it. Approving pays the held funds out through the payout provider;
+/// rejecting returns them to the user.
+pub struct WithdrawalService<R: Repository> {
+    /// Repository for database operations
+    repository: Arc<R>,
+    
+    /// Service holding, paying out and returning the funds
+    balance_service: Arc<BalanceService<R>>,
+    
+    /// Provider sending approved withdrawals to their destination
+    payout_provider: Arc<dyn PayoutProvider>,
+    
+    /// Most a user can ask to withdraw in any 24 hours
+    daily_limit: Decimal,
+    
+    /// Held while a request is made or reviewed, so limits and reviews see each other's changes
+    lock: Mutex<()>,
+}
+
+impl<R: Repository> WithdrawalService<R> {
+    /// Creates a new withdrawal service
+    pub fn new(
+        repository: Arc<R>,
+        balance_service: Arc<BalanceService<R>>,
+        payout_provider: Arc<dyn PayoutProvider>,
+        daily_limit: Decimal,
+    ) -> Self {
+        Self {
+            repository,
+            balance_service,
+            payout_provider,
+            daily_limit,
+            lock: Mutex::new(()),
+        }
+    }
+    
+    /// Requests a withdrawal, holding the funds until it is reviewed
+    pub async fn request_withdrawal(&self, user_id: Uuid, amount: Decimal, destination: String) -> Result<Withdrawal> {
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("Amount must be positive"));
+        }
+        if destination.trim().is_empty() {
+            return Err(anyhow!("A destination is required"));
+        }
+        
+        let _lock = self.lock.lock().await;
+        
+        // Requests in the last 24 hours count against the limit unless they were rejected
+        let withdrawn = self.repository.get_withdrawn_since(user_id, Utc::now() - Duration::hours(24)).await?;
+        if withdrawn + amount > self.daily_limit {
+            return Err(anyhow!(
+                "Withdrawal of {} would exceed the daily limit of {}: {} already requested in the last 24 hours",
+                amount, self.daily_limit, withdrawn
+            ));
+        }
+        
+        let withdrawal = Withdrawal::new(user_id, amount, destination);
+        self.balance_service.hold_withdrawal(user_id, amount, withdrawal.withdrawal_id).await?;
+        
+        if let Err(e) = self.repository.save_withdrawal(&withdrawal).await {
+            // If saving fails, return the held funds
+            if let Err(e) = self.balance_service.return_withdrawal(user_id, amount, withdrawal.withdrawal_id).await {
+                error!("Failed to return funds held for unsaved withdrawal {}: {}", withdrawal.withdrawal_id, e);
+            }
+            return Err(anyhow!("Failed to save withdrawal: {}", e));
+        }
+        
+        info!("User {} requested withdrawal {} of {}", user_id, withdrawal.withdrawal_id, amount);
+        Ok(withdrawal)
+    }
+    
+    /// Approves a pending withdrawal and pays it out
+    ///
+    /// If the payout provider fails the withdrawal stays pending, so it can be approved again.
+    pub async fn approve_withdrawal(&self, withdrawal_id: Uuid, review_note: Option<String>) -> Result<Withdrawal> {
+        let _lock = self.lock.lock().await;
+        let mut withdrawal = self.get_pending_withdrawal(withdrawal_id).await?;
+        
+        let payout_reference = self.payout_provider.send_payout(&withdrawal).await
+            .map_err(|e| anyhow!("Payout of withdrawal {} failed: {}", withdrawal_id, e))?;
+        self.balance_service.pay_withdrawal(withdrawal.user_id, withdrawal.amount, withdrawal_id).await?;
+        
+        withdrawal.approve(payout_reference, review_note);
+        self.save_review(&withdrawal).await?;
+        
+        info!("Approved withdrawal {} of {} for user {}", withdrawal_id, withdrawal.amount, withdrawal.user_id);
+        Ok(withdrawal)
+    }
+    
+    /// Rejects a pending withdrawal and returns the held funds to the user
+    pub async fn reject_withdrawal(&self, withdrawal_id: Uuid, review_note: Option<String>) -> Result<Withdrawal> {
+        let _lock = self.lock.lock().await;
+        let mut withdrawal = self.get_pending_withdrawal(withdrawal_id).await?;
+        
+        self.balance_service.return_withdrawal(withdrawal.user_id, withdrawal.amount, withdrawal_id).await?;
+        
+        withdrawal.reject(review_note);
+        self.save_review(&withdrawal).await?;
+        
+        info!("Rejected withdrawal {} of {} for user {}", withdrawal_id, withdrawal.amount, withdrawal.user_id);
+        Ok(withdrawal)
+    }
+    
+    /// Gets a withdrawal by ID
+    pub async fn get_withdrawal(&self, withdrawal_id: Uuid) -> Result<Withdrawal> {
+        self.repository.get_withdrawal(withdrawal_id).await
+    }
+    
+    /// Gets a user's withdrawals, newest first
+    pub async fn get_withdrawals_for_user(&self, user_id: Uuid) -> Result<Vec<Withdrawal>> {
+        self.repository.get_withdrawals(Some(user_id), None).await
+    }
+    
+    /// Gets every withdrawal, or only those with a status, newest first
+    pub async fn get_withdrawals(&self, status: Option<WithdrawalStatus>) -> Result<Vec<Withdrawal>> {
+        self.repository.get_withdrawals(None, status).await
+    }
+    
+    /// Gets a withdrawal that is still waiting for review
+    async fn get_pending_withdrawal(&self, withdrawal_id: Uuid) -> Result<Withdrawal> {
+        let withdrawal = self.repository.get_withdrawal(withdrawal_id).await?;
+        if !withdrawal.is_pending() {
+            return Err(anyhow!("Withdrawal {} is already {:?}", withdrawal_id, withdrawal.status));
+        }
+        Ok(withdrawal)
+    }
+    
+    /// Saves the outcome of a review
+    async fn save_review(&self, withdrawal: &Withdrawal) -> Result<()> {
+        if !self.repository.update_withdrawal(withdrawal, WithdrawalStatus::Pending).await? {
+            return Err(anyhow!("Withdrawal {} was reviewed by someone else", withdrawal.withdrawal_id));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Concurrent balance updates against a real database
+//!
+//! These tests need a migrated PostgreSQL database in `DATABASE_URL` and are
+//! skipped when it is not set.
+
+use std::env;
+use std::sync::Arc;
+
+use prediction_engine::{BalanceService, DepositService, LedgerAccount, MockPaymentProvider, Repository, SqlxRepository};
+use prediction_engine::models::DepositStatus;
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+use uuid::Uuid;
+
+/// Number of updates raced against the same balance
+const CONCURRENT_UPDATES: usize = 20;
+
+async fn setup() -> Option<(Arc<SqlxRepository>, Arc<BalanceService<SqlxRepository>>)> {
+    let Ok(database_url) = env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set, skipping");
+        return None;
+    };
+    
+    let pool = PgPoolOptions::new()
+        .max_connections(10)
+        .connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let repository = Arc::new(SqlxRepository::new(pool));
+    let balance_service = Arc::new(BalanceService::new(repository.clone()));
+    Some((repository, balance_service))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_deposits_are_not_lost() {
+    let Some((repository, balance_service)) = setup().await else { return };
+    let user_id = Uuid::new_v4();
+    
+    let deposits: Vec<_> = (0..CONCURRENT_UPDATES)
+        .map(|_| {
+            let balance_service = balance_service.clone();
+            tokio::spawn(async move { balance_service.add_funds(user_id, Decimal::ONE, None).await })
+        })
+        .collect();
+    for deposit in deposits {
+        deposit.await.unwrap().expect("Deposit failed");
+    }
+    
+    let balance = balance_service.get_user_balance(user_id).await.unwrap();
+    let expected = Decimal::from(CONCURRENT_UPDATES);
+    assert_eq!(balance.available_balance, expected);
+    assert_eq!(balance.version, CONCURRENT_UPDATES as i64 + 1);
+    assert_eq!(repository.get_ledger_balance(&LedgerAccount::user_available(user_id)).await.unwrap(), expected);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_reserves_cannot_overdraw() {
+    let Some((repository, balance_service)) = setup().await else { return };
+    let user_id = Uuid::new_v4();
+    let market_id = format!("balance-test-{}", user_id);
+    let amount = Decimal::from(10);
+    balance_service.add_funds(user_id, Decimal::from(50), None).await.unwrap();
+    
+    let reserves: Vec<_> = (0..CONCURRENT_UPDATES)
+        .map(|_| {
+            let balance_service = balance_service.clone();
+            let market_id = market_id.clone();
+            tokio::spawn(async move {
+                balance_service.reserve_funds(user_id, &market_id, amount, Uuid::new_v4()).await
+            })
+        })
+        .collect();
+    let mut reserved = 0;
+    for reserve in reserves {
+        if reserve.await.unwrap().is_ok() {
+            reserved += 1;
+        }
+    }
+    
+    // Only as many reserves as the funds cover go through, and every one of them is kept
+    assert_eq!(reserved, 5);
+    let balance = balance_service.get_user_balance(user_id).await.unwrap();
+    assert_eq!(balance.available_balance, Decimal::ZERO);
+    assert_eq!(balance.reserved_balance, Decimal::from(50));
+    let collateral = LedgerAccount::market_collateral(&market_id, user_id);
+    assert_eq!(repository.get_ledger_balance(&collateral).await.unwrap(), Decimal::from(50));
+    
+    // Leave the market holding nothing
+    balance_service.release_funds(user_id, &market_id, Decimal::from(50), Uuid::new_v4()).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn repeated_deposit_reference_is_credited_once() {
+    let Some((repository, balance_service)) = setup().await else { return };
+    let user_id = Uuid::new_v4();
+    let reference = format!("deposit-{}", user_id);
+    
+    let deposits: Vec<_> = (0..CONCURRENT_UPDATES)
+        .map(|_| {
+            let balance_service = balance_service.clone();
+            let reference = reference.clone();
+            tokio::spawn(async move { balance_service.add_funds(user_id, Decimal::ONE, Some(&reference)).await })
+        })
+        .collect();
+    for deposit in deposits {
+        deposit.await.unwrap().expect("Deposit failed");
+    }
+    
+    let balance = balance_service.get_user_balance(user_id).await.unwrap();
+    assert_eq!(balance.available_balance, Decimal::ONE);
+    assert_eq!(repository.get_ledger_balance(&LedgerAccount::user_available(user_id)).await.unwrap(), Decimal::ONE);
+    
+    // The same reference cannot be reused for a different amount
+    assert!(balance_service.add_funds(user_id, Decimal::TWO, Some(&reference)).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn repeated_deposit_webhook_is_credited_once() {
+    let Some((repository, balance_service)) = setup().await else { return };
+    let provider = Arc::new(MockPaymentProvider::new("test-secret".to_string()));
+    let deposit_service = Arc::new(DepositService::new(repository.clone(), balance_service.clone(), provider.clone()));
+    let user_id = Uuid::new_v4();
+    
+    let deposit = deposit_service.create_deposit(user_id, Decimal::TEN).await.unwrap();
+    let body = serde_json::json!({
+        "deposit_id": deposit.deposit_id,
+        "provider_reference": deposit.provider_reference,
+        "amount": "10",
+        "status": "Completed",
+    }).to_string();
+    
+    // A webhook signed with another secret is refused
+    let forged = MockPaymentProvider::new("wrong-secret".to_string()).sign(body.as_bytes());
+    assert!(deposit_service.handle_callback(body.as_bytes(), &forged).await.is_err());
+    
+    let signature = provider.sign(body.as_bytes());
+    let callbacks: Vec<_> = (0..CONCURRENT_UPDATES)
+        .map(|_| {
+            let deposit_service = deposit_service.clone();
+            let body = body.clone();
+            let signature = signature.clone();
+            tokio::spawn(async move { deposit_service.handle_callback(body.as_bytes(), &signature).await })
+        })
+        .collect();
+    for callback in callbacks {
+        assert_eq!(callback.await.unwrap().expect("Webhook failed").status, DepositStatus::Completed);
+    }
+    
+    let balance = balance_service.get_user_balance(user_id).await.unwrap();
+    assert_eq!(balance.available_balance, Decimal::TEN);
+    assert_eq!(repository.get_ledger_balance(&LedgerAccount::user_available(user_id)).await.unwrap(), Decimal::TEN);
+}
@@ -0,0 +1,170 @@
+//! Settlement and the ledger's invariants against a real database
+//!
+//! These tests need a migrated PostgreSQL database in `DATABASE_URL` and are
+//! skipped when it is not set.
+
+use std::env;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use prediction_engine::{
+    AmmService, BalanceService, LedgerAccount, LedgerEntry, LedgerService, Market, MatchingEngine, Order, OrderService,
+    OrderSide, OutcomeSide, PositionService, Repository, RiskService, SettlementService, SqlxRepository, TaxService,
+    TransactionType,
+};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+
+struct Services {
+    repository: Arc<SqlxRepository>,
+    balance_service: Arc<BalanceService<SqlxRepository>>,
+    order_service: Arc<OrderService<SqlxRepository>>,
+    settlement_service: SettlementService<SqlxRepository>,
+    ledger_service: LedgerService<SqlxRepository>,
+}
+
+async fn setup() -> Option<Services> {
+    let Ok(database_url) = env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set, skipping");
+        return None;
+    };
+    
+    let pool = PgPoolOptions::new()
+        .max_connections(10)
+        .connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let repository = Arc::new(SqlxRepository::new(pool));
+    
+    // Nothing listens for trades, status changes or payouts, so their notifications are dropped
+    let (trade_sender, _) = mpsc::channel(100);
+    let (status_sender, _) = mpsc::channel(100);
+    let (payout_sender, _) = mpsc::channel(100);
+    
+    let balance_service = Arc::new(BalanceService::new(repository.clone()));
+    let position_service = Arc::new(PositionService::new(repository.clone()));
+    let order_service = Arc::new(OrderService::new(
+        repository.clone(),
+        Arc::new(Mutex::new(MatchingEngine::new(trade_sender))),
+        balance_service.clone(),
+        Arc::new(AmmService::new(repository.clone(), balance_service.clone())),
+        position_service.clone(),
+        Arc::new(RiskService::new(repository.clone())),
+        status_sender,
+    ));
+    let tax_service = Arc::new(TaxService::new(repository.clone(), balance_service.clone(), Decimal::new(30, 2)));
+    let settlement_service = SettlementService::new(
+        payout_sender,
+        repository.clone(),
+        balance_service.clone(),
+        order_service.clone(),
+        position_service,
+        tax_service,
+    );
+    let ledger_service = LedgerService::new(repository.clone());
+    Some(Services { repository, balance_service, order_service, settlement_service, ledger_service })
+}
+
+impl Services {
+    /// Opens a market in which `buyer` has bought 10 Yes shares at 0.60 from `seller`,
+    /// who did not hold any and so put up collateral for them
+    async fn open_traded_market(&self, buyer: Uuid, seller: Uuid) -> String {
+        let market_id = format!("settlement-test-{}", Uuid::new_v4());
+        let market = Market::new(market_id.clone(), "Test market".to_string(), String::new(), Some(Utc::now() + Duration::days(1)));
+        self.order_service.create_market(market).await.unwrap();
+        
+        for user_id in [buyer, seller] {
+            self.balance_service.add_funds(user_id, Decimal::from(100), None).await.unwrap();
+        }
+        let price = Decimal::new(60, 2);
+        self.order_service.submit_order(Order::new(buyer, market_id.clone(), OrderSide::Buy, OutcomeSide::YES, price, 10)).await.unwrap();
+        let result = self.order_service
+            .submit_order(Order::new(seller, market_id.clone(), OrderSide::Sell, OutcomeSide::YES, price, 10))
+            .await
+            .unwrap();
+        assert_eq!(result.trades.len(), 1, "The orders should have matched");
+        
+        market_id
+    }
+    
+    async fn available_balance(&self, user_id: Uuid) -> Decimal {
+        self.balance_service.get_user_balance(user_id).await.unwrap().available_balance
+    }
+    
+    /// Gets what the market's accounts hold in total
+    async fn held_by_market(&self, market_id: &str) -> Decimal {
+        self.repository.get_market_ledger_balances(market_id).await.unwrap().iter().map(|b| b.balance).sum()
+    }
+    
+    /// Gets the ledger check's violations that concern these users or market
+    ///
+    /// Other tests share the database, so violations they leave are ignored.
+    async fn violations(&self, market_id: &str, user_ids: &[Uuid]) -> Vec<String> {
+        let check = self.ledger_service.check_invariants().await.unwrap();
+        check.violations
+            .into_iter()
+            .filter(|violation| {
+                violation.contains(market_id) || user_ids.iter().any(|user_id| violation.contains(&user_id.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn resolving_a_market_pays_winners_out_of_escrow_and_leaves_it_empty() {
+    let Some(services) = setup().await else { return };
+    let (buyer, seller) = (Uuid::new_v4(), Uuid::new_v4());
+    let market_id = services.open_traded_market(buyer, seller).await;
+    
+    // The market holds a full unit for each share: the buyer's 0.60 and the seller's 0.40
+    assert_eq!(services.held_by_market(&market_id).await, Decimal::from(10));
+    assert_eq!(services.violations(&market_id, &[buyer, seller]).await, Vec::<String>::new());
+    
+    services.settlement_service.close_market(&market_id).await.unwrap();
+    services.settlement_service.resolve_market(&market_id, OutcomeSide::YES).await.unwrap();
+    
+    // The buyer is paid 10 for shares that cost 6, less 30% tax on the 4 won
+    assert_eq!(services.available_balance(buyer).await, Decimal::new(10280, 2));
+    assert_eq!(services.available_balance(seller).await, Decimal::from(96));
+    assert_eq!(services.repository.get_ledger_balance(&LedgerAccount::market_escrow(&market_id)).await.unwrap(), Decimal::ZERO);
+    assert_eq!(services.held_by_market(&market_id).await, Decimal::ZERO);
+    assert_eq!(services.violations(&market_id, &[buyer, seller]).await, Vec::<String>::new());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn cancelling_a_market_refunds_everyone_and_leaves_it_empty() {
+    let Some(services) = setup().await else { return };
+    let (buyer, seller) = (Uuid::new_v4(), Uuid::new_v4());
+    let market_id = services.open_traded_market(buyer, seller).await;
+    
+    services.settlement_service.cancel_market(&market_id).await.unwrap();
+    
+    assert_eq!(services.available_balance(buyer).await, Decimal::from(100));
+    assert_eq!(services.available_balance(seller).await, Decimal::from(100));
+    assert_eq!(services.held_by_market(&market_id).await, Decimal::ZERO);
+    assert_eq!(services.violations(&market_id, &[buyer, seller]).await, Vec::<String>::new());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn paying_out_of_an_empty_escrow_is_refused() {
+    let Some(services) = setup().await else { return };
+    let user_id = Uuid::new_v4();
+    let market_id = format!("settlement-test-{}", Uuid::new_v4());
+    let escrow = LedgerAccount::market_escrow(&market_id);
+    
+    let entry = LedgerEntry::new(
+        escrow.clone(),
+        LedgerAccount::user_winnings(user_id),
+        Decimal::ONE,
+        TransactionType::SettlementPayout,
+        Some(market_id.clone()),
+        "Payout from an empty escrow".to_string(),
+    );
+    let error = services.repository.post_ledger_entries(&[], &[], &[entry]).await.unwrap_err();
+    
+    assert!(format!("{:#}", error).contains("would be overdrawn"), "Unexpected error: {:#}", error);
+    assert_eq!(services.repository.get_ledger_balance(&escrow).await.unwrap(), Decimal::ZERO);
+    assert_eq!(services.repository.get_ledger_balance(&LedgerAccount::user_winnings(user_id)).await.unwrap(), Decimal::ZERO);
+}